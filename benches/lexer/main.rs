@@ -0,0 +1,72 @@
+#[macro_use]
+extern crate criterion;
+
+// The regex lexer this crate had before the scanner, as the baseline. It is the
+// file as it was but for its imports and the changes the token types made since
+// force on it, so its lints are left alone.
+#[allow(clippy::all)]
+mod regex_lexer;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use regex_lexer::Lexer as RegexLexer;
+use rexparser::lexer::lexer::Lexer;
+use rexparser::lexer::token::Token;
+
+/// The location in the panics of the regex lexer, which predates the source map.
+trait GetDebugInfo {
+    fn get_debug_info(self) -> String;
+}
+impl GetDebugInfo for Token {
+    fn get_debug_info(self) -> String {
+        format!("byte {}", self.debug_info().start)
+    }
+}
+
+/// A source file of `functions` functions, about 12 lines each.
+fn generate_source(functions: usize) -> String {
+    let mut code = String::new();
+    for i in 0..functions {
+        code.push_str(&format!(
+            "/* function {i} */\n\
+             int function_{i}(int x, float y) {{\n\
+             \x20   int total = 0x{i:x} + {i}u;\n\
+             \x20   float ratio = y * 1.5e-3f; // scale\n\
+             \x20   char *label = \"label {i}\\n\";\n\
+             \x20   for (int j = 0; j < x; ++j) {{\n\
+             \x20       if (j == 'a' || total > 100) {{\n\
+             \x20           total += j;\n\
+             \x20       }}\n\
+             \x20   }}\n\
+             \x20   return total;\n\
+             }}\n\n",
+            i = i
+        ));
+    }
+    code
+}
+
+fn lexer_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+    group.sample_size(10);
+    for functions in [100, 1000, 5000].iter() {
+        let code = generate_source(*functions);
+        group.throughput(Throughput::Bytes(code.len() as u64));
+        let lexer = Lexer::new();
+        group.bench_with_input(BenchmarkId::new("scanner", functions), &code, |b, code| {
+            b.iter(|| lexer.lex(code.clone()))
+        });
+        // the regex lexer is quadratic and already takes seconds on the smallest
+        // source, so it only gets that one
+        if *functions > 100 {
+            continue;
+        }
+        let regex_lexer = RegexLexer::new();
+        group.bench_with_input(BenchmarkId::new("regex", functions), &code, |b, code| {
+            b.iter(|| regex_lexer.lex(code.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lexer_benchmark);
+criterion_main!(benches);
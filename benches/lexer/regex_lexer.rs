@@ -0,0 +1,306 @@
+use crate::GetDebugInfo;
+use log::debug;
+use regex::Regex;
+use rexparser::lexer::literal::*;
+use rexparser::lexer::token::{DebugInfo, Token, Tokens};
+use rexparser::source_map::FileId;
+
+pub struct Lexer {
+    re: Regex,
+    names: Vec<&'static str>,
+    /// Keep comments as trivia in the `DebugInfo` of the token that follows them,
+    /// instead of discarding them. Comments after the last token are dropped.
+    pub keep_comments: bool,
+}
+
+impl Lexer {
+    // static constructor
+    pub fn new() -> Lexer {
+        let token_patterns = vec![
+            ("COMMENT", r"//(\\\n|[^\n])*|/\*(?s:.*?)\*/"),
+            ("UNTERMINATED_COMMENT", r"/\*"),
+            ("PRAGMA", r"#[ \t]*pragma\b[^\n]*"),
+            ("COLON", r":"),
+            ("QUESTION", r"\?"),
+            (
+                "FLOAT_NUM",
+                r"0[xX]([0-9a-fA-F]*\.[0-9a-fA-F]+|[0-9a-fA-F]+\.?)[pP][+-]?\d+[fFlL]?|(\d+\.\d*|\.\d+)([eE][+-]?\d+)?[fFlL]?|\d+[eE][+-]?\d+[fFlL]?",
+            ),
+            (
+                "INT_NUM",
+                r"(0[xX][0-9a-fA-F]+|0[bB][01]+|\d+)([uU](ll|LL|l|L)?|(ll|LL|l|L)[uU]?)?",
+            ),
+            ("SEMI", r";"),
+            ("VA", r"\.\.\."),
+            ("STR", r#""(\\(.|\n)|[^"\\\n])*""#),
+            ("UNTERMINATED_STR", r#"""#),
+            ("SQUARE_E", r"\]"),
+            ("PAREN_E", r"\)"),
+            ("CURLY_S", r"\{"),
+            ("CURLY_E", r"\}"),
+            ("ANDOP", r"(&&)"),
+            ("PREFIXOP", r"((\s|^)\+\+)|&"),
+            ("SUFFIXOP", r"(\+\+|\[|\()|\."),
+            ("OP", r"((\|\|)|(\+=)|(==)|>|<|\+|-|\*|=|,)"),
+            ("CHAR", r"'(\\(.|\n)|[^'\\\n])*'"),
+            ("UNTERMINATED_CHAR", r"'"),
+            ("IDE", r"[A-Za-z_][A-Za-z_0-9]*"),
+        ];
+        let re = make_regex(&token_patterns);
+        let names = get_names(&token_patterns);
+        let re = Regex::new(&re).expect("something went wrong making the regex");
+        Lexer {
+            re,
+            names,
+            keep_comments: false,
+        }
+    }
+
+    pub fn lex(&self, code: String) -> Tokens {
+        self.lex_mapped(code, &|_| ())
+    }
+    /// Lex code whose locations are not the source ones, such as preprocessed code.
+    /// `map` rewrites the location of every token as soon as it is read, so that
+    /// errors found while lexing point into the source too.
+    pub fn lex_mapped(&self, code: String, map: &dyn Fn(&mut DebugInfo)) -> Tokens {
+        let mut code = code;
+        let tokens = self.tokenize(&mut code, map);
+        tokens
+    }
+    fn tokenize(&self, code: &mut String, map: &dyn Fn(&mut DebugInfo)) -> Tokens {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut comments: Vec<DebugInfo> = Vec::new();
+
+        // get token's location
+        let mut locations: Vec<DebugInfo> = Vec::new();
+        for mat in self.re.find_iter(&code) {
+            let mut location = DebugInfo {
+                file: FileId::default(),
+                start: mat.start(),
+                end: mat.end(),
+                s: mat.as_str().to_string(),
+                presumed: None,
+                comments: Vec::new(),
+            };
+            map(&mut location);
+            locations.push(location);
+        }
+        for (i, caps) in self.re.captures_iter(&code).enumerate() {
+            let mut typ = String::from("nil");
+            let val = String::from(&caps[0]);
+            for name in &self.names {
+                if caps.name(name).is_some() {
+                    typ = name.to_string();
+                }
+            }
+            let mut debug_info = locations.clone().remove(i);
+            match typ.as_ref() {
+                "COMMENT" => {
+                    if self.keep_comments {
+                        comments.push(debug_info);
+                    }
+                    continue;
+                }
+                "UNTERMINATED_COMMENT" => panic!(
+                    "unterminated comment at {}",
+                    Token::Op(val, debug_info).get_debug_info()
+                ),
+                _ => debug_info.comments = comments.drain(..).collect(),
+            }
+            match typ.as_ref() {
+                "PRAGMA" => {
+                    let val = val.trim_start_matches(|c| c == '#' || c == ' ' || c == '\t');
+                    let val = val["pragma".len()..].trim().to_string();
+                    tokens.push(Token::Pragma(val, debug_info));
+                }
+                "COLON" => tokens.push(Token::Colon(debug_info)),
+                "QUESTION" => tokens.push(Token::Question(debug_info)),
+                "FLOAT_NUM" => {
+                    if let Err(message) = parse_float_literal(&val) {
+                        panic!(
+                            "{} at {}",
+                            message,
+                            Token::FloatNum(val, debug_info).get_debug_info()
+                        );
+                    }
+                    tokens.push(Token::FloatNum(val, debug_info))
+                }
+                "INT_NUM" => {
+                    if let Err(message) = parse_int_literal(&val) {
+                        panic!(
+                            "{} at {}",
+                            message,
+                            Token::IntNum(val, debug_info).get_debug_info()
+                        );
+                    }
+                    tokens.push(Token::IntNum(val, debug_info))
+                }
+                "SEMI" => tokens.push(Token::Semi(debug_info)),
+                "VA" => tokens.push(Token::Va(debug_info)),
+                "STR" => {
+                    let string = match parse_string_literal(&val) {
+                        Ok(string) => string,
+                        Err(message) => panic!(
+                            "{} at {}",
+                            message,
+                            Token::Str(val.into_bytes(), debug_info).get_debug_info()
+                        ),
+                    };
+                    // adjacent string literals are concatenated
+                    if let Some(Token::Str(previous, previous_info)) = tokens.last_mut() {
+                        previous.extend(string);
+                        previous_info.end = debug_info.end;
+                        previous_info.s.push(' ');
+                        previous_info.s.push_str(&debug_info.s);
+                        continue;
+                    }
+                    tokens.push(Token::Str(string, debug_info));
+                }
+                "UNTERMINATED_STR" => panic!(
+                    "missing terminating \" character at {}",
+                    Token::Op(val, debug_info).get_debug_info()
+                ),
+                "UNTERMINATED_CHAR" => panic!(
+                    "missing terminating ' character at {}",
+                    Token::Op(val, debug_info).get_debug_info()
+                ),
+                "SQUARE_E" => tokens.push(Token::SquareE(debug_info)),
+                "PAREN_E" => tokens.push(Token::ParenE(debug_info)),
+                "CURLY_S" => tokens.push(Token::CurlyS(debug_info)),
+                "CURLY_E" => tokens.push(Token::CurlyE(debug_info)),
+                "ANDOP" => {
+                    let val = val.trim_end().to_string();
+                    tokens.push(Token::Op(val.clone(), debug_info))
+                }
+                "PREFIXOP" => {
+                    let val = val.trim_start().to_string();
+                    tokens.push(Token::PrefixOp(val, debug_info));
+                }
+                "SUFFIXOP" => {
+                    let val = val.trim_end().to_string();
+                    tokens.push(Token::SuffixOp(val, debug_info));
+                }
+                "OP" => {
+                    let val = val.trim_end().to_string();
+                    tokens.push(Token::Op(val.clone(), debug_info))
+                }
+                "IDE" => tokens.push(keyword_or_identifier(val, debug_info)),
+                "CHAR" => match parse_char_literal(&val) {
+                    Ok(num) => tokens.push(Token::IntNum(num.to_string(), debug_info)),
+                    Err(message) => panic!(
+                        "{} at {}",
+                        message,
+                        Token::IntNum(val, debug_info).get_debug_info()
+                    ),
+                },
+                _ => panic!("This is not an expected panic"),
+            }
+        }
+        debug!("tokens:  {:?}", tokens);
+        Tokens::new(tokens)
+    }
+}
+/// Every keyword of C11. A word is a keyword only when the whole identifier matches.
+pub const KEYWORDS: [&str; 44] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_Bool",
+    "_Complex",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+];
+
+/// Type specifiers are looked up by name like any other type, so they stay identifiers.
+const TYPE_SPECIFIERS: [&str; 12] = [
+    "char",
+    "double",
+    "float",
+    "int",
+    "long",
+    "short",
+    "signed",
+    "unsigned",
+    "void",
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word)
+}
+
+fn keyword_or_identifier(word: String, debug_info: DebugInfo) -> Token {
+    match word.as_ref() {
+        "struct" => Token::Struct(debug_info),
+        "enum" => Token::Enum(debug_info),
+        "switch" => Token::Switch(debug_info),
+        "extern" => Token::Extern(debug_info),
+        "case" => Token::Case(debug_info),
+        "default" => Token::Default(debug_info),
+        "return" => Token::Return(debug_info),
+        "if" => Token::If(debug_info),
+        "else" => Token::Else(debug_info),
+        "while" => Token::While(debug_info),
+        "break" => Token::Break(debug_info),
+        "continue" => Token::Continue(debug_info),
+        "for" => Token::For(debug_info),
+        _ if is_keyword(&word) && !TYPE_SPECIFIERS.contains(&word.as_ref()) => {
+            Token::Keyword(word, debug_info)
+        }
+        _ => Token::Ide(word, debug_info),
+    }
+}
+
+fn make_regex(token_patterns: &Vec<(&str, &str)>) -> String {
+    token_patterns
+        .into_iter()
+        .map(|pattern| format!("(?P<{}>{})", pattern.0, pattern.1))
+        .collect::<Vec<String>>()
+        .join("|")
+}
+
+fn get_names<'a, 'b>(token_patterns: &Vec<(&'a str, &'b str)>) -> Vec<&'a str> {
+    token_patterns
+        .into_iter()
+        .map(|pattern| pattern.0)
+        .collect()
+}
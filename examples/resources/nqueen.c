@@ -6,9 +6,9 @@ int print_board(int board[8][8]) {
                 putchar('Q');
             else
                 putchar('.');
-        putchar(10);
+        putchar('\n');
     }
-    putchar(10);
+    putchar('\n');
     return 0;
 }
 
@@ -42,10 +42,7 @@ int solve(int board[8][8], int row) {
 }
 
 int main() {
-    int board[8][8];
-    for (int i = 0; i < 8; i++)
-        for (int j = 0; j < 8; j++)
-            board[i][j] = 0;
+    int board[8][8] = {0};
     solve(board, 0);
     return 3;
 }
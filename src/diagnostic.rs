@@ -0,0 +1,141 @@
+use crate::lexer::token::{DebugInfo, PresumedLocation};
+use crate::source_map::{FileId, SourceMap};
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A byte range of a file in the `SourceMap`.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+    /// File name and line number at `start` as seen through `#line` directives.
+    pub presumed: Option<PresumedLocation>,
+}
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        let mut span = self.clone();
+        if other.file == self.file {
+            span.end = span.end.max(other.end);
+        }
+        span
+    }
+}
+impl From<&DebugInfo> for Span {
+    fn from(debug_info: &DebugInfo) -> Span {
+        Span {
+            file: debug_info.file,
+            start: debug_info.start,
+            end: debug_info.end,
+            presumed: debug_info.presumed.clone(),
+        }
+    }
+}
+
+/// An error or warning found while compiling, reported to the user instead of
+/// stopping the compiler with a panic.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Where the problem is. Some errors, such as a missing `main`, have no location.
+    pub span: Option<Span>,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+impl Diagnostic {
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Diagnostic {
+        Diagnostic {
+            severity,
+            span: None,
+            message: message.into(),
+            notes: Vec::new(),
+        }
+    }
+    pub fn error<S: Into<String>>(message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+    pub fn warning<S: Into<String>>(message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+    pub fn at(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+    /// Locate the diagnostic at `span` unless it already has a location.
+    pub fn or_at(mut self, span: Span) -> Diagnostic {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+    /// `file:line:column: severity: message`, followed by the source line with the
+    /// span underlined and by the notes.
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        let mut text = String::new();
+        let file = self
+            .span
+            .as_ref()
+            .and_then(|span| source_map.file(span.file).map(|file| (span, file)));
+        match file {
+            Some((span, file)) => {
+                let (line, column) = file.line_column(span.start);
+                let (name, presumed_line) = match &span.presumed {
+                    Some(presumed) => (presumed.file.as_str(), presumed.line),
+                    None => (file.name.as_str(), line),
+                };
+                text.push_str(&format!(
+                    "{}:{}:{}: {}: {}\n",
+                    name, presumed_line, column, self.severity, self.message
+                ));
+                let line_text = file.line_text(line);
+                let (_, end_column) = file.line_column(span.end);
+                let width = if file.line_column(span.end).0 == line {
+                    end_column.saturating_sub(column).max(1)
+                } else {
+                    (line_text.chars().count() + 1)
+                        .saturating_sub(column)
+                        .max(1)
+                };
+                // tabs are kept so that the caret lines up with the source line
+                let indent: String = line_text
+                    .chars()
+                    .take(column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                text.push_str(&format!(" {}\n", line_text));
+                text.push_str(&format!(" {}^{}\n", indent, "~".repeat(width - 1)));
+            }
+            None => text.push_str(&format!("{}: {}\n", self.severity, self.message)),
+        }
+        for note in &self.notes {
+            text.push_str(&format!("note: {}\n", note));
+        }
+        text
+    }
+}
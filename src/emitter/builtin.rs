@@ -2,10 +2,9 @@ use inkwell::context::Context;
 use inkwell::IntPredicate;
 
 use crate::emitter::environment::Environment;
-use crate::emitter::Emitter;
-use std::path;
+use crate::emitter::{print_module, EmitResult, Emitter};
 
-pub fn emit_builtin() {
+pub fn emit_builtin() -> EmitResult<()> {
     // initialize
     let context = Context::create();
     let module = context.create_module("builtin_module");
@@ -26,7 +25,7 @@ pub fn emit_builtin() {
     emit_or_int(&mut emitter);
 
     // output llvm-ir
-    let _ = emitter.module.print_to_file(path::Path::new("builtin.ll"));
+    print_module(&emitter.module, "builtin.ll")
 }
 
 fn emit_comp_int(emitter: &mut Emitter, function_name: &str, operator: IntPredicate) {
@@ -1,23 +1,57 @@
+use inkwell::types::BasicTypeEnum;
 use inkwell::values::BasicValueEnum;
 
+use crate::diagnostic::{Diagnostic, Span};
+use crate::emitter::util::*;
 use crate::emitter::*;
 use crate::lexer::token::*;
+use crate::parser::ctype::CType;
 use crate::parser::expression::*;
 
-pub fn emit_const_expression(emitter: &mut Emitter, node: ExpressionNode) -> BasicValueEnum {
+/// A constant and its C type.
+pub fn emit_const_expression(
+    emitter: &mut Emitter,
+    node: ExpressionNode,
+) -> EmitResult<(BasicValueEnum, CType)> {
     match node {
         ExpressionNode::Token(node) => emit_const_token(emitter, node),
-        _ => panic!(format!("TODO {:?}", node)),
+        _ => Err(unsupported("a global initializer other than a constant")),
     }
 }
 
-fn emit_const_token(emitter: &mut Emitter, node: TokenNode) -> BasicValueEnum {
-    let default_int_type = emitter.context.i32_type();
-    let default_float_type = emitter.context.f32_type();
+/// Convert a constant of the C type `from` to the type of the variable it
+/// initializes.
+pub fn cast_const_value(
+    value: BasicValueEnum,
+    from: &CType,
+    value_type: BasicTypeEnum,
+) -> BasicValueEnum {
+    match (value, value_type) {
+        (BasicValueEnum::IntValue(value), BasicTypeEnum::IntType(int_type)) => {
+            value.const_cast(int_type, !is_unsigned(from)).into()
+        }
+        (BasicValueEnum::FloatValue(value), BasicTypeEnum::FloatType(float_type)) => {
+            value.const_cast(float_type).into()
+        }
+        _ => value,
+    }
+}
+
+fn emit_const_token(emitter: &mut Emitter, node: TokenNode) -> EmitResult<(BasicValueEnum, CType)> {
     match node.token {
-        Token::IntNum(val, _) => default_int_type.const_int_from_string(&val, 10).into(),
-        Token::FloatNum(val, _) => default_float_type.const_float_from_string(&val).into(),
-        Token::Ide(_, _) => panic!(format!("{:?} is not a compile-time constant", node)),
-        _ => panic!("TODO"),
+        Token::IntNum(val, _) => {
+            let (value, ctype) = const_int_literal(emitter, &val);
+            Ok((value.into(), ctype))
+        }
+        Token::FloatNum(val, _) => {
+            let (value, ctype) = const_float_literal(emitter, &val);
+            Ok((value.into(), ctype))
+        }
+        Token::Ide(val, debug_info) => Err(Diagnostic::error(format!(
+            "\"{}\" is not a compile-time constant",
+            val
+        ))
+        .at(Span::from(&debug_info))),
+        token => Err(unsupported("a global initializer other than a number").at(token.span())),
     }
 }
@@ -1,13 +1,19 @@
+use inkwell::context::Context;
 use inkwell::types::{BasicTypeEnum, StructType};
 use inkwell::values::{GlobalValue, PointerValue};
+use inkwell::AddressSpace;
 use std::collections::HashMap;
 
+use crate::diagnostic::Diagnostic;
 use crate::emitter::util::*;
-use crate::emitter::Emitter;
+use crate::emitter::EmitResult;
+use crate::parser::ctype::CType;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Struct {
     pub names: Vec<String>,
+    /// The types of the members, with the typedefs in them resolved.
+    pub types: Vec<CType>,
     pub struct_type: StructType,
 }
 
@@ -26,15 +32,12 @@ impl Environment {
     pub fn pop_scope(&mut self) {
         self.scopes.pop();
     }
-    pub fn insert_new_other(&mut self, key: String, value: Other) -> Option<Other> {
-        if let Some(scope) = self.scopes.last_mut() {
-            if scope.other_stack.contains_key(&key) {
-                panic!(format!("redefinition of {}", key))
-            }
-            scope.other_stack.insert(key, value)
-        } else {
-            panic!()
+    pub fn insert_new_other(&mut self, key: String, value: Other) -> EmitResult<Option<Other>> {
+        let scope = self.scopes.last_mut().expect("a scope to declare in");
+        if scope.other_stack.contains_key(&key) {
+            return Err(Diagnostic::error(format!("redefinition of \"{}\"", key)));
         }
+        Ok(scope.other_stack.insert(key, value))
     }
     pub fn get_other(&self, key: &str) -> Option<Other> {
         let scopes = self.scopes.clone();
@@ -45,15 +48,19 @@ impl Environment {
         }
         None
     }
-    pub fn insert_new_tag(&mut self, key: String, value: Tag) -> Option<Tag> {
-        if let Some(scope) = self.scopes.last_mut() {
-            if scope.tag_stack.contains_key(&key) {
-                panic!(format!("redefinition of {}", key))
-            }
-            scope.tag_stack.insert(key, value)
-        } else {
-            panic!()
+    /// The declaration of `key` at file scope, which a local one may hide.
+    pub fn get_global(&self, key: &str) -> Option<Other> {
+        self.scopes
+            .first()
+            .and_then(|scope| scope.other_stack.get(key))
+            .cloned()
+    }
+    pub fn insert_new_tag(&mut self, key: String, value: Tag) -> EmitResult<Option<Tag>> {
+        let scope = self.scopes.last_mut().expect("a scope to declare in");
+        if scope.tag_stack.contains_key(&key) {
+            return Err(Diagnostic::error(format!("redefinition of \"{}\"", key)));
         }
+        Ok(scope.tag_stack.insert(key, value))
     }
     pub fn get_tag(&self, key: &str) -> Option<Tag> {
         let scopes = self.scopes.clone();
@@ -64,38 +71,95 @@ impl Environment {
         }
         None
     }
-    pub fn get_type_from_string(&self, type_string: &str) -> BasicTypeEnum {
-        // parse [] (ex. int[3][2])
-        let mut vec: Vec<&str> = type_string
-            .split(|c: char| c == '[' || c == ']')
-            .filter(|v| !v.is_empty())
-            .rev()
-            .collect();
-        let type_string: &str = vec.pop().unwrap();
-
-        // parse * (ex. int*, float**,...)
-        let type_string_split: Vec<&str> = type_string.split("*").collect();
-        let pointer_depth = type_string_split.len() - 1;
-        let type_string = type_string_split
-            .get(0)
-            .expect("expect at least one element");
-
-        let mut basic_type = match self.get_other(type_string) {
-            Some(other) => match other {
-                Other::Type(t) => t,
-                _ => panic!(format!("{} is not a type", type_string)),
+    /// `ctype` with the typedef names in it replaced by the types they name.
+    pub fn resolve_typedefs(&self, ctype: &CType) -> EmitResult<CType> {
+        let resolve = |ctype: &CType| self.resolve_typedefs(ctype).map(Box::new);
+        Ok(match ctype {
+            CType::Typedef(name) => match self.get_other(name) {
+                Some(Other::Typedef(resolved)) => resolved,
+                _ => return Err(Diagnostic::error(format!("unknown type name \"{}\"", name))),
             },
-            None => panic!(format!("type {} is not exist", type_string)),
-        };
-
-        for val in vec.into_iter().rev() {
-            basic_type = to_array_type(basic_type, val.parse::<u32>().unwrap());
-        }
-
-        for _ in 0..pointer_depth {
-            basic_type = to_pointer_type(basic_type);
+            CType::Pointer(ctype) => CType::Pointer(resolve(ctype)?),
+            CType::Array(ctype, size) => CType::Array(resolve(ctype)?, *size),
+            CType::Function {
+                return_type,
+                parameters,
+                is_var_args,
+            } => CType::Function {
+                return_type: resolve(return_type)?,
+                parameters: parameters
+                    .iter()
+                    .map(|parameter| self.resolve_typedefs(parameter))
+                    .collect::<EmitResult<Vec<CType>>>()?,
+                is_var_args: *is_var_args,
+            },
+            CType::Qualified(ctype, qualifiers) => CType::Qualified(resolve(ctype)?, *qualifiers),
+            ctype => ctype.clone(),
+        })
+    }
+    /// `ctype` without its qualifiers, and the type it names if it is a typedef
+    /// name.
+    pub fn unqualified(&self, ctype: &CType) -> EmitResult<CType> {
+        match ctype {
+            CType::Qualified(ctype, _) => self.unqualified(ctype),
+            CType::Typedef(_) => self.unqualified(&self.resolve_typedefs(ctype)?),
+            ctype => Ok(ctype.clone()),
         }
-        basic_type
+    }
+    pub fn get_type(&self, context: &Context, ctype: &CType) -> EmitResult<BasicTypeEnum> {
+        let basic_type = match self.unqualified(ctype)? {
+            CType::Void => return Err(unsupported("a value of type void")),
+            CType::Int { bits, .. } => BasicTypeEnum::IntType(match bits {
+                1 => context.bool_type(),
+                8 => context.i8_type(),
+                16 => context.i16_type(),
+                32 => context.i32_type(),
+                64 => context.i64_type(),
+                bits => unreachable!("the parser reads no integer of {} bits", bits),
+            }),
+            CType::Float { bits: 32 } => BasicTypeEnum::FloatType(context.f32_type()),
+            // `long double` is lowered as `double`
+            CType::Float { .. } => BasicTypeEnum::FloatType(context.f64_type()),
+            CType::Pointer(ctype) => match self.unqualified(&ctype)? {
+                // as in C, a pointer to void is one to char
+                CType::Void => to_pointer_type(BasicTypeEnum::IntType(context.i8_type()))?,
+                CType::Function {
+                    return_type,
+                    parameters,
+                    is_var_args,
+                } => {
+                    let return_type = self.get_type(context, &return_type)?;
+                    let param_types = parameters
+                        .iter()
+                        .map(|parameter| self.get_type(context, parameter))
+                        .collect::<EmitResult<Vec<BasicTypeEnum>>>()?;
+                    let fn_type = to_fn_type(return_type, param_types, is_var_args)?;
+                    BasicTypeEnum::PointerType(fn_type.ptr_type(AddressSpace::Generic))
+                }
+                ctype => to_pointer_type(self.get_type(context, &ctype)?)?,
+            },
+            CType::Array(ctype, Some(size)) => {
+                to_array_type(self.get_type(context, &ctype)?, size)?
+            }
+            CType::Array(_, None) => return Err(unsupported("an array without a size")),
+            CType::Struct(Some(tag)) => match self.get_tag(&tag) {
+                Some(Tag::Struct(struct_value)) => struct_value.struct_type.into(),
+                None => return Err(Diagnostic::error(format!("unknown struct \"{}\"", tag))),
+            },
+            CType::Struct(None) => {
+                return Err(unsupported("a struct without a tag outside a typedef"))
+            }
+            CType::Union(_) => return Err(unsupported("unions")),
+            // enumerated types are int
+            CType::Enum(_) => BasicTypeEnum::IntType(context.i32_type()),
+            CType::Function { .. } => {
+                return Err(Diagnostic::error("a function cannot be declared here"))
+            }
+            CType::Typedef(_) | CType::Qualified(_, _) => {
+                unreachable!("unqualified resolves typedef names and qualifiers")
+            }
+        };
+        Ok(basic_type)
     }
 }
 
@@ -105,15 +169,8 @@ pub struct Scope {
     pub tag_stack: HashMap<String, Tag>,
 }
 impl Scope {
-    pub fn new(emitter: &mut Emitter) -> Scope {
-        let mut other_stack: HashMap<String, Other> = HashMap::new();
-        let default_int_type = BasicTypeEnum::IntType(emitter.context.i32_type());
-        let default_char_type = BasicTypeEnum::IntType(emitter.context.i8_type());
-        let default_float_type = BasicTypeEnum::FloatType(emitter.context.f32_type());
-        other_stack.insert(String::from("int"), Other::Type(default_int_type));
-        other_stack.insert(String::from("char"), Other::Type(default_char_type));
-        other_stack.insert(String::from("float"), Other::Type(default_float_type));
-
+    pub fn new() -> Scope {
+        let other_stack: HashMap<String, Other> = HashMap::new();
         let tag_stack: HashMap<String, Tag> = HashMap::new();
 
         Scope {
@@ -122,12 +179,22 @@ impl Scope {
         }
     }
 }
+impl Default for Scope {
+    fn default() -> Scope {
+        Scope::new()
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Other {
-    Variable(PointerValue),
-    Type(BasicTypeEnum),
-    Global(GlobalValue),
+    /// A local variable and its type, with the typedefs in it resolved, as in
+    /// all the types below.
+    Variable(PointerValue, CType),
+    Global(GlobalValue, CType),
+    /// The type of a function, which is called through the module.
+    Function(CType),
+    /// The type a typedef names, with the typedefs in it resolved.
+    Typedef(CType),
 }
 
 #[derive(Debug, PartialEq, Clone)]
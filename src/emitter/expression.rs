@@ -1,13 +1,37 @@
+use inkwell::module::Linkage;
 use inkwell::types::{AnyTypeEnum, BasicTypeEnum};
-use inkwell::values::{BasicValueEnum, PointerValue};
+use inkwell::values::{BasicValueEnum, IntValue, PointerValue};
+use inkwell::IntPredicate;
 
+use crate::diagnostic::{Diagnostic, Span};
 use crate::emitter::util::*;
 use crate::emitter::*;
 use crate::lexer::token::*;
+use crate::parser::ctype::CType;
 use crate::parser::expression::*;
 
-pub fn emit_expression(emitter: &mut Emitter, node: ExpressionNode) -> PointerValue {
-    match node {
+/// The result of an expression: the memory its value is in, and its C type with
+/// the typedefs in it resolved, which LLVM does not keep the sign of.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Operand {
+    pub alloca: PointerValue,
+    pub ctype: CType,
+}
+
+/// Store `value` of `ctype` to be the result of an expression.
+fn store_operand(
+    emitter: &mut Emitter,
+    value: BasicValueEnum,
+    ctype: CType,
+) -> EmitResult<Operand> {
+    let alloca = store_value(emitter, value)?;
+    Ok(Operand { alloca, ctype })
+}
+
+/// Errors without a location are reported at the whole expression.
+pub fn emit_expression(emitter: &mut Emitter, node: ExpressionNode) -> EmitResult<Operand> {
+    let span = node.span();
+    let result = match node {
         ExpressionNode::Token(node) => emit_token(emitter, node),
         ExpressionNode::BinExp(node) => emit_bin_exp(emitter, node),
         ExpressionNode::Prefix(node) => emit_prefix(emitter, node),
@@ -16,229 +40,338 @@ pub fn emit_expression(emitter: &mut Emitter, node: ExpressionNode) -> PointerVa
         ExpressionNode::Suffix(node) => emit_suffix(emitter, node),
         ExpressionNode::Access(node) => emit_access(emitter, node),
         ExpressionNode::TernaryExp(node) => emit_ternary_exp(emitter, node),
-        _ => panic!(format!("TODO {:?}", node)),
-    }
+        ExpressionNode::Empty(_) => Err(Diagnostic::error("expected an expression")),
+    };
+    result.map_err(|error| error.or_at(span))
 }
 
-fn emit_token(emitter: &mut Emitter, node: TokenNode) -> PointerValue {
-    let default_int_type = emitter.context.i32_type();
-    let default_float_type = emitter.context.f32_type();
+fn emit_token(emitter: &mut Emitter, node: TokenNode) -> EmitResult<Operand> {
     match node.token {
-        Token::IntNum(val, _) => store_value(
-            emitter,
-            default_int_type.const_int_from_string(&val, 10).into(),
-        ),
-        Token::FloatNum(val, _) => store_value(
-            emitter,
-            default_float_type.const_float_from_string(&val).into(),
-        ),
-        Token::Ide(identifier, _) => match emitter.env.get_other(&identifier) {
-            Some(other) => match other {
-                Other::Variable(alloca) => alloca,
-                Other::Global(alloca) => alloca.as_pointer_value(),
-                _ => panic!(format!("TODO: {:?}", other)),
-            },
-            None => panic!(format!("{} is not exists", identifier)),
+        Token::IntNum(val, _) => {
+            let (value, ctype) = const_int_literal(emitter, &val);
+            store_operand(emitter, value.into(), ctype)
+        }
+        Token::FloatNum(val, _) => {
+            let (value, ctype) = const_float_literal(emitter, &val);
+            store_operand(emitter, value.into(), ctype)
+        }
+        Token::Ide(identifier, debug_info) => match emitter.env.get_other(&identifier) {
+            Some(Other::Variable(alloca, ctype)) => Ok(Operand { alloca, ctype }),
+            Some(Other::Global(global, ctype)) => Ok(Operand {
+                alloca: global.as_pointer_value(),
+                ctype,
+            }),
+            Some(Other::Function(_)) => {
+                Err(unsupported("a function name as a value").at(Span::from(&debug_info)))
+            }
+            Some(Other::Typedef(_)) => Err(Diagnostic::error(format!(
+                "unexpected type name \"{}\" in an expression",
+                identifier
+            ))
+            .at(Span::from(&debug_info))),
+            None => Err(Diagnostic::error(format!(
+                "use of undeclared identifier \"{}\"",
+                identifier
+            ))
+            .at(Span::from(&debug_info))),
         },
         Token::Str(val, _) => {
-            let s = unsafe { emitter.builder.build_global_string(&val, "str") };
-            s.as_pointer_value()
+            // an array of the bytes and a terminating zero, which may follow others
+            let i8_type = emitter.context.i8_type();
+            let bytes: Vec<IntValue> = val
+                .iter()
+                .chain(&[0])
+                .map(|byte| i8_type.const_int(u64::from(*byte), false))
+                .collect();
+            let len = bytes.len() as u32;
+            let s = emitter
+                .module
+                .add_global(i8_type.array_type(len), None, "str");
+            s.set_initializer(&i8_type.const_array(&bytes));
+            s.set_constant(true);
+            s.set_linkage(Linkage::Private);
+            let ctype = CType::Array(
+                Box::new(CType::Int {
+                    bits: 8,
+                    is_signed: true,
+                }),
+                Some(len),
+            );
+            Ok(Operand {
+                alloca: s.as_pointer_value(),
+                ctype,
+            })
         }
-        _ => panic!("TODO"),
+        token => Err(unsupported("this operand").at(token.span())),
     }
 }
 
-fn emit_bin_exp(emitter: &mut Emitter, node: BinExpNode) -> PointerValue {
+fn emit_bin_exp(emitter: &mut Emitter, node: BinExpNode) -> EmitResult<Operand> {
     let (operator, debug_info) = match node.op.token {
         Token::Op(op, debug_info) => (op, debug_info),
-        _ => panic!("expects operator token"),
+        _ => unreachable!("the parser only builds binary expressions on operators"),
     };
+    let span = Span::from(&debug_info);
 
     if is_assign_operator(&operator) {
-        let alloca = emit_expression(emitter, *node.lhs);
-        let value_alloca = emit_expression(emitter, *node.rhs);
+        let target = emit_expression(emitter, *node.lhs)?;
+        let value = emit_expression(emitter, *node.rhs)?;
         match operator.as_ref() {
             "=" => {
-                let value = load_value(emitter, value_alloca);
-                match alloca.get_type().get_element_type() {
-                    AnyTypeEnum::IntType(int_type) => {
-                        let value = cast_to_int(emitter, int_type, value);
-                        emitter.builder.build_store(alloca, value);
-                    }
-                    AnyTypeEnum::FloatType(float_type) => {
-                        let value = cast_to_float(emitter, float_type, value);
-                        emitter.builder.build_store(alloca, value);
-                    }
-                    AnyTypeEnum::PointerType(pointer_type) => match value {
-                        BasicValueEnum::PointerValue(pointer_value) => {
-                            assert!(
-                                pointer_type.get_element_type()
-                                    == pointer_value.get_type().get_element_type()
-                            );
-                            emitter.builder.build_store(alloca, pointer_value);
-                        }
-                        _ => panic!(format!(
-                            "expect pointer value, but {:?} at {:?}",
-                            value, debug_info
-                        )),
-                    },
-                    _ => panic!("TODO"),
-                }
-                alloca
+                let (value, ctype) = load_operand(emitter, &value)?;
+                store_converted(emitter, &target, value, &ctype).map_err(|e| e.or_at(span))?;
+                Ok(target)
             }
             "+=" => {
-                let lhs_value = load_value(emitter, alloca);
-                let rhs_value = load_value(emitter, value_alloca);
-
-                match alloca.get_type().get_element_type() {
-                    AnyTypeEnum::IntType(int_type) => {
-                        let rhs_value = cast_to_int(emitter, int_type, rhs_value);
-                        let added_value = emitter.builder.build_int_add(
-                            lhs_value.into_int_value(),
-                            rhs_value,
-                            "plusequal",
-                        );
-                        emitter.builder.build_store(alloca, added_value);
+                let (lhs, lhs_ctype) = load_operand(emitter, &target)?;
+                let (rhs, rhs_ctype) = load_operand(emitter, &value)?;
+                let ctype = arithmetic_type(&lhs_ctype, &rhs_ctype).ok_or_else(|| {
+                    unsupported("\"+=\" on a non-arithmetic value").at(span.clone())
+                })?;
+                let lhs = convert(emitter, lhs, &lhs_ctype, &ctype)?;
+                let rhs = convert(emitter, rhs, &rhs_ctype, &ctype)?;
+                let added_value: BasicValueEnum = match (lhs, rhs) {
+                    (BasicValueEnum::IntValue(lhs), BasicValueEnum::IntValue(rhs)) => {
+                        emitter.builder.build_int_add(lhs, rhs, "plusequal").into()
                     }
-                    _ => panic!("TODO"),
-                }
-                alloca
+                    (BasicValueEnum::FloatValue(lhs), BasicValueEnum::FloatValue(rhs)) => emitter
+                        .builder
+                        .build_float_add(lhs, rhs, "plusequal")
+                        .into(),
+                    _ => unreachable!("both operands are converted to an arithmetic type"),
+                };
+                store_converted(emitter, &target, added_value, &ctype)
+                    .map_err(|e| e.or_at(span))?;
+                Ok(target)
             }
-            _ => panic!("TODO"),
+            _ => unreachable!("is_assign_operator only accepts \"=\" and \"+=\""),
         }
     } else {
-        let lhs_alloca = emit_expression(emitter, *node.lhs);
-        let rhs_alloca = emit_expression(emitter, *node.rhs);
-        let lhs = load_value(emitter, lhs_alloca);
-        let rhs = load_value(emitter, rhs_alloca);
+        let lhs_operand = emit_expression(emitter, *node.lhs)?;
+        let rhs_operand = emit_expression(emitter, *node.rhs)?;
+        let lhs = load_value(emitter, lhs_operand.alloca)?;
+        let rhs = load_value(emitter, rhs_operand.alloca)?;
+        if operator == "&&" || operator == "||" {
+            // the builtins take each operand as 1 for true and 0 for false
+            let bool_type = emitter.context.bool_type();
+            let i32_type = emitter.context.i32_type();
+            let mut truth = |value, ctype: &CType| -> EmitResult<_> {
+                let value = cast_to_int(emitter, bool_type, value, ctype, ctype)?;
+                Ok(emitter.builder.build_int_z_extend(value, i32_type, "truth"))
+            };
+            let lhs = truth(lhs, &lhs_operand.ctype).map_err(|e| e.or_at(span.clone()))?;
+            let rhs = truth(rhs, &rhs_operand.ctype).map_err(|e| e.or_at(span.clone()))?;
+            let builtin = if operator == "&&" {
+                "and_int"
+            } else {
+                "or_int"
+            };
+            let value = emit_compare_expression_int(emitter, builtin, lhs, rhs).into();
+            return store_operand(emitter, value, CType::int());
+        }
+        // the usual arithmetic conversions
+        let (lhs, rhs, ctype) = match arithmetic_type(&lhs_operand.ctype, &rhs_operand.ctype) {
+            Some(ctype) => {
+                let lhs = convert(emitter, lhs, &lhs_operand.ctype, &ctype)?;
+                let rhs = convert(emitter, rhs, &rhs_operand.ctype, &ctype)?;
+                (lhs, rhs, ctype)
+            }
+            None => (lhs, rhs, lhs_operand.ctype.clone()),
+        };
         match lhs.get_type() {
-            BasicTypeEnum::IntType(int_type) => {
+            BasicTypeEnum::IntType(_) => {
                 let lhs = lhs.into_int_value();
-                let rhs = cast_to_int(emitter, int_type, rhs);
-                match operator.as_ref() {
-                    "+" => store_value(
-                        emitter,
-                        emitter.builder.build_int_add(lhs, rhs, "add").into(),
-                    ),
-                    "-" => store_value(
-                        emitter,
-                        emitter.builder.build_int_sub(lhs, rhs, "sub").into(),
-                    ),
-                    "*" => store_value(
-                        emitter,
-                        emitter.builder.build_int_mul(lhs, rhs, "mul").into(),
-                    ),
-                    "==" => {
-                        let value = emit_compare_expression_int(emitter, "eq_int", lhs, rhs).into();
-                        store_value(emitter, value)
+                let rhs = match rhs {
+                    BasicValueEnum::IntValue(rhs) => rhs,
+                    _ => {
+                        return Err(unsupported(&format!("\"{}\" on this type", operator)).at(span))
                     }
-                    ">" => {
-                        let value =
-                            emit_compare_expression_int(emitter, "sgt_int", lhs, rhs).into();
-                        store_value(emitter, value)
+                };
+                let (signed, unsigned) = match operator.as_ref() {
+                    "+" => {
+                        let value = emitter.builder.build_int_add(lhs, rhs, "add").into();
+                        return store_operand(emitter, value, ctype);
                     }
-                    "<" => {
-                        let value =
-                            emit_compare_expression_int(emitter, "slt_int", lhs, rhs).into();
-                        store_value(emitter, value)
+                    "-" => {
+                        let value = emitter.builder.build_int_sub(lhs, rhs, "sub").into();
+                        return store_operand(emitter, value, ctype);
                     }
-                    "&&" => {
-                        let value =
-                            emit_compare_expression_int(emitter, "and_int", lhs, rhs).into();
-                        store_value(emitter, value)
+                    "*" => {
+                        let value = emitter.builder.build_int_mul(lhs, rhs, "mul").into();
+                        return store_operand(emitter, value, ctype);
                     }
-                    "||" => {
-                        let value = emit_compare_expression_int(emitter, "or_int", lhs, rhs).into();
-                        store_value(emitter, value)
+                    "&" => {
+                        let value = emitter.builder.build_and(lhs, rhs, "and").into();
+                        return store_operand(emitter, value, ctype);
                     }
-                    _ => panic!("TODO"),
-                }
+                    "==" => (IntPredicate::EQ, IntPredicate::EQ),
+                    ">" => (IntPredicate::SGT, IntPredicate::UGT),
+                    "<" => (IntPredicate::SLT, IntPredicate::ULT),
+                    _ => return Err(unsupported(&format!("\"{}\" on integers", operator)).at(span)),
+                };
+                let predicate = if is_unsigned(&ctype) {
+                    unsigned
+                } else {
+                    signed
+                };
+                let value = emitter
+                    .builder
+                    .build_int_compare(predicate, lhs, rhs, "cmp");
+                let value =
+                    emitter
+                        .builder
+                        .build_int_z_extend(value, emitter.context.i32_type(), "cmp");
+                store_operand(emitter, value.into(), CType::int())
             }
-            BasicTypeEnum::FloatType(float_type) => {
+            BasicTypeEnum::FloatType(_) => {
                 let lhs = lhs.into_float_value();
-                let rhs = cast_to_float(emitter, float_type, rhs);
+                let rhs = match rhs {
+                    BasicValueEnum::FloatValue(rhs) => rhs,
+                    _ => {
+                        return Err(unsupported(&format!("\"{}\" on this type", operator)).at(span))
+                    }
+                };
                 match operator.as_ref() {
-                    "+" => store_value(
-                        emitter,
-                        emitter.builder.build_float_add(lhs, rhs, "add").into(),
-                    ),
+                    "+" => {
+                        let value = emitter.builder.build_float_add(lhs, rhs, "add").into();
+                        store_operand(emitter, value, ctype)
+                    }
                     "*" => {
                         let value = emitter.builder.build_float_mul(lhs, rhs, "mul").into();
-                        store_value(emitter, value)
+                        store_operand(emitter, value, ctype)
                     }
-                    _ => panic!("TODO"),
+                    _ => Err(unsupported(&format!("\"{}\" on floating values", operator)).at(span)),
                 }
             }
             BasicTypeEnum::ArrayType(_array_type) => {
                 let rhs = if let BasicValueEnum::IntValue(value) = rhs {
                     value
                 } else {
-                    panic!("expects an integer value")
+                    return Err(Diagnostic::error("an array offset must be an integer").at(span));
+                };
+                let element = match emitter.env.unqualified(&ctype)? {
+                    CType::Array(element, _) => element,
+                    _ => unreachable!("a value of an array type has a C array type"),
                 };
                 let const_zero = emitter.context.i32_type().const_zero();
                 let value = unsafe {
                     emitter
                         .builder
-                        .build_gep(lhs_alloca, &[const_zero, rhs], "gep")
+                        .build_gep(lhs_operand.alloca, &[const_zero, rhs], "gep")
                 };
-                store_value(emitter, BasicValueEnum::PointerValue(value))
+                store_operand(
+                    emitter,
+                    BasicValueEnum::PointerValue(value),
+                    CType::Pointer(element),
+                )
             }
-            _ => panic!(format!("TODO {:?}", lhs.get_type())),
+            _ => Err(unsupported(&format!("\"{}\" on this type", operator)).at(span)),
         }
     }
 }
 
-fn emit_prefix(emitter: &mut Emitter, node: PrefixNode) -> PointerValue {
+fn emit_prefix(emitter: &mut Emitter, node: PrefixNode) -> EmitResult<Operand> {
     let expression = *node.node;
-    match node.prefix.token {
-        Token::PrefixOp(op, _) => match op.as_ref() {
-            "&" => {
-                let value = BasicValueEnum::PointerValue(emit_expression(emitter, expression));
-                store_value(emitter, value)
-            }
-            "*" => {
-                let alloca = emit_expression(emitter, expression);
-                let value_alloca = load_value(emitter, alloca);
-                if let BasicValueEnum::PointerValue(value_alloca) = value_alloca {
-                    let value = load_value(emitter, value_alloca);
-                    store_value(emitter, value)
-                } else {
-                    panic!("")
+    let (op, debug_info) = match node.prefix.token {
+        Token::PrefixOp(op, debug_info) => (op, debug_info),
+        _ => unreachable!("the parser only builds prefix expressions on prefix operators"),
+    };
+    let span = Span::from(&debug_info);
+    match op.as_ref() {
+        "&" => {
+            let operand = emit_expression(emitter, expression)?;
+            let value = BasicValueEnum::PointerValue(operand.alloca);
+            store_operand(emitter, value, CType::Pointer(Box::new(operand.ctype)))
+        }
+        "*" => {
+            let operand = emit_expression(emitter, expression)?;
+            let value_alloca = load_value(emitter, operand.alloca)?;
+            match (value_alloca, emitter.env.unqualified(&operand.ctype)?) {
+                (BasicValueEnum::PointerValue(value_alloca), CType::Pointer(ctype)) => {
+                    let value = load_value(emitter, value_alloca)?;
+                    store_operand(emitter, value, *ctype)
+                }
+                _ => {
+                    Err(Diagnostic::error("the operand of unary \"*\" must be a pointer").at(span))
                 }
             }
-            "++" => {
-                let alloca = emit_expression(emitter, expression);
-                let value = load_value(emitter, alloca);
-                match value.get_type() {
-                    BasicTypeEnum::IntType(_type) => {
-                        let const_one = emitter.context.i32_type().const_int(1, false);
-                        let incremented_val = emitter.builder.build_int_add(
-                            value.into_int_value(),
-                            const_one,
-                            "postinc",
-                        );
-                        emitter.builder.build_store(alloca, incremented_val);
-                        alloca
-                    }
-                    _ => panic!(),
+        }
+        "+" => {
+            let operand = emit_expression(emitter, expression)?;
+            let value = load_value(emitter, operand.alloca)?;
+            let (value, ctype) = promote(emitter, value, &operand.ctype)?;
+            store_operand(emitter, value, ctype)
+        }
+        "-" => {
+            let operand = emit_expression(emitter, expression)?;
+            let value = load_value(emitter, operand.alloca)?;
+            let (value, ctype) = promote(emitter, value, &operand.ctype)?;
+            let value = match value {
+                BasicValueEnum::IntValue(value) => {
+                    emitter.builder.build_int_neg(value, "neg").into()
+                }
+                BasicValueEnum::FloatValue(value) => {
+                    emitter.builder.build_float_neg(value, "neg").into()
                 }
+                _ => {
+                    return Err(Diagnostic::error(
+                        "the operand of unary \"-\" must have an arithmetic type",
+                    )
+                    .at(span))
+                }
+            };
+            store_operand(emitter, value, ctype)
+        }
+        "++" | "--" => {
+            let operand = emit_expression(emitter, expression)?;
+            let value = load_value(emitter, operand.alloca)?;
+            match value {
+                BasicValueEnum::IntValue(value) => {
+                    let const_one = value.get_type().const_int(1, false);
+                    let incremented_val = if op == "++" {
+                        emitter.builder.build_int_add(value, const_one, "preinc")
+                    } else {
+                        emitter.builder.build_int_sub(value, const_one, "predec")
+                    };
+                    emitter.builder.build_store(operand.alloca, incremented_val);
+                    Ok(operand)
+                }
+                _ => Err(unsupported(&format!("\"{}\" on a non-integer value", op)).at(span)),
             }
-            _ => panic!("TODO"),
-        },
-        _ => panic!("expects prefix operator"),
+        }
+        _ => unreachable!("the parser only accepts &, *, +, -, ++ and -- as prefixes"),
     }
 }
 
-fn emit_array_index(emitter: &mut Emitter, node: ArrayIndexNode) -> PointerValue {
-    let array_alloca = emit_expression(emitter, *node.array);
-    let index_alloca = emit_expression(emitter, *node.index);
-    let index_value = load_value(emitter, index_alloca).into_int_value();
+fn emit_array_index(emitter: &mut Emitter, node: ArrayIndexNode) -> EmitResult<Operand> {
+    let array = emit_expression(emitter, *node.array)?;
+    let index = emit_expression(emitter, *node.index)?;
+    let index_value = match load_value(emitter, index.alloca)? {
+        // an index narrower than the pointers is extended by its sign
+        BasicValueEnum::IntValue(value) => {
+            let i64_type = emitter.context.i64_type();
+            let long = CType::Int {
+                bits: 64,
+                is_signed: true,
+            };
+            cast_to_int(emitter, i64_type, value.into(), &index.ctype, &long)?
+        }
+        _ => return Err(Diagnostic::error("an array subscript must be an integer")),
+    };
+    let ctype = match emitter.env.unqualified(&array.ctype)? {
+        CType::Pointer(ctype) | CType::Array(ctype, _) => *ctype,
+        _ => {
+            return Err(Diagnostic::error(
+                "a subscripted value must be an array or a pointer",
+            ))
+        }
+    };
     let const_zero = emitter.context.i32_type().const_zero();
-    match array_alloca.get_type().get_element_type() {
+    let alloca = match array.alloca.get_type().get_element_type() {
         AnyTypeEnum::PointerType(_type) => unsafe {
             let array_alloca = emitter
                 .builder
-                .build_load(array_alloca, "arr_pointer")
+                .build_load(array.alloca, "arr_pointer")
                 .into_pointer_value();
             emitter
                 .builder
@@ -247,101 +380,127 @@ fn emit_array_index(emitter: &mut Emitter, node: ArrayIndexNode) -> PointerValue
         AnyTypeEnum::ArrayType(_type) => unsafe {
             emitter
                 .builder
-                .build_gep(array_alloca, &[const_zero, index_value], "arrind")
+                .build_gep(array.alloca, &[const_zero, index_value], "arrind")
         },
-        _ => panic!("unexpected type"),
-    }
+        _ => {
+            return Err(Diagnostic::error(
+                "a subscripted value must be an array or a pointer",
+            ))
+        }
+    };
+    Ok(Operand { alloca, ctype })
 }
 
-fn emit_comma_as_arguments(emitter: &mut Emitter, node: ExpressionNode) -> Vec<BasicValueEnum> {
+/// The values of the arguments and their types, with an array passed as the
+/// pointer to its first element.
+fn emit_comma_as_arguments(
+    emitter: &mut Emitter,
+    node: ExpressionNode,
+) -> EmitResult<Vec<(BasicValueEnum, CType)>> {
     let arguments = match node {
         ExpressionNode::BinExp(node) => match node.clone().op.token {
-            Token::Op(op, _) => match op.as_ref() {
-                "," => {
-                    let mut lhs = emit_comma_as_arguments(emitter, *node.lhs);
-                    let rhs = emit_comma_as_arguments(emitter, *node.rhs);
-                    lhs.extend(rhs.iter().cloned());
-                    lhs
-                }
-                _ => {
-                    let val_ptr = emit_bin_exp(emitter, node);
-                    vec![load_value(emitter, val_ptr)]
-                }
-            },
-            _ => panic!(),
+            Token::Op(op, _) if op == "," => {
+                let mut lhs = emit_comma_as_arguments(emitter, *node.lhs)?;
+                let rhs = emit_comma_as_arguments(emitter, *node.rhs)?;
+                lhs.extend(rhs.iter().cloned());
+                lhs
+            }
+            _ => {
+                let operand = emit_bin_exp(emitter, node)?;
+                vec![load_operand(emitter, &operand)?]
+            }
         },
-        ExpressionNode::Token(node) => {
-            let val_ptr = emit_token(emitter, node);
-            vec![load_value(emitter, val_ptr)]
+        ExpressionNode::Empty(_) => vec![],
+        node => {
+            let operand = emit_expression(emitter, node)?;
+            vec![load_operand(emitter, &operand)?]
         }
-        ExpressionNode::Empty => vec![],
-        _ => panic!(),
     };
-    let mut converted_arguments: Vec<BasicValueEnum> = Vec::new();
-    for argument in arguments.into_iter() {
-        let val = match argument.get_type() {
-            BasicTypeEnum::ArrayType(_type) => {
-                let const_zero = emitter.context.i32_type().const_zero();
-                let array_alloca = store_value(emitter, argument);
-                unsafe {
-                    emitter
-                        .builder
-                        .build_gep(array_alloca, &[const_zero, const_zero], "arrptr")
-                        .into()
-                }
-            }
-            _ => argument,
-        };
-        converted_arguments.push(val)
-    }
-    converted_arguments
+    Ok(arguments)
 }
 
-fn emit_function_call(emitter: &mut Emitter, node: FunctionCallNode) -> PointerValue {
+fn emit_function_call(emitter: &mut Emitter, node: FunctionCallNode) -> EmitResult<Operand> {
+    let span = node.identifier.token.span();
     let identifier = match node.identifier.token {
         Token::Ide(identifier, _) => identifier,
-        _ => panic!(),
+        _ => return Err(Diagnostic::error("only a function name can be called").at(span)),
     };
     let fn_value = match emitter.module.get_function(&identifier) {
         Some(value) => value,
-        None => panic!(format!("call of undeclared function {}", identifier)),
+        None => {
+            return Err(Diagnostic::error(format!(
+                "call of undeclared function \"{}\"",
+                identifier
+            ))
+            .at(span))
+        }
+    };
+    // the builtins are declared in the module only, and take and return int
+    let (return_type, parameters) = match emitter.env.get_other(&identifier) {
+        Some(Other::Function(CType::Function {
+            return_type,
+            parameters,
+            ..
+        })) => (*return_type, parameters),
+        _ => (CType::int(), Vec::new()),
     };
-    let arguments = emit_comma_as_arguments(emitter, *node.parameters);
+    let mut arguments = Vec::new();
+    for (i, (value, ctype)) in emit_comma_as_arguments(emitter, *node.parameters)?
+        .into_iter()
+        .enumerate()
+    {
+        let value = match parameters.get(i) {
+            Some(parameter) => convert(emitter, value, &ctype, parameter)?,
+            // an argument for `...` gets the integer promotions
+            None => promote(emitter, value, &ctype)?.0,
+        };
+        arguments.push(value);
+    }
     let func_call_site = emitter.builder.build_call(fn_value, &arguments, "func");
-    let val: BasicValueEnum = func_call_site.try_as_basic_value().left().unwrap();
-    store_value(emitter, val)
+    let val: BasicValueEnum = match func_call_site.try_as_basic_value().left() {
+        Some(val) => val,
+        None => {
+            return Err(
+                Diagnostic::error(format!("the result of \"{}\" has no value", identifier))
+                    .at(span),
+            )
+        }
+    };
+    store_operand(emitter, val, return_type)
 }
 
-fn emit_suffix(emitter: &mut Emitter, node: SuffixNode) -> PointerValue {
+fn emit_suffix(emitter: &mut Emitter, node: SuffixNode) -> EmitResult<Operand> {
     let suffix = node.suffix;
     let expression = *node.node;
-    let value_alloca = emit_expression(emitter, expression);
-    let value = load_value(emitter, value_alloca);
-    match suffix.token {
-        Token::SuffixOp(op, _) => match op.as_ref() {
-            "++" => match value.get_type() {
-                BasicTypeEnum::IntType(_type) => {
-                    let const_one = emitter.context.i32_type().const_int(1, false);
-                    let incremented_val =
-                        emitter
-                            .builder
-                            .build_int_add(value.into_int_value(), const_one, "postinc");
-                    emitter.builder.build_store(value_alloca, incremented_val);
-                    store_value(emitter, value) // return not incremented value
-                }
-                _ => panic!(),
-            },
-            _ => panic!(),
-        },
-        _ => panic!(),
+    let operand = emit_expression(emitter, expression)?;
+    let value = load_value(emitter, operand.alloca)?;
+    let (op, debug_info) = match suffix.token {
+        Token::SuffixOp(op, debug_info) => (op, debug_info),
+        _ => unreachable!("the parser only builds suffix expressions on suffix operators"),
+    };
+    match value {
+        BasicValueEnum::IntValue(value) => {
+            let const_one = value.get_type().const_int(1, false);
+            let incremented_val = if op == "++" {
+                emitter.builder.build_int_add(value, const_one, "postinc")
+            } else {
+                emitter.builder.build_int_sub(value, const_one, "postdec")
+            };
+            emitter.builder.build_store(operand.alloca, incremented_val);
+            store_operand(emitter, value.into(), operand.ctype) // return not incremented value
+        }
+        _ => {
+            Err(unsupported(&format!("\"{}\" on a non-integer value", op))
+                .at(Span::from(&debug_info)))
+        }
     }
 }
 
-fn emit_access(emitter: &mut Emitter, node: AccessNode) -> PointerValue {
-    let access_identifier = if let Token::Ide(identifier, _) = node.access_identifier {
-        identifier
-    } else {
-        panic!("unexpected")
+fn emit_access(emitter: &mut Emitter, node: AccessNode) -> EmitResult<Operand> {
+    let span = node.access_identifier.span();
+    let access_identifier = match node.access_identifier {
+        Token::Ide(identifier, _) => identifier,
+        _ => unreachable!("the parser only accepts an identifier after \".\""),
     };
     let expression = *node.node;
 
@@ -351,31 +510,31 @@ fn emit_access(emitter: &mut Emitter, node: AccessNode) -> PointerValue {
     {
         identifier
     } else {
-        panic!("unexpected")
+        return Err(unsupported("a member access on anything but a variable").at(span));
     };
-    let field_identifier = identifier + "." + &access_identifier;
+    let field_identifier = identifier.clone() + "." + &access_identifier;
     match emitter.env.get_other(&field_identifier) {
-        Some(other) => match other {
-            Other::Variable(value_alloca) => value_alloca,
-            _ => panic!("unexpected"),
-        },
-        None => panic!("unexpected"),
+        Some(Other::Variable(alloca, ctype)) => Ok(Operand { alloca, ctype }),
+        _ => Err(Diagnostic::error(format!(
+            "\"{}\" has no member named \"{}\"",
+            identifier, access_identifier
+        ))
+        .at(span)),
     }
 }
 
-fn emit_ternary_exp(emitter: &mut Emitter, node: TernaryExpNode) -> PointerValue {
-    let function = match emitter.module.get_last_function() {
-        Some(func) => func,
-        None => panic!(),
-    };
-    let entry_bb = function.get_last_basic_block().unwrap();
+fn emit_ternary_exp(emitter: &mut Emitter, node: TernaryExpNode) -> EmitResult<Operand> {
+    let function = emitter.module.get_last_function().expect("a function");
+    let entry_bb = function
+        .get_last_basic_block()
+        .expect("a block to branch from");
     let then_bb = function.append_basic_block("ifthen");
     let cont_bb = function.append_basic_block("ifcont");
 
-    let then_val_alloca = emit_expression(emitter, *node.lhs);
-    let entry_val_alloca = emit_expression(emitter, *node.rhs);
+    let then_val = emit_expression(emitter, *node.lhs)?;
+    let entry_val = emit_expression(emitter, *node.rhs)?;
 
-    let condition_val = emit_condition_expression_eq(emitter, *node.condition);
+    let condition_val = emit_condition_expression_eq(emitter, *node.condition)?;
     emitter
         .builder
         .build_conditional_branch(condition_val, &cont_bb, &then_bb);
@@ -384,9 +543,12 @@ fn emit_ternary_exp(emitter: &mut Emitter, node: TernaryExpNode) -> PointerValue
     emitter.builder.build_unconditional_branch(&cont_bb);
     emitter.builder.position_at_end(&cont_bb);
 
-    let phi_type = entry_val_alloca.get_type();
+    let phi_type = entry_val.alloca.get_type();
     let phi = emitter.builder.build_phi(phi_type, "compphi");
 
-    phi.add_incoming(&[(&then_val_alloca, &then_bb), (&entry_val_alloca, &entry_bb)]);
-    *phi.as_basic_value().as_pointer_value()
+    phi.add_incoming(&[(&then_val.alloca, &then_bb), (&entry_val.alloca, &entry_bb)]);
+    Ok(Operand {
+        alloca: *phi.as_basic_value().as_pointer_value(),
+        ctype: then_val.ctype,
+    })
 }
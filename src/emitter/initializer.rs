@@ -0,0 +1,367 @@
+//! Initializer lists. A list is flattened into the scalar values it sets, each
+//! found by the indices of the members and elements it is in, which are then
+//! stored in a local variable or folded into the constant of a global one.
+
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{BasicValueEnum, PointerValue};
+use std::collections::VecDeque;
+
+use crate::diagnostic::Diagnostic;
+use crate::emitter::const_expression::*;
+use crate::emitter::environment::*;
+use crate::emitter::expression::*;
+use crate::emitter::statement::*;
+use crate::emitter::util::*;
+use crate::emitter::*;
+use crate::parser::ctype::CType;
+use crate::parser::declare::initializer::*;
+use crate::parser::expression::ExpressionNode;
+
+/// A scalar value an initializer sets, in the member or element each index of
+/// `path` selects in turn.
+pub struct Store {
+    pub path: Vec<u32>,
+    /// The type of the scalar, with the typedefs in it resolved.
+    pub ctype: CType,
+    pub expression: ExpressionNode,
+}
+
+/// An array or struct, which a list fills one member or element after another.
+enum Aggregate {
+    /// The element type and the length, which is None for `[]`.
+    Array(CType, Option<u32>),
+    /// The names and types of the members.
+    Struct(Vec<String>, Vec<CType>),
+}
+impl Aggregate {
+    fn len(&self) -> Option<u32> {
+        match self {
+            Aggregate::Array(_, len) => *len,
+            Aggregate::Struct(names, _) => Some(names.len() as u32),
+        }
+    }
+    fn member(&self, index: u32) -> &CType {
+        match self {
+            Aggregate::Array(element, _) => element,
+            Aggregate::Struct(_, types) => &types[index as usize],
+        }
+    }
+    /// The index of the member or element `designator` selects.
+    fn designate(&self, designator: &Designator) -> EmitResult<u32> {
+        match (self, designator) {
+            (Aggregate::Array(_, Some(len)), Designator::Index(index)) if index >= len => {
+                Err(Diagnostic::error(format!(
+                    "array index {} in an initializer is out of bounds",
+                    index
+                )))
+            }
+            (Aggregate::Array(_, _), Designator::Index(index)) => Ok(*index),
+            (Aggregate::Struct(names, _), Designator::Field(name)) => names
+                .iter()
+                .position(|member| member == name)
+                .map(|index| index as u32)
+                .ok_or_else(|| {
+                    Diagnostic::error(format!("the struct has no member named \"{}\"", name))
+                }),
+            (Aggregate::Array(_, _), Designator::Field(name)) => Err(Diagnostic::error(format!(
+                "member designator \".{}\" in an array initializer",
+                name
+            ))),
+            (Aggregate::Struct(_, _), Designator::Index(index)) => Err(Diagnostic::error(format!(
+                "array designator \"[{}]\" in a struct initializer",
+                index
+            ))),
+        }
+    }
+}
+
+struct Flatten<'a> {
+    env: &'a Environment,
+    stores: Vec<Store>,
+}
+impl Flatten<'_> {
+    /// The array or struct `ctype` is, or None for a scalar.
+    fn aggregate(&self, ctype: &CType) -> EmitResult<Option<Aggregate>> {
+        Ok(match self.env.unqualified(ctype)? {
+            CType::Array(element, len) => Some(Aggregate::Array(*element, len)),
+            CType::Struct(Some(tag)) => match self.env.get_tag(&tag) {
+                Some(Tag::Struct(struct_value)) => {
+                    Some(Aggregate::Struct(struct_value.names, struct_value.types))
+                }
+                None => return Err(Diagnostic::error(format!("unknown struct \"{}\"", tag))),
+            },
+            CType::Struct(None) => {
+                return Err(unsupported("a struct without a tag outside a typedef"))
+            }
+            CType::Union(_) => return Err(unsupported("unions")),
+            _ => None,
+        })
+    }
+    /// Initialize the object of `ctype` at `path`.
+    fn initializer(
+        &mut self,
+        ctype: &CType,
+        initializer: Initializer,
+        path: Vec<u32>,
+    ) -> EmitResult<()> {
+        match (self.aggregate(ctype)?, initializer) {
+            (None, Initializer::Expression(expression)) => {
+                self.stores.push(Store {
+                    path,
+                    ctype: self.env.resolve_typedefs(ctype)?,
+                    expression,
+                });
+                Ok(())
+            }
+            (Some(_), Initializer::Expression(expression)) => Err(Diagnostic::error(
+                "an array or struct must be initialized with a list in braces",
+            )
+            .at(expression.span())),
+            // a scalar may be in braces, as in `int a = {1};`
+            (None, Initializer::List(list)) => {
+                let mut items = list.items.into_iter();
+                match (items.next(), items.next()) {
+                    (None, _) => Ok(()),
+                    (Some(item), None) if item.designators.is_empty() => {
+                        self.initializer(ctype, item.initializer, path)
+                    }
+                    (Some(item), None) => Err(Diagnostic::error(
+                        "a designator in the initializer of a scalar",
+                    )
+                    .at(item.initializer.span())),
+                    (Some(_), Some(item)) => {
+                        Err(Diagnostic::error("excess elements in a scalar initializer")
+                            .at(item.initializer.span()))
+                    }
+                }
+            }
+            (Some(aggregate), Initializer::List(list)) => {
+                self.fill(&aggregate, &mut list.items.into(), &path, true, false)?;
+                Ok(())
+            }
+        }
+    }
+    /// Fill the members or elements of `aggregate` at `path` from `items`, and
+    /// return the number of elements up to the last one set. Without braces of
+    /// its own, as in `int a[2][2] = {1, 2, 3};`, it stops at an item of the
+    /// enclosing list: one past the end, or one with designators unless it is
+    /// the first and `is_designated`.
+    fn fill(
+        &mut self,
+        aggregate: &Aggregate,
+        items: &mut VecDeque<InitializerItem>,
+        path: &[u32],
+        is_braced: bool,
+        mut is_designated: bool,
+    ) -> EmitResult<u32> {
+        let mut next = 0;
+        let mut len = 0;
+        while let Some(mut item) = items.pop_front() {
+            let span = item.initializer.span();
+            if !item.designators.is_empty() {
+                if !is_braced && !is_designated {
+                    items.push_front(item);
+                    break;
+                }
+                let designator = item.designators.remove(0);
+                next = aggregate
+                    .designate(&designator)
+                    .map_err(|error| error.or_at(span.clone()))?;
+            } else if matches!(aggregate.len(), Some(len) if next >= len) {
+                if !is_braced {
+                    items.push_front(item);
+                    break;
+                }
+                return Err(Diagnostic::error("excess elements in an initializer").at(span));
+            }
+            is_designated = false;
+
+            let ctype = aggregate.member(next).clone();
+            let mut member_path = path.to_vec();
+            member_path.push(next);
+            if !item.designators.is_empty() {
+                // `.a.b = 1` goes on into the members of `a`
+                let member = self.aggregate(&ctype)?.ok_or_else(|| {
+                    Diagnostic::error("a designator for a member of a scalar").at(span.clone())
+                })?;
+                items.push_front(item);
+                self.fill(&member, items, &member_path, false, true)?;
+            } else {
+                match (self.aggregate(&ctype)?, item.initializer) {
+                    // without braces, the values go into the members of the member
+                    (Some(member), Initializer::Expression(expression)) => {
+                        items.push_front(InitializerItem {
+                            designators: Vec::new(),
+                            initializer: Initializer::Expression(expression),
+                        });
+                        let left = items.len();
+                        self.fill(&member, items, &member_path, false, false)?;
+                        if items.len() == left {
+                            return Err(
+                                Diagnostic::error("excess elements in an initializer").at(span)
+                            );
+                        }
+                    }
+                    (_, initializer) => self.initializer(&ctype, initializer, member_path)?,
+                }
+            }
+            next = next.checked_add(1).ok_or_else(|| {
+                Diagnostic::error("an initializer that makes the array too large").at(span)
+            })?;
+            len = len.max(next);
+        }
+        Ok(len)
+    }
+}
+
+/// The values `initializer` sets in a variable of `ctype`, and the type, whose
+/// length the list gives for an array declared with `[]`.
+pub fn flatten(
+    env: &Environment,
+    ctype: &CType,
+    initializer: Initializer,
+) -> EmitResult<(CType, Vec<Store>)> {
+    let mut flatten = Flatten {
+        env,
+        stores: Vec::new(),
+    };
+    let ctype = match (env.unqualified(ctype)?, initializer) {
+        (CType::Array(element, None), Initializer::List(list)) => {
+            let aggregate = Aggregate::Array((*element).clone(), None);
+            let len = flatten.fill(&aggregate, &mut list.items.into(), &[], true, false)?;
+            CType::Array(element, Some(len))
+        }
+        (_, initializer) => {
+            flatten.initializer(ctype, initializer, Vec::new())?;
+            ctype.clone()
+        }
+    };
+    Ok((ctype, flatten.stores))
+}
+
+/// Declare a local variable initialized with `list`. It is zeroed first, so
+/// the members and elements the list leaves out are zero.
+pub fn emit_initialized_variable(
+    emitter: &mut Emitter,
+    identifier: String,
+    value_type: &CType,
+    list: InitializerListNode,
+) -> EmitResult<()> {
+    let (value_type, stores) = flatten(&emitter.env, value_type, Initializer::List(list))?;
+    let basic_type = emitter.env.get_type(&emitter.context, &value_type)?;
+
+    // a struct is a variable for each member, which the first index selects
+    let (is_struct, variables): (bool, Vec<(PointerValue, BasicTypeEnum)>) =
+        match emitter.env.unqualified(&value_type)? {
+            CType::Struct(Some(tag)) => {
+                let struct_type = basic_type.into_struct_type();
+                let fields = emit_struct_declare(emitter, identifier, &tag)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let field_type = struct_type
+                            .get_field_type_at_index(i as u32)
+                            .expect("a field type for each field name");
+                        (field, field_type)
+                    })
+                    .collect();
+                (true, fields)
+            }
+            _ => {
+                let alloca = alloca_from_basic_type(emitter, basic_type)?;
+                let ctype = emitter.env.resolve_typedefs(&value_type)?;
+                emitter
+                    .env
+                    .insert_new_other(identifier, Other::Variable(alloca, ctype))?;
+                (false, vec![(alloca, basic_type)])
+            }
+        };
+    for (alloca, basic_type) in &variables {
+        emitter
+            .builder
+            .build_store(*alloca, const_zero(*basic_type));
+    }
+
+    for store in stores {
+        let span = store.expression.span();
+        let mut path = store.path;
+        let alloca = if is_struct {
+            variables[path.remove(0) as usize].0
+        } else {
+            variables[0].0
+        };
+        let pointer = if path.is_empty() {
+            alloca
+        } else {
+            let i32_type = emitter.context.i32_type();
+            let mut indices = vec![i32_type.const_zero()];
+            indices.extend(
+                path.iter()
+                    .map(|&index| i32_type.const_int(u64::from(index), false)),
+            );
+            unsafe { emitter.builder.build_gep(alloca, &indices, "init") }
+        };
+        let target = Operand {
+            alloca: pointer,
+            ctype: store.ctype,
+        };
+        let value = emit_expression(emitter, store.expression)?;
+        let (value, ctype) = load_operand(emitter, &value)?;
+        store_converted(emitter, &target, value, &ctype).map_err(|error| error.or_at(span))?;
+    }
+    Ok(())
+}
+
+/// The constant a global of `value_type` starts with, which is zero where
+/// `stores` set nothing.
+pub fn emit_const_initializer(
+    emitter: &mut Emitter,
+    value_type: BasicTypeEnum,
+    mut stores: Vec<Store>,
+) -> EmitResult<BasicValueEnum> {
+    if stores.is_empty() {
+        return Ok(const_zero(value_type));
+    }
+    match value_type {
+        BasicTypeEnum::ArrayType(array_type) => {
+            let element_type = array_type.get_element_type();
+            let values = members(stores, array_type.len())
+                .into_iter()
+                .map(|stores| emit_const_initializer(emitter, element_type, stores))
+                .collect::<EmitResult<Vec<BasicValueEnum>>>()?;
+            Ok(const_array(element_type, values).into())
+        }
+        BasicTypeEnum::StructType(struct_type) => {
+            let values = members(stores, struct_type.count_fields())
+                .into_iter()
+                .enumerate()
+                .map(|(i, stores)| {
+                    let field_type = struct_type
+                        .get_field_type_at_index(i as u32)
+                        .expect("a field type for each field");
+                    emit_const_initializer(emitter, field_type, stores)
+                })
+                .collect::<EmitResult<Vec<BasicValueEnum>>>()?;
+            Ok(struct_type.const_named_struct(&values).into())
+        }
+        _ => {
+            // a designator may set a value again, and the last one is kept
+            let store = stores.pop().expect("a store for a scalar");
+            let span = store.expression.span();
+            let (value, ctype) =
+                emit_const_expression(emitter, store.expression).map_err(|e| e.or_at(span))?;
+            Ok(cast_const_value(value, &ctype, value_type))
+        }
+    }
+}
+
+/// `stores` grouped by the member or element they are in, with that index
+/// removed from their paths.
+fn members(stores: Vec<Store>, len: u32) -> Vec<Vec<Store>> {
+    let mut members: Vec<Vec<Store>> = (0..len).map(|_| Vec::new()).collect();
+    for mut store in stores {
+        let index = store.path.remove(0);
+        members[index as usize].push(store);
+    }
+    members
+}
@@ -2,6 +2,7 @@ pub mod builtin;
 pub mod const_expression;
 pub mod environment;
 pub mod expression;
+pub mod initializer;
 pub mod statement;
 pub mod util;
 
@@ -13,14 +14,18 @@ use inkwell::types::{BasicTypeEnum, FunctionType};
 
 use std::path;
 
-use crate::emitter::const_expression::*;
+use crate::diagnostic::Diagnostic;
 use crate::emitter::environment::*;
+use crate::emitter::initializer::*;
 use crate::emitter::statement::*;
 use crate::emitter::util::*;
+use crate::parser::ctype::CType;
 use crate::parser::declare::*;
 use crate::parser::statement::*;
 use crate::parser::ProgramNode;
 
+pub type EmitResult<T> = Result<T, Diagnostic>;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Control {
     Continue,
@@ -52,15 +57,22 @@ impl Emitter {
             env,
         }
     }
-    pub fn print_to_file(&self) {
-        let _ = self.module.print_to_file(path::Path::new("compiled.ll"));
+    pub fn print_to_file(&self) -> EmitResult<()> {
+        print_module(&self.module, "compiled.ll")
     }
-    pub fn emit(&mut self, node: ProgramNode) {
-        emit_program(self, node);
+    pub fn emit(&mut self, node: ProgramNode) -> EmitResult<()> {
+        emit_program(self, node)
     }
 }
 
-fn emit_program(emitter: &mut Emitter, node: ProgramNode) {
+/// Write the LLVM IR of `module` to the file `filename`.
+pub fn print_module(module: &Module, filename: &str) -> EmitResult<()> {
+    module
+        .print_to_file(path::Path::new(filename))
+        .map_err(|error| Diagnostic::error(format!("{}: {}", filename, error)))
+}
+
+fn emit_program(emitter: &mut Emitter, node: ProgramNode) -> EmitResult<()> {
     // set builtin functions
     let i32_type = emitter.context.i32_type();
     let fn_type = i32_type.fn_type(&[i32_type.into(), i32_type.into()], false);
@@ -74,18 +86,24 @@ fn emit_program(emitter: &mut Emitter, node: ProgramNode) {
     emitter.module.add_function("putchar", fn_type, None);
 
     let mut declares = node.declares;
-    let scope = Scope::new(emitter);
+    let scope = Scope::new();
     emitter.env.push_scope(scope);
     while let Some(declare) = declares.pop_front() {
+        let span = declare.span();
         match declare {
             DeclareNode::Function(node) => emit_function(emitter, node),
             DeclareNode::Variable(node) => emit_declare_statement_global(emitter, node),
-        };
+            DeclareNode::Pragma(_) => Ok(Control::Continue),
+            DeclareNode::Typedef(node) => emit_typedef(emitter, node, emit_declare_variable_global),
+            DeclareNode::Error(node) => Err(node.diagnostic),
+        }
+        .map_err(|error| error.or_at(span))?;
     }
     emitter.env.pop_scope();
+    Ok(())
 }
 
-fn emit_function(emitter: &mut Emitter, function_node: FunctionNode) -> Control {
+fn emit_function(emitter: &mut Emitter, function_node: FunctionNode) -> EmitResult<Control> {
     let identifier = function_node.identifier;
     let parameters = function_node.parameters;
     let mut param_types: Vec<BasicTypeEnum> = Vec::new();
@@ -93,19 +111,41 @@ fn emit_function(emitter: &mut Emitter, function_node: FunctionNode) -> Control
     let mut cloned_paramaters = parameters.clone();
     while let Some(declare_variable_node) = cloned_paramaters.pop_front() {
         let val_type = declare_variable_node.value_type;
-        let param_type = emitter.env.get_type_from_string(&val_type);
+        let param_type = emitter.env.get_type(&emitter.context, &val_type)?;
         param_types.push(param_type);
     }
-    let scope = Scope::new(emitter);
+    let ctype = emitter.env.resolve_typedefs(&CType::Function {
+        return_type: Box::new(function_node.return_type.clone()),
+        parameters: parameters
+            .iter()
+            .map(|parameter| parameter.value_type.clone())
+            .collect(),
+        is_var_args: function_node.is_var_args,
+    })?;
+    // a prototype declares the function before its definition does
+    match emitter.env.get_other(&identifier) {
+        Some(Other::Function(_)) => (),
+        _ => {
+            emitter
+                .env
+                .insert_new_other(identifier.clone(), Other::Function(ctype))?;
+        }
+    }
+    let scope = Scope::new();
     emitter.env.push_scope(scope);
-    let return_type = emitter.env.get_type_from_string(&function_node.return_type);
-    let fn_type = to_fn_type(return_type, param_types);
-    let func = if function_node.is_extern {
-        emitter
-            .module
-            .add_function(&identifier, fn_type, Some(Linkage::External))
-    } else {
-        emitter.module.add_function(&identifier, fn_type, None)
+    let return_type = emitter
+        .env
+        .get_type(&emitter.context, &function_node.return_type)?;
+    let fn_type = to_fn_type(return_type, param_types, function_node.is_var_args)?;
+    let func = match emitter.module.get_function(&identifier) {
+        // declared by a prototype
+        Some(func) => func,
+        None if function_node.is_extern => {
+            emitter
+                .module
+                .add_function(&identifier, fn_type, Some(Linkage::External))
+        }
+        None => emitter.module.add_function(&identifier, fn_type, None),
     };
 
     if let Some(mut statements) = function_node.statements {
@@ -116,14 +156,20 @@ fn emit_function(emitter: &mut Emitter, function_node: FunctionNode) -> Control
             // It can be used for assertion
             // let value_type = get_type_from_string(emitter, declare_variable_node.value_type);
             let identifier = declare_variable_node.identifier;
-            let value = match func.get_nth_param(i as u32) {
-                Some(val) => val,
-                None => panic!("unexpected"),
-            };
-            let alloca = store_value(emitter, value);
+            if identifier.is_empty() {
+                // a parameter without a name cannot be used
+                continue;
+            }
+            let value = func
+                .get_nth_param(i as u32)
+                .expect("a parameter for each declared one");
+            let alloca = store_value(emitter, value)?;
+            let ctype = emitter
+                .env
+                .resolve_typedefs(&declare_variable_node.value_type)?;
             emitter
                 .env
-                .insert_new_other(identifier, Other::Variable(alloca));
+                .insert_new_other(identifier, Other::Variable(alloca, ctype))?;
         }
 
         let next_blocks = NextBlock {
@@ -132,26 +178,47 @@ fn emit_function(emitter: &mut Emitter, function_node: FunctionNode) -> Control
         };
 
         while let Some(statement) = statements.pop_front() {
-            emit_statement(emitter, statement, next_blocks.clone());
+            emit_statement(emitter, statement, next_blocks.clone())?;
         }
-        emitter.env.pop_scope();
     }
-    Control::Continue
+    emitter.env.pop_scope();
+    Ok(Control::Continue)
 }
 
-fn emit_declare_statement_global(emitter: &mut Emitter, node: DeclareStatementNode) -> Control {
-    let node = node.declare_variable_node;
+fn emit_declare_statement_global(
+    emitter: &mut Emitter,
+    node: DeclareStatementNode,
+) -> EmitResult<Control> {
+    for declare_variable_node in node.declare_variable_nodes {
+        let span = declare_variable_node.span.clone();
+        emit_declare_variable_global(emitter, declare_variable_node)
+            .map_err(|error| error.or_at(span))?;
+    }
+    Ok(Control::Continue)
+}
 
+fn emit_declare_variable_global(
+    emitter: &mut Emitter,
+    node: DeclareVariableNode,
+) -> EmitResult<()> {
     let identifier = node.identifier;
-    let value_type = emitter.env.get_type_from_string(&node.value_type.clone());
+    let (value_type, stores) = match node.initializer {
+        Some(initializer) => {
+            let (value_type, stores) = flatten(&emitter.env, &node.value_type, initializer)?;
+            (value_type, Some(stores))
+        }
+        None => (node.value_type, None),
+    };
+    let ctype = emitter.env.resolve_typedefs(&value_type)?;
+    let value_type = emitter.env.get_type(&emitter.context, &ctype)?;
 
     let global = emitter.module.add_global(value_type, None, &identifier);
-    if let Some(expression) = node.initialize_expression {
-        let value = emit_const_expression(emitter, expression);
+    if let Some(stores) = stores {
+        let value = emit_const_initializer(emitter, value_type, stores)?;
         global.set_initializer(&value)
     }
     emitter
         .env
-        .insert_new_other(identifier, Other::Global(global));
-    Control::Continue
+        .insert_new_other(identifier, Other::Global(global, ctype))?;
+    Ok(())
 }
@@ -2,21 +2,31 @@ use inkwell::types::BasicTypeEnum;
 use inkwell::values::PointerValue;
 use inkwell::IntPredicate;
 
+use crate::diagnostic::Diagnostic;
 use crate::emitter::environment::*;
 use crate::emitter::expression::*;
+use crate::emitter::initializer::*;
 use crate::emitter::util::*;
 use crate::emitter::*;
+use crate::lexer::literal::parse_int_literal;
 use crate::lexer::token::*;
+use crate::parser::ctype::CType;
+use crate::parser::declare::initializer::Initializer;
+use crate::parser::declare::*;
+use crate::parser::expression::{ExpressionNode, TokenNode};
 use crate::parser::statement::*;
+use crate::parser::*;
 
 use std::collections::VecDeque;
 
+/// Errors without a location are reported at the whole statement.
 pub fn emit_statement(
     emitter: &mut Emitter,
     node: StatementNode,
     next_block: NextBlock,
-) -> Control {
-    match node {
+) -> EmitResult<Control> {
+    let span = node.span();
+    let result = match node {
         StatementNode::Return(node) => emit_return_statement(emitter, node),
         StatementNode::Declare(node) => emit_declare_statement(emitter, node),
         StatementNode::Expression(node) => emit_expression_statement(emitter, node),
@@ -29,28 +39,40 @@ pub fn emit_statement(
         StatementNode::For(node) => emit_for_statement(emitter, node),
         StatementNode::Struct(node) => emit_struct_statement(emitter, node),
         StatementNode::Enum(node) => emit_enum_statement(emitter, node),
-        StatementNode::Undetermined(node) => emit_undetermined_statement(emitter, node),
-        StatementNode::Empty => Control::Continue,
-        _ => panic!("TODO"),
-    }
+        StatementNode::Typedef(node) => emit_typedef(emitter, node, emit_declare_variable),
+        StatementNode::Pragma(_) => Ok(Control::Continue),
+        StatementNode::Empty(_) => Ok(Control::Continue),
+        StatementNode::Error(node) => Err(node.diagnostic),
+        StatementNode::Case(_) | StatementNode::Default(_) => Err(Diagnostic::error(
+            "a case or default label can only appear directly in a switch statement",
+        )),
+    };
+    result.map_err(|error| error.or_at(span))
 }
 
-fn emit_return_statement(emitter: &mut Emitter, node: ReturnStatementNode) -> Control {
-    let return_value = emit_expression(emitter, node.expression);
+fn emit_return_statement(emitter: &mut Emitter, node: ReturnStatementNode) -> EmitResult<Control> {
+    let return_value = emit_expression(emitter, node.expression)?;
     let function = emitter.module.get_last_function().expect("a function");
-    let return_type = function.get_return_type();
-    match return_type {
-        BasicTypeEnum::IntType(int_type) => {
-            let ret = load_value(emitter, return_value);
-            let ret = cast_to_int(emitter, int_type, ret);
+    let name = function.get_name().to_string_lossy();
+    let return_type = match emitter.env.get_global(&name) {
+        Some(Other::Function(CType::Function { return_type, .. })) => *return_type,
+        _ => unreachable!("a function is declared before its body is emitted"),
+    };
+    match function.get_return_type() {
+        BasicTypeEnum::IntType(_) => {
+            let (ret, ctype) = load_operand(emitter, &return_value)?;
+            let ret = convert(emitter, ret, &ctype, &return_type)?;
             emitter.builder.build_return(Some(&ret));
         }
-        _ => panic!("TODO"),
+        _ => return Err(unsupported("returning a non-integer value")),
     }
-    Control::Break
+    Ok(Control::Break)
 }
 
-pub fn alloca_from_basic_type(emitter: &mut Emitter, basic_type: BasicTypeEnum) -> PointerValue {
+pub fn alloca_from_basic_type(
+    emitter: &mut Emitter,
+    basic_type: BasicTypeEnum,
+) -> EmitResult<PointerValue> {
     let alloca = match basic_type {
         BasicTypeEnum::IntType(int_type) => emitter.builder.build_alloca(int_type, "alloca_int"),
         BasicTypeEnum::FloatType(float_type) => {
@@ -65,66 +87,96 @@ pub fn alloca_from_basic_type(emitter: &mut Emitter, basic_type: BasicTypeEnum)
         BasicTypeEnum::StructType(struct_type) => {
             emitter.builder.build_alloca(struct_type, "alloca_struct")
         }
-        _ => panic!(format!("TODO {:?}", basic_type)),
+        _ => return Err(unsupported("a variable of this type")),
     };
-    alloca
+    Ok(alloca)
 }
 
-fn emit_declare_statement(emitter: &mut Emitter, node: DeclareStatementNode) -> Control {
-    let node = node.declare_variable_node;
+pub type DeclareFn = fn(&mut Emitter, DeclareVariableNode) -> EmitResult<()>;
+
+fn emit_declare_statement(
+    emitter: &mut Emitter,
+    node: DeclareStatementNode,
+) -> EmitResult<Control> {
+    for declare_variable_node in node.declare_variable_nodes {
+        let span = declare_variable_node.span.clone();
+        emit_declare_variable(emitter, declare_variable_node).map_err(|error| error.or_at(span))?;
+    }
+    Ok(Control::Continue)
+}
+
+fn emit_declare_variable(emitter: &mut Emitter, node: DeclareVariableNode) -> EmitResult<()> {
+    let initializer = match node.initializer {
+        Some(Initializer::List(list)) => {
+            return emit_initialized_variable(emitter, node.identifier, &node.value_type, list)
+        }
+        Some(Initializer::Expression(expression)) => Some(expression),
+        None => None,
+    };
+    // a struct, which may be named with a typedef
+    if let CType::Struct(Some(tag)) = emitter.env.unqualified(&node.value_type)? {
+        emit_struct_declare(emitter, node.identifier, &tag)?;
+        return Ok(());
+    }
 
     let identifier = node.identifier;
-    let value_type = emitter.env.get_type_from_string(&node.value_type.clone());
+    let ctype = emitter.env.resolve_typedefs(&node.value_type)?;
+    let value_type = emitter.env.get_type(&emitter.context, &ctype)?;
 
-    let alloca = if let Some(node) = node.initialize_expression {
-        emit_expression(emitter, node)
-    } else {
-        alloca_from_basic_type(emitter, value_type)
+    let variable = Operand {
+        alloca: alloca_from_basic_type(emitter, value_type)?,
+        ctype,
     };
+    if let Some(node) = initializer {
+        let span = node.span();
+        let value = emit_expression(emitter, node)?;
+        let (value, ctype) = load_operand(emitter, &value)?;
+        store_converted(emitter, &variable, value, &ctype).map_err(|e| e.or_at(span))?;
+    }
     emitter
         .env
-        .insert_new_other(identifier, Other::Variable(alloca));
-    Control::Continue
+        .insert_new_other(identifier, Other::Variable(variable.alloca, variable.ctype))?;
+    Ok(())
 }
 
-fn emit_expression_statement(emitter: &mut Emitter, node: ExpressionStatementNode) -> Control {
-    emit_expression(emitter, node.expression);
-    Control::Continue
+fn emit_expression_statement(
+    emitter: &mut Emitter,
+    node: ExpressionStatementNode,
+) -> EmitResult<Control> {
+    emit_expression(emitter, node.expression)?;
+    Ok(Control::Continue)
 }
 
 fn emit_compound_statement(
     emitter: &mut Emitter,
     node: CompoundStatementNode,
     next_block: NextBlock,
-) -> Control {
+) -> EmitResult<Control> {
     let mut statements = node.statements;
-    let scope = Scope::new(emitter);
+    let scope = Scope::new();
     emitter.env.push_scope(scope);
     let mut control = Control::Continue;
     while let Some(statement) = statements.pop_front() {
-        control = emit_statement(emitter, statement, next_block.clone());
+        control = emit_statement(emitter, statement, next_block.clone())?;
         match control {
             Control::Continue => continue,
             Control::Break => break,
         }
     }
     emitter.env.pop_scope();
-    control
+    Ok(control)
 }
 
 fn emit_if_statement(
     emitter: &mut Emitter,
     node: IfStatementNode,
     next_block: NextBlock,
-) -> Control {
+) -> EmitResult<Control> {
     // ---- condition ---- ifthen ---- ifcont
     //          ┗------------------------┛
     //
-    let function = match emitter.module.get_last_function() {
-        Some(func) => func,
-        None => panic!(),
-    };
-    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression);
+    let function = emitter.module.get_last_function().expect("a function");
+    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression)?;
     match node.else_block {
         Some(else_block) => {
             let then_bb = function.append_basic_block("ifthen");
@@ -136,14 +188,14 @@ fn emit_if_statement(
                 .build_conditional_branch(condition_val, &else_bb, &then_bb);
 
             emitter.builder.position_at_end(&then_bb);
-            let control_if = emit_statement(emitter, *node.block, next_block.clone());
+            let control_if = emit_statement(emitter, *node.block, next_block.clone())?;
             if control_if == Control::Continue {
                 let cont_bb = lazy_cont_bb.force();
                 emitter.builder.build_unconditional_branch(&cont_bb);
             }
 
             emitter.builder.position_at_end(&else_bb);
-            let control_else = emit_statement(emitter, *else_block, next_block);
+            let control_else = emit_statement(emitter, *else_block, next_block)?;
             if control_else == Control::Continue {
                 let cont_bb = lazy_cont_bb.force();
                 emitter.builder.build_unconditional_branch(&cont_bb);
@@ -152,9 +204,9 @@ fn emit_if_statement(
             if control_if != Control::Break || control_else != Control::Break {
                 let cont_bb = lazy_cont_bb.force();
                 emitter.builder.position_at_end(&cont_bb);
-                Control::Continue
+                Ok(Control::Continue)
             } else {
-                Control::Break
+                Ok(Control::Break)
             }
         }
         None => {
@@ -166,22 +218,19 @@ fn emit_if_statement(
                 .build_conditional_branch(condition_val, &cont_bb, &then_bb);
 
             emitter.builder.position_at_end(&then_bb);
-            emit_statement(emitter, *node.block, next_block);
+            emit_statement(emitter, *node.block, next_block)?;
             emitter.builder.build_unconditional_branch(&cont_bb);
 
             emitter.builder.position_at_end(&cont_bb);
-            Control::Continue
+            Ok(Control::Continue)
         }
     }
 }
 
-fn emit_while_statement(emitter: &mut Emitter, node: WhileStatementNode) -> Control {
+fn emit_while_statement(emitter: &mut Emitter, node: WhileStatementNode) -> EmitResult<Control> {
     // ---- comp ---- then ---- cont
     //       ┗--------------------┛
-    let function = match emitter.module.get_last_function() {
-        Some(func) => func,
-        None => panic!(),
-    };
+    let function = emitter.module.get_last_function().expect("a function");
     let comp_bb = function.append_basic_block("comp");
     let then_bb = function.append_basic_block("then");
     let cont_bb = function.append_basic_block("cont");
@@ -190,7 +239,7 @@ fn emit_while_statement(emitter: &mut Emitter, node: WhileStatementNode) -> Cont
 
     emitter.builder.position_at_end(&comp_bb);
 
-    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression);
+    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression)?;
     emitter
         .builder
         .build_conditional_branch(condition_val, &cont_bb, &then_bb);
@@ -200,61 +249,62 @@ fn emit_while_statement(emitter: &mut Emitter, node: WhileStatementNode) -> Cont
         break_block: Some(&cont_bb),
         continue_block: Some(&comp_bb),
     };
-    emit_statement(emitter, *node.block, next_blocks);
+    emit_statement(emitter, *node.block, next_blocks)?;
     emitter.builder.build_unconditional_branch(&comp_bb);
 
     emitter.builder.position_at_end(&cont_bb);
-    Control::Continue
+    Ok(Control::Continue)
 }
 
 fn emit_break_statement(
     emitter: &mut Emitter,
     _node: BreakStatementNode,
     next_block: NextBlock,
-) -> Control {
+) -> EmitResult<Control> {
     match next_block.break_block {
         Some(next_block) => emitter.builder.build_unconditional_branch(next_block),
-        None => panic!("unexpected break"),
+        None => {
+            return Err(Diagnostic::error(
+                "a break statement not within a loop or switch",
+            ))
+        }
     };
-    Control::Break
+    Ok(Control::Break)
 }
 
 fn emit_continue_statement(
     emitter: &mut Emitter,
     _node: ContinueStatementNode,
     next_block: NextBlock,
-) -> Control {
+) -> EmitResult<Control> {
     match next_block.continue_block {
         Some(next_block) => emitter.builder.build_unconditional_branch(next_block),
-        None => panic!("unexpected break"),
+        None => return Err(Diagnostic::error("a continue statement not within a loop")),
     };
-    Control::Break
+    Ok(Control::Break)
 }
 
-fn emit_for_statement(emitter: &mut Emitter, node: ForStatementNode) -> Control {
+fn emit_for_statement(emitter: &mut Emitter, node: ForStatementNode) -> EmitResult<Control> {
     // setup
-    let function = match emitter.module.get_last_function() {
-        Some(func) => func,
-        None => panic!(),
-    };
+    let function = emitter.module.get_last_function().expect("a function");
     let comp_bb = function.append_basic_block("comp");
     let then_bb = function.append_basic_block("then");
     let thir_bb = function.append_basic_block("thir");
     let cont_bb = function.append_basic_block("cont");
 
     // emit first statement
-    let scope = Scope::new(emitter);
+    let scope = Scope::new();
     emitter.env.push_scope(scope);
     let next_blocks = NextBlock {
         break_block: None,
         continue_block: None,
     };
-    emit_statement(emitter, *node.first_statement, next_blocks);
+    emit_statement(emitter, *node.first_statement, next_blocks)?;
     emitter.builder.build_unconditional_branch(&comp_bb);
 
     // check condition
     emitter.builder.position_at_end(&comp_bb);
-    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression);
+    let condition_val = emit_condition_expression_eq(emitter, node.condition_expression)?;
     emitter
         .builder
         .build_conditional_branch(condition_val, &cont_bb, &then_bb);
@@ -265,96 +315,193 @@ fn emit_for_statement(emitter: &mut Emitter, node: ForStatementNode) -> Control
         break_block: Some(&cont_bb),
         continue_block: Some(&thir_bb),
     };
-    emit_statement(emitter, *node.block, next_blocks);
+    emit_statement(emitter, *node.block, next_blocks)?;
     emitter.builder.build_unconditional_branch(&thir_bb);
 
     emitter.builder.position_at_end(&thir_bb);
-    emit_expression(emitter, node.loop_expression);
+    emit_expression(emitter, node.loop_expression)?;
     emitter.builder.build_unconditional_branch(&comp_bb);
 
     emitter.builder.position_at_end(&cont_bb);
 
     emitter.env.pop_scope();
-    Control::Continue
+    Ok(Control::Continue)
 }
 
-fn emit_struct_statement(emitter: &mut Emitter, node: StructStatementNode) -> Control {
+fn emit_struct_statement(emitter: &mut Emitter, node: StructStatementNode) -> EmitResult<Control> {
     match node {
         StructStatementNode::Definition(node) => {
-            let mut field_names: Vec<String> = Vec::new();
-            let mut field_types: Vec<BasicTypeEnum> = Vec::new();
-            for (field_name, field_type) in node.members {
-                field_names.push(field_name);
-                let field_type: BasicTypeEnum = emitter.env.get_type_from_string(&field_type);
-                field_types.push(field_type);
-            }
-            let struct_type = emitter.context.struct_type(&field_types, false);
-            let struct_value = Struct {
-                names: field_names,
-                struct_type,
+            let tag = match node.tag.clone() {
+                Some(tag) => tag,
+                None => return Err(unsupported("a struct without a tag outside a typedef")),
             };
-            emitter
-                .env
-                .insert_new_tag(node.identifier, Tag::Struct(struct_value));
+            emit_struct_definition(emitter, tag, node)?;
         }
         StructStatementNode::Declare(node) => {
-            let declare_variable_node = node.declare_variable_node;
-            let identifier: String = declare_variable_node.identifier;
-            let value_type = declare_variable_node.value_type;
-            let (struct_type, field_names) = match emitter.env.get_tag(&value_type) {
-                Some(struct_type) => match struct_type {
-                    Tag::Struct(struct_type) => (struct_type.struct_type, struct_type.names),
-                },
-                None => panic!("unexpected"),
-            };
-            let alloca = alloca_from_basic_type(emitter, struct_type.into());
-            emitter
-                .env
-                .insert_new_other(identifier.clone(), Other::Variable(alloca));
-            for (i, field_name) in field_names.into_iter().enumerate() {
-                let field_type = match struct_type.get_field_type_at_index(i as u32) {
-                    Some(field_type) => field_type,
-                    None => panic!("unexpected"),
-                };
-                let alloca = alloca_from_basic_type(emitter, field_type);
-                let field_identifier = identifier.clone() + "." + &field_name;
-                emitter
-                    .env
-                    .insert_new_other(field_identifier, Other::Variable(alloca));
-            }
+            emit_declare_statement(emitter, *node)?;
         }
     }
-    Control::Continue
+    Ok(Control::Continue)
+}
+
+fn emit_struct_definition(
+    emitter: &mut Emitter,
+    tag: String,
+    node: StructDefinitionNode,
+) -> EmitResult<()> {
+    let mut field_names: Vec<String> = Vec::new();
+    let mut field_ctypes: Vec<CType> = Vec::new();
+    let mut field_types: Vec<BasicTypeEnum> = Vec::new();
+    for member in node.members {
+        field_types.push(emitter.env.get_type(&emitter.context, &member.value_type)?);
+        field_ctypes.push(emitter.env.resolve_typedefs(&member.value_type)?);
+        field_names.push(member.identifier);
+    }
+    let struct_type = emitter.context.struct_type(&field_types, false);
+    let struct_value = Struct {
+        names: field_names,
+        types: field_ctypes,
+        struct_type,
+    };
+    emitter.env.insert_new_tag(tag, Tag::Struct(struct_value))?;
+    Ok(())
+}
+
+/// Allocate the struct and each of its fields, which are found as `var.field`,
+/// and return the fields.
+pub fn emit_struct_declare(
+    emitter: &mut Emitter,
+    identifier: String,
+    tag: &str,
+) -> EmitResult<Vec<PointerValue>> {
+    let (struct_type, field_names, field_ctypes) = match emitter.env.get_tag(tag) {
+        Some(Tag::Struct(struct_type)) => (
+            struct_type.struct_type,
+            struct_type.names,
+            struct_type.types,
+        ),
+        None => return Err(Diagnostic::error(format!("unknown struct \"{}\"", tag))),
+    };
+    let alloca = alloca_from_basic_type(emitter, struct_type.into())?;
+    let ctype = CType::Struct(Some(tag.to_string()));
+    emitter
+        .env
+        .insert_new_other(identifier.clone(), Other::Variable(alloca, ctype))?;
+    let mut fields = Vec::new();
+    for (i, (field_name, field_ctype)) in field_names.into_iter().zip(field_ctypes).enumerate() {
+        let field_type = struct_type
+            .get_field_type_at_index(i as u32)
+            .expect("a field type for each field name");
+        let alloca = alloca_from_basic_type(emitter, field_type)?;
+        let field_identifier = identifier.clone() + "." + &field_name;
+        emitter
+            .env
+            .insert_new_other(field_identifier, Other::Variable(alloca, field_ctype))?;
+        fields.push(alloca);
+    }
+    Ok(fields)
 }
 
-fn emit_enum_statement(emitter: &mut Emitter, node: EnumStatementNode) -> Control {
+fn emit_enum_statement(emitter: &mut Emitter, node: EnumStatementNode) -> EmitResult<Control> {
     match node {
         EnumStatementNode::Definition(node) => {
-            let _tag = node.tag;
-            let enums: Vec<DeclareVariableNode> = node.enums;
-
-            for declare_variable_node in enums {
-                emit_declare_statement(
-                    emitter,
-                    DeclareStatementNode {
-                        declare_variable_node,
-                    },
-                );
+            emit_enum_definition(emitter, node, emit_declare_variable)
+        }
+        EnumStatementNode::Declare(node) => emit_declare_statement(emitter, *node),
+    }
+}
+
+/// Declare the enumerators as variables with `declare`, which is different at
+/// file scope.
+fn emit_enum_definition(
+    emitter: &mut Emitter,
+    node: EnumDefinitionNode,
+    declare: DeclareFn,
+) -> EmitResult<Control> {
+    let enums: Vec<DeclareVariableNode> = node.enums;
+
+    // an enumerator without a value is one more than the previous one
+    let mut next_value: Option<u64> = Some(0);
+    for mut declare_variable_node in enums {
+        let span = declare_variable_node.span.clone();
+        match &declare_variable_node.initializer {
+            Some(Initializer::Expression(ExpressionNode::Token(TokenNode {
+                token: Token::IntNum(num, _),
+            }))) => {
+                let literal = parse_int_literal(num)
+                    .map_err(|message| Diagnostic::error(message).at(span.clone()))?;
+                next_value = Some(literal.value + 1);
+            }
+            Some(_) => next_value = None,
+            None => {
+                let value = next_value.ok_or_else(|| {
+                    Diagnostic::error(
+                        "an enumerator without a value must follow one set to an integer constant",
+                    )
+                    .at(span.clone())
+                })?;
+                let debug_info = DebugInfo {
+                    file: span.file,
+                    start: span.start,
+                    end: span.end,
+                    s: value.to_string(),
+                    presumed: span.presumed.clone(),
+                    comments: Vec::new(),
+                };
+                declare_variable_node.initializer =
+                    Some(Initializer::Expression(ExpressionNode::Token(TokenNode {
+                        token: Token::IntNum(value.to_string(), debug_info),
+                    })));
+                next_value = Some(value + 1);
             }
-            Control::Continue
         }
-        EnumStatementNode::Declare(node) => emit_declare_statement(emitter, node),
+        declare(emitter, declare_variable_node).map_err(|error| error.or_at(span))?;
     }
+    Ok(Control::Continue)
 }
 
-fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Control {
+/// Define the struct or enum of a typedef, and declare the name for the type.
+pub fn emit_typedef(
+    emitter: &mut Emitter,
+    node: TypedefNode,
+    declare: DeclareFn,
+) -> EmitResult<Control> {
+    // the parser gives a function declared with a function type its parameters
+    if node.parameters.is_some() {
+        return Ok(Control::Continue);
+    }
+    let mut value_type = node.value_type;
+    match node.definition {
+        Some(TypeDefinitionNode::Struct(definition)) => {
+            let tag = match definition.tag.clone() {
+                Some(tag) => tag,
+                None => {
+                    // a struct without a tag is known by the typedef only
+                    let tag = format!("typedef {}", node.identifier);
+                    *value_type.base_mut() = CType::Struct(Some(tag.clone()));
+                    tag
+                }
+            };
+            emit_struct_definition(emitter, tag, definition)?;
+        }
+        Some(TypeDefinitionNode::Enum(definition)) => {
+            emit_enum_definition(emitter, definition, declare)?;
+        }
+        None => (),
+    }
+    let resolved = emitter.env.resolve_typedefs(&value_type)?;
+    emitter.env.get_type(&emitter.context, &resolved)?;
+    emitter
+        .env
+        .insert_new_other(node.identifier, Other::Typedef(resolved))?;
+    Ok(Control::Continue)
+}
+
+fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> EmitResult<Control> {
     let condition_expression = node.condition_expression;
     let statements = node.statements.statements;
 
-    let function = match emitter.module.get_last_function() {
-        Some(func) => func,
-        None => panic!(),
-    };
+    let function = emitter.module.get_last_function().expect("a function");
 
     // create basic blocks
     let mut cmp_bbs: VecDeque<BasicBlock> = VecDeque::new();
@@ -381,7 +528,11 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
                 cmp_bbs.push_back(cmp_bb);
                 need_cmp_bb = false;
             }
-            _ => panic!("TODO"),
+            _ => {
+                return Err(unsupported(
+                    "a statement before the first case label of a switch",
+                ))
+            }
         }
     }
     let cont_bb = function.append_basic_block("cont");
@@ -422,8 +573,8 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
         &cont_bb
     };
 
-    let condition_val_alloca = emit_expression(emitter, condition_expression);
-    let condition_val = load_value(emitter, condition_val_alloca);
+    let condition = emit_expression(emitter, condition_expression)?;
+    let condition_val = load_value(emitter, condition.alloca)?;
     emitter.builder.build_unconditional_branch(first_cmp_bb_ref);
 
     // cmp
@@ -443,13 +594,20 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
         match statements_for_cmp.pop_front() {
             Some(statement) => match statement {
                 StatementNode::Case(statement) => {
-                    let case_condition_val_alloca =
-                        emit_expression(emitter, statement.condition_expression);
-                    let case_condition_val = load_value(emitter, case_condition_val_alloca);
+                    let case_condition = emit_expression(emitter, statement.condition_expression)?;
+                    let case_condition_val = load_value(emitter, case_condition.alloca)?;
+                    let condition_val = *condition_val.as_int_value();
+                    let case_condition_val = cast_to_int(
+                        emitter,
+                        condition_val.get_type(),
+                        case_condition_val,
+                        &case_condition.ctype,
+                        &condition.ctype,
+                    )?;
                     let cmp_val = emitter.builder.build_int_compare(
                         IntPredicate::EQ,
-                        *case_condition_val.as_int_value(),
-                        *condition_val.as_int_value(),
+                        case_condition_val,
+                        condition_val,
                         "caseeq",
                     );
                     emitter
@@ -459,9 +617,9 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
                 StatementNode::Default(_) => {
                     emitter.builder.build_unconditional_branch(case_bb_ref);
                 }
-                _ => panic!("TODO"),
+                _ => unreachable!("only case and default labels get a compare block"),
             },
-            None => panic!("unexpected"),
+            None => unreachable!("a label for each compare block"),
         }
     }
 
@@ -472,9 +630,9 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
         let block_statements = match statement {
             StatementNode::Case(statement) => statement.statements,
             StatementNode::Default(statement) => statement.statements,
-            _ => panic!(),
+            _ => unreachable!("only case and default labels get a case block"),
         };
-        let next_bb_ref = match emit_compound_statement(emitter, block_statements, nb.clone()) {
+        let next_bb_ref = match emit_compound_statement(emitter, block_statements, nb.clone())? {
             Control::Continue => {
                 if idx + 1 < statements_size {
                     &case_bbs[idx + 1]
@@ -488,33 +646,5 @@ fn emit_switch_statement(emitter: &mut Emitter, node: SwitchStatementNode) -> Co
     }
 
     emitter.builder.position_at_end(&cont_bb);
-    Control::Continue
-}
-
-fn emit_undetermined_statement(emitter: &mut Emitter, node: UndeterminedStatementNode) -> Control {
-    let mut tokens = node.tokens;
-    let token = tokens.peek().expect("expect at least one token");
-    let identifier = match token {
-        Token::Ide(identifier, _) => identifier,
-        _ => panic!("unexpected"),
-    };
-    let is_type = match emitter.env.get_other(&identifier) {
-        Some(other) => match other {
-            Other::Type(_) => true,
-            _ => false,
-        },
-        None => false,
-    };
-
-    let node = if is_type {
-        StatementNode::Declare(DeclareStatementNode::new(&mut tokens))
-    } else {
-        StatementNode::Expression(ExpressionStatementNode::new(&mut tokens))
-    };
-
-    let next_block = NextBlock {
-        break_block: None,
-        continue_block: None,
-    };
-    emit_statement(emitter, node, next_block)
+    Ok(Control::Continue)
 }
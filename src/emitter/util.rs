@@ -1,9 +1,14 @@
 use inkwell::types::{AnyTypeEnum, BasicTypeEnum, FloatType, IntType};
-use inkwell::values::{BasicValueEnum, FloatValue, InstructionOpcode, IntValue, PointerValue};
-use inkwell::{AddressSpace, IntPredicate};
+use inkwell::values::{
+    ArrayValue, BasicValueEnum, FloatValue, InstructionOpcode, IntValue, PointerValue,
+};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
 
+use crate::diagnostic::Diagnostic;
 use crate::emitter::expression::*;
 use crate::emitter::*;
+use crate::lexer::literal::*;
+use crate::parser::ctype::CType;
 use crate::parser::expression::*;
 
 pub struct Delay<T, F> {
@@ -28,39 +33,244 @@ where
     }
 }
 
-pub fn cast_to_int(emitter: &mut Emitter, int_type: IntType, value: BasicValueEnum) -> IntValue {
+/// An error for a construct the emitter cannot compile yet.
+pub fn unsupported(what: &str) -> Diagnostic {
+    Diagnostic::error(format!("{} is not supported yet", what))
+}
+
+/// Whether an integer of `ctype` is unsigned, which decides how it is extended,
+/// compared and converted to and from a floating value.
+pub fn is_unsigned(ctype: &CType) -> bool {
+    match ctype {
+        CType::Int { is_signed, .. } => !is_signed,
+        CType::Qualified(ctype, _) => is_unsigned(ctype),
+        _ => false,
+    }
+}
+
+/// Convert `value` of the C type `from` to `int_type`, the type of `to`.
+pub fn cast_to_int(
+    emitter: &mut Emitter,
+    int_type: IntType,
+    value: BasicValueEnum,
+    from: &CType,
+    to: &CType,
+) -> EmitResult<IntValue> {
+    let bit_width = int_type.get_bit_width();
     match value {
-        BasicValueEnum::IntValue(value) => value,
+        // a _Bool is 1 for any value but zero
+        BasicValueEnum::IntValue(value) if bit_width == 1 => Ok(emitter.builder.build_int_compare(
+            IntPredicate::NE,
+            value,
+            value.get_type().const_zero(),
+            "tobool",
+        )),
+        BasicValueEnum::FloatValue(value) if bit_width == 1 => {
+            Ok(emitter.builder.build_float_compare(
+                FloatPredicate::ONE,
+                value,
+                value.get_type().const_zero(),
+                "tobool",
+            ))
+        }
+        BasicValueEnum::IntValue(value) => {
+            let value_width = value.get_type().get_bit_width();
+            Ok(if value_width == bit_width {
+                value
+            } else if value_width > bit_width {
+                emitter.builder.build_int_truncate(value, int_type, "trunc")
+            } else if is_unsigned(from) {
+                emitter.builder.build_int_z_extend(value, int_type, "zext")
+            } else {
+                emitter.builder.build_int_s_extend(value, int_type, "sext")
+            })
+        }
         BasicValueEnum::FloatValue(value) => {
-            let opcode = InstructionOpcode::FPToSI;
-            emitter
+            let opcode = if is_unsigned(to) {
+                InstructionOpcode::FPToUI
+            } else {
+                InstructionOpcode::FPToSI
+            };
+            Ok(emitter
                 .builder
                 .build_cast(opcode, value, int_type, "convert")
-                .into_int_value()
+                .into_int_value())
         }
-        _ => panic!(format!("TODO: {:?}", value)),
+        _ => Err(Diagnostic::error(
+            "a value of this type cannot be converted to an integer",
+        )),
     }
 }
 
+/// Convert `value` of the C type `from` to `float_type`.
 pub fn cast_to_float(
     emitter: &mut Emitter,
     float_type: FloatType,
     value: BasicValueEnum,
-) -> FloatValue {
+    from: &CType,
+) -> EmitResult<FloatValue> {
     match value {
         BasicValueEnum::IntValue(value) => {
-            let opcode = InstructionOpcode::SIToFP;
-            emitter
+            let opcode = if is_unsigned(from) {
+                InstructionOpcode::UIToFP
+            } else {
+                InstructionOpcode::SIToFP
+            };
+            Ok(emitter
                 .builder
                 .build_cast(opcode, value, float_type, "convert")
-                .into_float_value()
+                .into_float_value())
+        }
+        BasicValueEnum::FloatValue(value) => {
+            if value.get_type() == float_type {
+                Ok(value)
+            } else {
+                Ok(emitter
+                    .builder
+                    .build_float_cast(value, float_type, "convert"))
+            }
+        }
+        _ => Err(Diagnostic::error(
+            "a value of this type cannot be converted to a floating type",
+        )),
+    }
+}
+
+/// Convert `value` of the C type `from` to `to`, as an assignment does.
+pub fn convert(
+    emitter: &mut Emitter,
+    value: BasicValueEnum,
+    from: &CType,
+    to: &CType,
+) -> EmitResult<BasicValueEnum> {
+    let value = match emitter.env.get_type(&emitter.context, to)? {
+        BasicTypeEnum::IntType(int_type) => cast_to_int(emitter, int_type, value, from, to)?.into(),
+        BasicTypeEnum::FloatType(float_type) => {
+            cast_to_float(emitter, float_type, value, from)?.into()
+        }
+        BasicTypeEnum::PointerType(pointer_type) => match value {
+            BasicValueEnum::PointerValue(pointer_value)
+                if pointer_type.get_element_type()
+                    == pointer_value.get_type().get_element_type() =>
+            {
+                pointer_value.into()
+            }
+            _ => {
+                return Err(Diagnostic::error(
+                    "assigning a value of a different type to a pointer",
+                ))
+            }
+        },
+        _ => return Err(unsupported("assigning to a value of this type")),
+    };
+    Ok(value)
+}
+
+/// `value` of the C type `ctype` after the integer promotions, which widen an
+/// integer narrower than int to int.
+pub fn promote(
+    emitter: &mut Emitter,
+    value: BasicValueEnum,
+    ctype: &CType,
+) -> EmitResult<(BasicValueEnum, CType)> {
+    match value {
+        BasicValueEnum::IntValue(int_value) if int_value.get_type().get_bit_width() < 32 => {
+            let int_type = emitter.context.i32_type();
+            let value = cast_to_int(emitter, int_type, value, ctype, &CType::int())?;
+            Ok((value.into(), CType::int()))
         }
-        BasicValueEnum::FloatValue(value) => value,
-        _ => panic!("TODO"),
+        value => Ok((value, ctype.clone())),
+    }
+}
+
+/// The type the usual arithmetic conversions bring both operands of a binary
+/// operator to, or None unless both have arithmetic types. It is the wider one
+/// after the integer promotions, and unsigned where the unsigned one is as wide.
+pub fn arithmetic_type(lhs: &CType, rhs: &CType) -> Option<CType> {
+    let ctype = match (promoted_type(lhs)?, promoted_type(rhs)?) {
+        (CType::Float { bits: lhs }, CType::Float { bits: rhs }) => {
+            CType::Float { bits: lhs.max(rhs) }
+        }
+        (float @ CType::Float { .. }, _) | (_, float @ CType::Float { .. }) => float,
+        (
+            CType::Int {
+                bits: lhs_bits,
+                is_signed: lhs_signed,
+            },
+            CType::Int {
+                bits: rhs_bits,
+                is_signed: rhs_signed,
+            },
+        ) => {
+            let (signed_bits, unsigned_bits) = match (lhs_signed, rhs_signed) {
+                (true, false) => (lhs_bits, rhs_bits),
+                (false, true) => (rhs_bits, lhs_bits),
+                _ => {
+                    return Some(CType::Int {
+                        bits: lhs_bits.max(rhs_bits),
+                        is_signed: lhs_signed,
+                    })
+                }
+            };
+            CType::Int {
+                bits: signed_bits.max(unsigned_bits),
+                is_signed: signed_bits > unsigned_bits,
+            }
+        }
+        _ => unreachable!("promoted_type only returns integer and floating types"),
+    };
+    Some(ctype)
+}
+
+/// `ctype` after the integer promotions, or None unless it is arithmetic.
+fn promoted_type(ctype: &CType) -> Option<CType> {
+    match ctype {
+        CType::Qualified(ctype, _) => promoted_type(ctype),
+        CType::Int { bits, .. } if *bits < 32 => Some(CType::int()),
+        // enumerated types are int
+        CType::Enum(_) => Some(CType::int()),
+        CType::Int { .. } | CType::Float { .. } => Some(ctype.clone()),
+        _ => None,
+    }
+}
+
+/// An integer constant and its C type.
+pub fn const_int_literal(emitter: &mut Emitter, spelling: &str) -> (IntValue, CType) {
+    let literal =
+        parse_int_literal(spelling).expect("the lexer only accepts valid integer constants");
+    let int_type = match literal.literal_type.bit_width() {
+        32 => emitter.context.i32_type(),
+        _ => emitter.context.i64_type(),
+    };
+    let ctype = CType::Int {
+        bits: literal.literal_type.bit_width(),
+        is_signed: !literal.literal_type.is_unsigned(),
+    };
+    (int_type.const_int(literal.value, false), ctype)
+}
+
+/// A floating constant as `float` or `double`, and its C type. `long double` is
+/// lowered as `double`.
+pub fn const_float_literal(emitter: &mut Emitter, spelling: &str) -> (FloatValue, CType) {
+    let literal =
+        parse_float_literal(spelling).expect("the lexer only accepts valid floating constants");
+    match literal.literal_type {
+        FloatLiteralType::Float => (
+            emitter.context.f32_type().const_float(literal.value),
+            CType::Float { bits: 32 },
+        ),
+        FloatLiteralType::Double => (
+            emitter.context.f64_type().const_float(literal.value),
+            CType::Float { bits: 64 },
+        ),
+        FloatLiteralType::LongDouble => (
+            emitter.context.f64_type().const_float(literal.value),
+            CType::Float { bits: 128 },
+        ),
     }
 }
 
-pub fn store_value(emitter: &mut Emitter, value: BasicValueEnum) -> PointerValue {
+pub fn store_value(emitter: &mut Emitter, value: BasicValueEnum) -> EmitResult<PointerValue> {
     let alloca = emitter
         .builder
         .build_alloca(value.get_type(), "store_value");
@@ -81,13 +291,25 @@ pub fn store_value(emitter: &mut Emitter, value: BasicValueEnum) -> PointerValue
             value.into_array_value();
             emitter.builder.build_store(alloca, value);
         }
-        _ => panic!("TODO"),
+        _ => return Err(unsupported("storing a struct value")),
     };
-    alloca
+    Ok(alloca)
 }
 
-pub fn load_value(emitter: &mut Emitter, alloca: PointerValue) -> BasicValueEnum {
-    match alloca.get_type().get_element_type() {
+/// Store `value` of the C type `from` in `target`, converted to its type.
+pub fn store_converted(
+    emitter: &mut Emitter,
+    target: &Operand,
+    value: BasicValueEnum,
+    from: &CType,
+) -> EmitResult<()> {
+    let value = convert(emitter, value, from, &target.ctype)?;
+    emitter.builder.build_store(target.alloca, value);
+    Ok(())
+}
+
+pub fn load_value(emitter: &mut Emitter, alloca: PointerValue) -> EmitResult<BasicValueEnum> {
+    let value = match alloca.get_type().get_element_type() {
         AnyTypeEnum::IntType(_int_type) => BasicValueEnum::IntValue(
             emitter
                 .builder
@@ -112,35 +334,114 @@ pub fn load_value(emitter: &mut Emitter, alloca: PointerValue) -> BasicValueEnum
                 .build_load(alloca, "alloca")
                 .into_array_value(),
         ),
-        _ => panic!(format!("TODO {:?}", alloca.get_type().get_element_type())),
+        _ => return Err(unsupported("loading a struct or function value")),
+    };
+    Ok(value)
+}
+
+/// The value of `operand` and its type, where an array is the pointer to its
+/// first element as C converts it.
+pub fn load_operand(
+    emitter: &mut Emitter,
+    operand: &Operand,
+) -> EmitResult<(BasicValueEnum, CType)> {
+    match emitter.env.unqualified(&operand.ctype)? {
+        CType::Array(element, _) => {
+            let const_zero = emitter.context.i32_type().const_zero();
+            let pointer = unsafe {
+                emitter
+                    .builder
+                    .build_gep(operand.alloca, &[const_zero, const_zero], "decay")
+            };
+            Ok((pointer.into(), CType::Pointer(element)))
+        }
+        _ => Ok((load_value(emitter, operand.alloca)?, operand.ctype.clone())),
     }
 }
 
-pub fn to_fn_type(type_enum: BasicTypeEnum, param_types: Vec<BasicTypeEnum>) -> FunctionType {
-    match type_enum {
-        BasicTypeEnum::IntType(t) => t.fn_type(&param_types, false),
-        _ => panic!("TODO"),
+/// The zero of `basic_type`, which is null for a pointer.
+pub fn const_zero(basic_type: BasicTypeEnum) -> BasicValueEnum {
+    match basic_type {
+        BasicTypeEnum::IntType(t) => t.const_zero().into(),
+        BasicTypeEnum::FloatType(t) => t.const_zero().into(),
+        BasicTypeEnum::PointerType(t) => t.const_null().into(),
+        BasicTypeEnum::ArrayType(t) => t.const_zero().into(),
+        BasicTypeEnum::StructType(t) => t.const_zero().into(),
+        BasicTypeEnum::VectorType(_) => unreachable!("the emitter makes no vector types"),
+    }
+}
+
+/// A constant array of `values`, which are all of `element_type`.
+pub fn const_array(element_type: BasicTypeEnum, values: Vec<BasicValueEnum>) -> ArrayValue {
+    match element_type {
+        BasicTypeEnum::IntType(t) => t.const_array(
+            &values
+                .into_iter()
+                .map(|v| v.into_int_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::FloatType(t) => t.const_array(
+            &values
+                .into_iter()
+                .map(|v| v.into_float_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::PointerType(t) => t.const_array(
+            &values
+                .into_iter()
+                .map(|v| v.into_pointer_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::ArrayType(t) => t.const_array(
+            &values
+                .into_iter()
+                .map(|v| v.into_array_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::StructType(t) => t.const_array(
+            &values
+                .into_iter()
+                .map(|v| v.into_struct_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::VectorType(_) => unreachable!("the emitter makes no vector types"),
     }
 }
 
-pub fn to_array_type(type_enum: BasicTypeEnum, size: u32) -> BasicTypeEnum {
+pub fn to_fn_type(
+    type_enum: BasicTypeEnum,
+    param_types: Vec<BasicTypeEnum>,
+    is_var_args: bool,
+) -> EmitResult<FunctionType> {
     match type_enum {
-        BasicTypeEnum::IntType(t) => BasicTypeEnum::ArrayType(t.array_type(size)),
-        BasicTypeEnum::ArrayType(t) => BasicTypeEnum::ArrayType(t.array_type(size)),
-        _ => panic!(format!("TODO: {:?}", type_enum)),
+        BasicTypeEnum::IntType(t) => Ok(t.fn_type(&param_types, is_var_args)),
+        _ => Err(unsupported("a function returning a non-integer type")),
     }
 }
 
-pub fn to_pointer_type(type_enum: BasicTypeEnum) -> BasicTypeEnum {
+pub fn to_array_type(type_enum: BasicTypeEnum, size: u32) -> EmitResult<BasicTypeEnum> {
     match type_enum {
-        BasicTypeEnum::IntType(t) => BasicTypeEnum::PointerType(t.ptr_type(AddressSpace::Generic)),
-        BasicTypeEnum::ArrayType(t) => {
-            BasicTypeEnum::PointerType(t.ptr_type(AddressSpace::Generic))
-        }
-        _ => panic!("TODO"),
+        BasicTypeEnum::IntType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
+        BasicTypeEnum::FloatType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
+        BasicTypeEnum::PointerType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
+        BasicTypeEnum::ArrayType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
+        BasicTypeEnum::StructType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
+        BasicTypeEnum::VectorType(t) => Ok(BasicTypeEnum::ArrayType(t.array_type(size))),
     }
 }
 
+pub fn to_pointer_type(type_enum: BasicTypeEnum) -> EmitResult<BasicTypeEnum> {
+    let address_space = AddressSpace::Generic;
+    Ok(BasicTypeEnum::PointerType(match type_enum {
+        BasicTypeEnum::IntType(t) => t.ptr_type(address_space),
+        BasicTypeEnum::FloatType(t) => t.ptr_type(address_space),
+        BasicTypeEnum::PointerType(t) => t.ptr_type(address_space),
+        BasicTypeEnum::ArrayType(t) => t.ptr_type(address_space),
+        BasicTypeEnum::StructType(t) => t.ptr_type(address_space),
+        BasicTypeEnum::VectorType(t) => t.ptr_type(address_space),
+    }))
+}
+
 pub fn is_assign_operator(operator: &str) -> bool {
     match operator {
         "=" | "+=" => true,
@@ -154,10 +455,10 @@ pub fn emit_compare_expression_int(
     lhs: IntValue,
     rhs: IntValue,
 ) -> IntValue {
-    let fn_value = match emitter.module.get_function(operator) {
-        Some(value) => value,
-        None => panic!(format!("call of undeclared function {}", operator)),
-    };
+    let fn_value = emitter
+        .module
+        .get_function(operator)
+        .expect("the comparison builtins are declared before the program");
     let arguments: Vec<BasicValueEnum> = vec![lhs.into(), rhs.into()];
     let func_call_site = emitter
         .builder
@@ -165,7 +466,7 @@ pub fn emit_compare_expression_int(
     let val = func_call_site
         .try_as_basic_value()
         .left()
-        .unwrap()
+        .expect("the comparison builtins return int")
         .into_int_value();
     val
 }
@@ -173,11 +474,14 @@ pub fn emit_compare_expression_int(
 pub fn emit_condition_expression_eq(
     emitter: &mut Emitter,
     condition_expression: ExpressionNode,
-) -> IntValue {
-    let condition_val_alloca = emit_expression(emitter, condition_expression);
-    let condition_val = load_value(emitter, condition_val_alloca).into_int_value();
-    let const_zero = emitter.context.i32_type().const_int(0, false);
-    emitter
+) -> EmitResult<IntValue> {
+    let condition = emit_expression(emitter, condition_expression)?;
+    let condition_val = match load_value(emitter, condition.alloca)? {
+        BasicValueEnum::IntValue(value) => value,
+        _ => return Err(unsupported("a condition that is not an integer")),
+    };
+    let const_zero = condition_val.get_type().const_zero();
+    Ok(emitter
         .builder
-        .build_int_compare(IntPredicate::EQ, condition_val, const_zero, "foreq")
+        .build_int_compare(IntPredicate::EQ, condition_val, const_zero, "foreq"))
 }
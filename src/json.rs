@@ -0,0 +1,218 @@
+//! JSON for the tokens and the syntax tree, for tools that do not link to the
+//! compiler.
+//!
+//! # Schema
+//!
+//! A document is an object with the version of the schema and the tokens or the
+//! program:
+//!
+//! ```json
+//! {"version": 9, "tokens": [Token, ...]}
+//! {"version": 9, "program": ProgramNode}
+//! ```
+//!
+//! Everything in it is the Rust type of the same name written as follows:
+//!
+//! - A struct is an object with its fields, named as in Rust. `ProgramNode` is
+//!   `{"declares": [DeclareNode, ...]}`.
+//! - An enum variant is an object with the name of the variant as its only key.
+//!   The value is the field of the variant, or an array of its fields when it has
+//!   more than one. `Token::Ide` is `{"Ide": ["main", DebugInfo]}` and
+//!   `Token::Semi` is `{"Semi": DebugInfo}`. A variant with named fields has an
+//!   object of them, as `CType::Int` is `{"Int": {"bits": 32, "is_signed":
+//!   true}}`. A variant without fields, such as `CType::Void`, is the string
+//!   `"Void"`.
+//! - `Option` is the value or `null`, `Box` is the value, and `Vec` and `VecDeque`
+//!   are arrays.
+//! - `Tokens` is the array of its tokens. The value of `Token::Str` is the array
+//!   of the bytes of the string, as `{"Str": [[104, 105], DebugInfo]}` for
+//!   `"hi"`.
+//! - `FileId` is a number: `0` is the file compiled, and the files it includes
+//!   follow in the order they were read.
+//! - `DebugInfo` is `{"file": 0, "start": 4, "end": 8, "s": "main", "presumed":
+//!   null, "comments": []}`, where `start` and `end` are byte offsets in the file
+//!   and `s` is the text of the token. `comments` are the `DebugInfo` of the
+//!   comments before the token, when the lexer keeps them. `presumed` is
+//!   `{"file": "a.c", "line": 3}` after a `#line` directive.
+//! - `Span` is `{"file": 0, "start": 4, "end": 8, "presumed": null}`.
+//!
+//! The version changes when a document of the previous version would be read
+//! differently or not at all.
+//!
+//! A program is checked for what the parser ensures but the types do not, such
+//! as the width of an integer type or the operator of an expression, so that a
+//! malformed one is an error here rather than a panic in a later pass.
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::literal::*;
+use crate::lexer::token::{Token, Tokens};
+use crate::parser::ctype::CType;
+use crate::parser::declare::*;
+use crate::parser::expression::*;
+use crate::parser::visitor::*;
+use crate::parser::ProgramNode;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const SCHEMA_VERSION: u32 = 9;
+
+#[derive(Serialize, Deserialize)]
+struct TokensDocument<T> {
+    version: u32,
+    tokens: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProgramDocument<T> {
+    version: u32,
+    program: T,
+}
+
+pub fn tokens_to_json(tokens: &Tokens) -> String {
+    to_json(&TokensDocument {
+        version: SCHEMA_VERSION,
+        tokens,
+    })
+}
+
+pub fn tokens_from_json(json: &str) -> Result<Tokens, Diagnostic> {
+    let document: TokensDocument<Tokens> = from_json(json, "tokens")?;
+    Ok(document.tokens)
+}
+
+pub fn program_to_json(node: &ProgramNode) -> String {
+    to_json(&ProgramDocument {
+        version: SCHEMA_VERSION,
+        program: node,
+    })
+}
+
+pub fn program_from_json(json: &str) -> Result<ProgramNode, Diagnostic> {
+    let document: ProgramDocument<ProgramNode> = from_json(json, "AST")?;
+    let mut validator = Validator { error: None };
+    validator.visit_program(&document.program);
+    match validator.error {
+        Some(error) => Err(error),
+        None => Ok(document.program),
+    }
+}
+
+fn to_json<T: Serialize>(document: &T) -> String {
+    serde_json::to_string_pretty(document).expect("the syntax tree to have only JSON types")
+}
+
+/// Read a document of `kind`, after checking its version so that a document of
+/// another version is not reported as malformed.
+fn from_json<T: DeserializeOwned>(json: &str, kind: &str) -> Result<T, Diagnostic> {
+    let invalid =
+        |err: serde_json::Error| Diagnostic::error(format!("invalid {} JSON: {}", kind, err));
+    let value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    match value.get("version").and_then(|version| version.as_u64()) {
+        Some(version) if version == u64::from(SCHEMA_VERSION) => (),
+        Some(version) => {
+            return Err(Diagnostic::error(format!(
+                "unsupported {} JSON version {}, expected {}",
+                kind, version, SCHEMA_VERSION
+            )))
+        }
+        None => {
+            return Err(Diagnostic::error(format!(
+                "invalid {} JSON: missing field `version`",
+                kind
+            )))
+        }
+    }
+    serde_json::from_value(value).map_err(invalid)
+}
+
+/// Finds the first node of a program that the parser would not have built.
+struct Validator {
+    error: Option<Diagnostic>,
+}
+impl Validator {
+    fn fail(&mut self, message: String) {
+        if self.error.is_none() {
+            self.error = Some(Diagnostic::error(format!("invalid AST JSON: {}", message)));
+        }
+    }
+    fn ctype(&mut self, ctype: &CType) {
+        match ctype {
+            CType::Int { bits, .. } if ![1, 8, 16, 32, 64].contains(bits) => {
+                self.fail(format!("an integer type of {} bits", bits))
+            }
+            CType::Float { bits } if ![32, 64, 128].contains(bits) => {
+                self.fail(format!("a floating type of {} bits", bits))
+            }
+            CType::Pointer(ctype) | CType::Array(ctype, _) | CType::Qualified(ctype, _) => {
+                self.ctype(ctype)
+            }
+            CType::Function {
+                return_type,
+                parameters,
+                ..
+            } => {
+                self.ctype(return_type);
+                for parameter in parameters {
+                    self.ctype(parameter);
+                }
+            }
+            _ => (),
+        }
+    }
+    fn not_operator(&mut self, token: &Token, kind: &str) {
+        let s = &token.debug_info().s;
+        self.fail(format!("\"{}\" is not a {} operator", s, kind));
+    }
+}
+impl Visitor for Validator {
+    fn visit_function(&mut self, node: &FunctionNode) {
+        self.ctype(&node.return_type);
+        walk_function(self, node)
+    }
+    fn visit_declare_variable(&mut self, node: &DeclareVariableNode) {
+        self.ctype(&node.value_type);
+        walk_declare_variable(self, node)
+    }
+    fn visit_typedef(&mut self, node: &TypedefNode) {
+        self.ctype(&node.value_type);
+        walk_typedef(self, node)
+    }
+    fn visit_bin_exp(&mut self, node: &BinExpNode) {
+        if !matches!(node.op.token, Token::Op(_, _)) {
+            self.not_operator(&node.op.token, "binary");
+        }
+        walk_bin_exp(self, node)
+    }
+    fn visit_prefix(&mut self, node: &PrefixNode) {
+        match &node.prefix.token {
+            Token::PrefixOp(op, _) if ["&", "*", "+", "-", "++", "--"].contains(&op.as_str()) => (),
+            token => self.not_operator(token, "prefix"),
+        }
+        walk_prefix(self, node)
+    }
+    fn visit_suffix(&mut self, node: &SuffixNode) {
+        match &node.suffix.token {
+            Token::SuffixOp(op, _) if op == "++" || op == "--" => (),
+            token => self.not_operator(token, "suffix"),
+        }
+        walk_suffix(self, node)
+    }
+    fn visit_access(&mut self, node: &AccessNode) {
+        if !matches!(node.access_identifier, Token::Ide(_, _)) {
+            let s = &node.access_identifier.debug_info().s;
+            self.fail(format!("\"{}\" is not a member name", s));
+        }
+        walk_access(self, node)
+    }
+    fn visit_token(&mut self, node: &TokenNode) {
+        let result = match &node.token {
+            Token::IntNum(spelling, _) => parse_int_literal(spelling).map(|_| ()),
+            Token::FloatNum(spelling, _) => parse_float_literal(spelling).map(|_| ()),
+            _ => Ok(()),
+        };
+        if let Err(message) = result {
+            self.fail(message);
+        }
+    }
+}
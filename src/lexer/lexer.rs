@@ -1,149 +1,265 @@
+use crate::diagnostic::{Diagnostic, Span};
+use crate::lexer::literal::*;
+use crate::lexer::scanner::{Lexeme, Scanner};
 use crate::lexer::token::{DebugInfo, Token, Tokens};
+use crate::source_map::FileId;
 use log::debug;
-use regex::Regex;
 
 pub struct Lexer {
-    re: Regex,
-    names: Vec<&'static str>,
+    /// Keep comments as trivia in the `DebugInfo` of the token that follows them,
+    /// instead of discarding them. Comments after the last token are kept in
+    /// `Tokens::trailing_comments`.
+    pub keep_comments: bool,
 }
 
 impl Lexer {
     // static constructor
     pub fn new() -> Lexer {
-        let token_patterns = vec![
-            ("COLON", r":"),
-            ("QUESTION", r"\?"),
-            ("FLOAT_NUM", r"(\d+\.\d+)"),
-            ("INT_NUM", r"(\d+)"),
-            ("SEMI", r";"),
-            ("VA", r"\.\.\."),
-            ("STR", r#"".+""#),
-            ("STRUCT", r"(struct)"),
-            ("ENUM", r"(enum)"),
-            ("SWITCH", r"switch"),
-            ("CONST", r"const"),
-            ("EXTERN", r"extern"),
-            ("CASE", r"case"),
-            ("DEFAULT", r"default"),
-            ("RETURN", r"return"),
-            ("IF", r"if"),
-            ("ELSE", r"else"),
-            ("WHILE", r"while"),
-            ("BREAK", r"break"),
-            ("CONTINUE", r"continue"),
-            ("FOR", r"for"),
-            ("SQUARE_E", r"\]"),
-            ("PAREN_E", r"\)"),
-            ("CURLY_S", r"\{"),
-            ("CURLY_E", r"\}"),
-            ("ANDOP", r"(&&)"),
-            ("PREFIXOP", r"((\s|^)\+\+)|&"),
-            ("SUFFIXOP", r"(\+\+|\[|\()|\."),
-            ("OP", r"((\|\|)|(\+=)|(==)|>|<|\+|-|\*|=|,)"),
-            ("CHAR", r"'[A-Za-z_0-9.]'"),
-            ("IDE", r"[A-Za-z_][A-Za-z_0-9]*"),
-        ];
-        let re = make_regex(&token_patterns);
-        let names = get_names(&token_patterns);
-        let re = Regex::new(&re).expect("something went wrong making the regex");
-        Lexer { re, names }
-    }
-    pub fn lex(&self, code: String) -> Tokens {
-        let mut code = code;
-        let tokens = self.tokenize(&mut code);
-        tokens
+        Lexer {
+            keep_comments: false,
+        }
     }
-    fn tokenize(&self, code: &mut String) -> Tokens {
-        let mut tokens: Vec<Token> = Vec::new();
 
-        // get token's location
-        let mut locations: Vec<DebugInfo> = Vec::new();
-        for mat in self.re.find_iter(&code) {
-            let location = DebugInfo {
-                start: mat.start(),
-                end: mat.end(),
-                s: mat.as_str().to_string(),
+    pub fn lex(&self, code: String) -> Result<Tokens, Vec<Diagnostic>> {
+        self.lex_mapped(code, &|_| ())
+    }
+    /// Lex code whose locations are not the source ones, such as preprocessed code.
+    /// `map` rewrites the location of every token as soon as it is read, so that
+    /// errors found while lexing point into the source too.
+    ///
+    /// Lexing goes on after an error, so that every error of the code is returned.
+    pub fn lex_mapped(
+        &self,
+        code: String,
+        map: &dyn Fn(&mut DebugInfo),
+    ) -> Result<Tokens, Vec<Diagnostic>> {
+        let mut builder = TokenBuilder::new(self.keep_comments);
+        for (lexeme, start, end) in Scanner::new(&code) {
+            let mut debug_info = DebugInfo {
+                file: FileId::default(),
+                start,
+                end,
+                s: code[start..end].to_string(),
+                presumed: None,
+                comments: Vec::new(),
             };
-            locations.push(location);
+            map(&mut debug_info);
+            builder.push(lexeme, debug_info);
+        }
+        builder.finish()
+    }
+}
+
+/// Turns lexemes into tokens. Comments are attached to the next token, keywords
+/// are told apart from identifiers and adjacent string literals are concatenated.
+pub struct TokenBuilder {
+    tokens: Vec<Token>,
+    comments: Vec<DebugInfo>,
+    keep_comments: bool,
+    errors: Vec<Diagnostic>,
+}
+impl TokenBuilder {
+    pub fn new(keep_comments: bool) -> TokenBuilder {
+        TokenBuilder {
+            tokens: Vec::new(),
+            comments: Vec::new(),
+            keep_comments,
+            errors: Vec::new(),
         }
-        for (i, caps) in self.re.captures_iter(&code).enumerate() {
-            let mut typ = String::from("nil");
-            let val = String::from(&caps[0]);
-            for name in &self.names {
-                if caps.name(name).is_some() {
-                    typ = name.to_string();
+    }
+    fn error(&mut self, message: String, debug_info: &DebugInfo) {
+        self.errors
+            .push(Diagnostic::error(message).at(Span::from(debug_info)));
+    }
+    /// Push the lexeme whose text is `debug_info.s`.
+    pub fn push(&mut self, lexeme: Lexeme, debug_info: DebugInfo) {
+        let mut debug_info = debug_info;
+        match lexeme {
+            Lexeme::Comment => {
+                if self.keep_comments {
+                    self.comments.push(debug_info);
                 }
+                return;
             }
-            let debug_info = locations.clone().remove(i);
-            match typ.as_ref() {
-                "COLON" => tokens.push(Token::Colon(debug_info)),
-                "QUESTION" => tokens.push(Token::Question(debug_info)),
-                "FLOAT_NUM" => tokens.push(Token::FloatNum(val, debug_info)),
-                "INT_NUM" => tokens.push(Token::IntNum(val, debug_info)),
-                "SEMI" => tokens.push(Token::Semi(debug_info)),
-                "VA" => tokens.push(Token::Va(debug_info)),
-                "STRUCT" => tokens.push(Token::Struct(debug_info)),
-                "STR" => {
-                    let val = val.trim_matches('\"').to_string();
-                    tokens.push(Token::Str(val, debug_info));
-                }
-                "ENUM" => tokens.push(Token::Enum(debug_info)),
-                "SWITCH" => tokens.push(Token::Switch(debug_info)),
-                "CONST" => (),
-                "EXTERN" => tokens.push(Token::Extern(debug_info)),
-                "CASE" => tokens.push(Token::Case(debug_info)),
-                "DEFAULT" => tokens.push(Token::Default(debug_info)),
-                "RETURN" => tokens.push(Token::Return(debug_info)),
-                "IF" => tokens.push(Token::If(debug_info)),
-                "ELSE" => tokens.push(Token::Else(debug_info)),
-                "WHILE" => tokens.push(Token::While(debug_info)),
-                "BREAK" => tokens.push(Token::Break(debug_info)),
-                "CONTINUE" => tokens.push(Token::Continue(debug_info)),
-                "FOR" => tokens.push(Token::For(debug_info)),
-                "SQUARE_E" => tokens.push(Token::SquareE(debug_info)),
-                "PAREN_E" => tokens.push(Token::ParenE(debug_info)),
-                "CURLY_S" => tokens.push(Token::CurlyS(debug_info)),
-                "CURLY_E" => tokens.push(Token::CurlyE(debug_info)),
-                "ANDOP" => {
-                    let val = val.trim_end().to_string();
-                    tokens.push(Token::Op(val.clone(), debug_info))
-                }
-                "PREFIXOP" => {
-                    let val = val.trim_start().to_string();
-                    tokens.push(Token::PrefixOp(val, debug_info));
-                }
-                "SUFFIXOP" => {
-                    let val = val.trim_end().to_string();
-                    tokens.push(Token::SuffixOp(val, debug_info));
-                }
-                "OP" => {
-                    let val = val.trim_end().to_string();
-                    tokens.push(Token::Op(val.clone(), debug_info))
-                }
-                "IDE" => tokens.push(Token::Ide(val, debug_info)),
-                "CHAR" => {
-                    let chars: Vec<&str> = val.split("'").collect();
-                    let num: i32 = chars[1].chars().into_iter().nth(0).unwrap() as i32;
-                    tokens.push(Token::IntNum(num.to_string(), debug_info))
+            Lexeme::UnterminatedComment => {
+                return self.error(String::from("unterminated comment"), &debug_info)
+            }
+            _ => debug_info.comments = self.comments.drain(..).collect(),
+        }
+        match lexeme {
+            Lexeme::Pragma => {
+                let val = debug_info
+                    .s
+                    .trim_start_matches(|c| c == '#' || c == ' ' || c == '\t');
+                let val = val["pragma".len()..].trim().to_string();
+                self.tokens.push(Token::Pragma(val, debug_info));
+            }
+            Lexeme::Colon => self.tokens.push(Token::Colon(debug_info)),
+            Lexeme::Question => self.tokens.push(Token::Question(debug_info)),
+            Lexeme::FloatNum => match parse_float_literal(&debug_info.s) {
+                Ok(_) => self
+                    .tokens
+                    .push(Token::FloatNum(debug_info.s.clone(), debug_info)),
+                Err(message) => self.error(message, &debug_info),
+            },
+            Lexeme::IntNum => match parse_int_literal(&debug_info.s) {
+                Ok(_) => self
+                    .tokens
+                    .push(Token::IntNum(debug_info.s.clone(), debug_info)),
+                Err(message) => self.error(message, &debug_info),
+            },
+            Lexeme::Semi => self.tokens.push(Token::Semi(debug_info)),
+            Lexeme::Va => self.tokens.push(Token::Va(debug_info)),
+            Lexeme::Str => {
+                let string = match parse_string_literal(&debug_info.s) {
+                    Ok(string) => string,
+                    Err(message) => return self.error(message, &debug_info),
+                };
+                // adjacent string literals are concatenated
+                if let Some(Token::Str(previous, previous_info)) = self.tokens.last_mut() {
+                    previous.extend(string);
+                    previous_info.end = debug_info.end;
+                    previous_info.s.push(' ');
+                    previous_info.s.push_str(&debug_info.s);
+                    return;
                 }
-                _ => panic!("This is not an expected panic"),
+                self.tokens.push(Token::Str(string, debug_info));
+            }
+            Lexeme::UnterminatedStr => self.error(
+                String::from("missing terminating \" character"),
+                &debug_info,
+            ),
+            Lexeme::UnterminatedChar => {
+                self.error(String::from("missing terminating ' character"), &debug_info)
             }
+            Lexeme::SquareS => self.tokens.push(Token::SquareS(debug_info)),
+            Lexeme::SquareE => self.tokens.push(Token::SquareE(debug_info)),
+            Lexeme::ParenS => self.tokens.push(Token::ParenS(debug_info)),
+            Lexeme::ParenE => self.tokens.push(Token::ParenE(debug_info)),
+            Lexeme::Dot => self.tokens.push(Token::Dot(debug_info)),
+            Lexeme::CurlyS => self.tokens.push(Token::CurlyS(debug_info)),
+            Lexeme::CurlyE => self.tokens.push(Token::CurlyE(debug_info)),
+            Lexeme::Op => {
+                let val = debug_info.s.trim_end().to_string();
+                self.tokens.push(Token::Op(val, debug_info))
+            }
+            Lexeme::Ide => {
+                let word = debug_info.s.clone();
+                self.tokens.push(keyword_or_identifier(word, debug_info))
+            }
+            Lexeme::Char => match parse_char_literal(&debug_info.s) {
+                Ok(num) => self.tokens.push(Token::IntNum(num.to_string(), debug_info)),
+                Err(message) => self.error(message, &debug_info),
+            },
+            Lexeme::Unknown => self.error(
+                format!("unknown character '{}'", debug_info.s.escape_default()),
+                &debug_info,
+            ),
+            Lexeme::Comment | Lexeme::UnterminatedComment => unreachable!(),
+        }
+    }
+    /// The tokens, or every error found while lexing.
+    pub fn finish(self) -> Result<Tokens, Vec<Diagnostic>> {
+        debug!("tokens:  {:?}", self.tokens);
+        if !self.errors.is_empty() {
+            return Err(self.errors);
         }
-        debug!("tokens:  {:?}", tokens);
-        Tokens { tokens }
+        let mut tokens = Tokens::new(self.tokens);
+        tokens.trailing_comments = self.comments;
+        Ok(tokens)
     }
 }
-fn make_regex(token_patterns: &Vec<(&str, &str)>) -> String {
-    token_patterns
-        .into_iter()
-        .map(|pattern| format!("(?P<{}>{})", pattern.0, pattern.1))
-        .collect::<Vec<String>>()
-        .join("|")
+
+/// Every keyword of C11. A word is a keyword only when the whole identifier matches.
+pub const KEYWORDS: [&str; 44] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_Bool",
+    "_Complex",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+];
+
+/// Type specifiers are looked up by name like any other type, so they stay identifiers.
+const TYPE_SPECIFIERS: [&str; 12] = [
+    "char",
+    "double",
+    "float",
+    "int",
+    "long",
+    "short",
+    "signed",
+    "unsigned",
+    "void",
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word)
 }
 
-fn get_names<'a, 'b>(token_patterns: &Vec<(&'a str, &'b str)>) -> Vec<&'a str> {
-    token_patterns
-        .into_iter()
-        .map(|pattern| pattern.0)
-        .collect()
+pub fn is_type_specifier(word: &str) -> bool {
+    TYPE_SPECIFIERS.contains(&word)
+}
+
+fn keyword_or_identifier(word: String, debug_info: DebugInfo) -> Token {
+    match word.as_ref() {
+        "struct" => Token::Struct(debug_info),
+        "enum" => Token::Enum(debug_info),
+        "switch" => Token::Switch(debug_info),
+        "extern" => Token::Extern(debug_info),
+        "case" => Token::Case(debug_info),
+        "default" => Token::Default(debug_info),
+        "return" => Token::Return(debug_info),
+        "if" => Token::If(debug_info),
+        "else" => Token::Else(debug_info),
+        "while" => Token::While(debug_info),
+        "break" => Token::Break(debug_info),
+        "continue" => Token::Continue(debug_info),
+        "for" => Token::For(debug_info),
+        _ if is_keyword(&word) && !TYPE_SPECIFIERS.contains(&word.as_ref()) => {
+            Token::Keyword(word, debug_info)
+        }
+        _ => Token::Ide(word, debug_info),
+    }
 }
@@ -0,0 +1,295 @@
+/// Type of an integer constant, chosen by its suffix, radix and value.
+/// `long` is 64 bits wide.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntLiteralType {
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+}
+impl IntLiteralType {
+    pub fn bit_width(&self) -> u32 {
+        match self {
+            IntLiteralType::Int | IntLiteralType::UnsignedInt => 32,
+            _ => 64,
+        }
+    }
+    pub fn is_unsigned(&self) -> bool {
+        match self {
+            IntLiteralType::UnsignedInt
+            | IntLiteralType::UnsignedLong
+            | IntLiteralType::UnsignedLongLong => true,
+            _ => false,
+        }
+    }
+    fn max_value(&self) -> u64 {
+        match self {
+            IntLiteralType::Int => i32::max_value() as u64,
+            IntLiteralType::UnsignedInt => u32::max_value() as u64,
+            IntLiteralType::Long | IntLiteralType::LongLong => i64::max_value() as u64,
+            IntLiteralType::UnsignedLong | IntLiteralType::UnsignedLongLong => u64::max_value(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IntLiteral {
+    pub value: u64,
+    pub literal_type: IntLiteralType,
+}
+
+/// Parse an integer constant such as `42`, `0x1F`, `017`, `0b101`, `10u` or `3LL`.
+///
+/// The type is the first one of the candidate list that can represent the value
+/// (C11 6.4.4.1). Octal, hex and binary constants may also take the unsigned types.
+pub fn parse_int_literal(spelling: &str) -> Result<IntLiteral, String> {
+    let digits_end = spelling
+        .rfind(|c: char| !"uUlL".contains(c))
+        .map_or(0, |idx| idx + 1);
+    let (body, suffix) = spelling.split_at(digits_end);
+    let (digits, radix) = if body.starts_with("0x") || body.starts_with("0X") {
+        (&body[2..], 16)
+    } else if body.starts_with("0b") || body.starts_with("0B") {
+        (&body[2..], 2)
+    } else if body.len() > 1 && body.starts_with('0') {
+        (&body[1..], 8)
+    } else {
+        (body, 10)
+    };
+    if digits.is_empty() {
+        return Err(format!("invalid integer constant \"{}\"", spelling));
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!(
+            "invalid digit \"{}\" in integer constant \"{}\"",
+            c, spelling
+        ));
+    }
+    let value = match u64::from_str_radix(digits, radix) {
+        Ok(value) => value,
+        Err(_) => return Err(format!("integer constant \"{}\" is too large", spelling)),
+    };
+
+    // `lL` is not a valid long long suffix
+    if suffix.contains("lL") || suffix.contains("Ll") {
+        return Err(format!("invalid suffix \"{}\" on integer constant", suffix));
+    }
+    use IntLiteralType::*;
+    let is_decimal = radix == 10;
+    let candidates: &[IntLiteralType] = match (suffix.to_ascii_lowercase().as_ref(), is_decimal) {
+        ("", true) => &[Int, Long, LongLong],
+        ("", false) => &[
+            Int,
+            UnsignedInt,
+            Long,
+            UnsignedLong,
+            LongLong,
+            UnsignedLongLong,
+        ],
+        ("u", _) => &[UnsignedInt, UnsignedLong, UnsignedLongLong],
+        ("l", true) => &[Long, LongLong],
+        ("l", false) => &[Long, UnsignedLong, LongLong, UnsignedLongLong],
+        ("ul", _) | ("lu", _) => &[UnsignedLong, UnsignedLongLong],
+        ("ll", true) => &[LongLong],
+        ("ll", false) => &[LongLong, UnsignedLongLong],
+        ("ull", _) | ("llu", _) => &[UnsignedLongLong],
+        _ => return Err(format!("invalid suffix \"{}\" on integer constant", suffix)),
+    };
+    match candidates.iter().find(|t| value <= t.max_value()) {
+        Some(literal_type) => Ok(IntLiteral {
+            value,
+            literal_type: *literal_type,
+        }),
+        None => Err(format!(
+            "integer constant \"{}\" is too large for its type",
+            spelling
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FloatLiteralType {
+    Float,
+    Double,
+    /// Lowered the same as `double`.
+    LongDouble,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FloatLiteral {
+    pub value: f64,
+    pub literal_type: FloatLiteralType,
+}
+
+/// Parse a floating constant such as `1.5`, `.5`, `2.f`, `1e-9`, `3.0L` or `0x1.8p3`.
+/// Unsuffixed constants are `double`.
+pub fn parse_float_literal(spelling: &str) -> Result<FloatLiteral, String> {
+    let is_hex = spelling.starts_with("0x") || spelling.starts_with("0X");
+    let (body, literal_type) = match spelling.chars().last() {
+        Some('f') | Some('F') => (&spelling[..spelling.len() - 1], FloatLiteralType::Float),
+        Some('l') | Some('L') => (
+            &spelling[..spelling.len() - 1],
+            FloatLiteralType::LongDouble,
+        ),
+        _ => (spelling, FloatLiteralType::Double),
+    };
+    let value = if is_hex {
+        parse_hex_float(&body[2..])
+    } else if literal_type == FloatLiteralType::Float {
+        // parse as float directly to avoid rounding twice
+        body.parse::<f32>().ok().map(f64::from)
+    } else {
+        body.parse::<f64>().ok()
+    };
+    match value {
+        Some(value) => Ok(FloatLiteral {
+            value,
+            literal_type,
+        }),
+        None => Err(format!("invalid floating constant \"{}\"", spelling)),
+    }
+}
+
+/// `h.hhhp[+-]d`, where the binary exponent is required.
+fn parse_hex_float(body: &str) -> Option<f64> {
+    let p = body.find(|c| c == 'p' || c == 'P')?;
+    let (mantissa, exponent) = (&body[..p], &body[p + 1..]);
+    let exponent: i32 = exponent.parse().ok()?;
+    let (int_part, fraction) = match mantissa.find('.') {
+        Some(dot) => (&mantissa[..dot], &mantissa[dot + 1..]),
+        None => (mantissa, ""),
+    };
+    if int_part.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let mut value = 0f64;
+    for c in int_part.chars().chain(fraction.chars()) {
+        value = value * 16.0 + f64::from(c.to_digit(16)?);
+    }
+    let exponent = exponent - 4 * fraction.len() as i32;
+    Some(value * 2f64.powi(exponent))
+}
+
+/// Decode the escape sequences in the body of a string or character literal.
+/// Universal character names are encoded as UTF-8. A backslash-newline is removed.
+pub fn unescape(body: &str) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let escape = match chars.next() {
+            Some(escape) => escape,
+            None => return Err(String::from("incomplete escape sequence")),
+        };
+        let byte = match escape {
+            '\n' => continue,
+            '\'' | '"' | '?' | '\\' => escape as u8,
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'v' => 0x0b,
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                if value > 0xff {
+                    return Err(String::from("octal escape sequence out of range"));
+                }
+                value as u8
+            }
+            'x' => {
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.saturating_mul(16).saturating_add(digit);
+                    digits += 1;
+                    chars.next();
+                }
+                if digits == 0 {
+                    return Err(String::from("\\x used with no following hex digits"));
+                }
+                if value > 0xff {
+                    return Err(String::from("hex escape sequence out of range"));
+                }
+                value as u8
+            }
+            'u' | 'U' => {
+                let len = if escape == 'u' { 4 } else { 8 };
+                let digits: String = chars.by_ref().take(len).collect();
+                let code = match u32::from_str_radix(&digits, 16) {
+                    Ok(code) if digits.len() == len => code,
+                    _ => {
+                        return Err(format!(
+                            "incomplete universal character name \\{}{}",
+                            escape, digits
+                        ))
+                    }
+                };
+                // C11 6.4.3: only $, @ and ` may be named below U+00A0
+                let is_basic = code < 0xa0 && code != 0x24 && code != 0x40 && code != 0x60;
+                match std::char::from_u32(code) {
+                    Some(c) if !is_basic => {
+                        let mut buf = [0; 4];
+                        bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+                        continue;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "\\{}{} is not a valid universal character",
+                            escape, digits
+                        ))
+                    }
+                }
+            }
+            _ => return Err(format!("unknown escape sequence '\\{}'", escape)),
+        };
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+
+/// Bytes of a string literal `"..."` with its escape sequences decoded, which
+/// may be any value including zero.
+pub fn parse_string_literal(spelling: &str) -> Result<Vec<u8>, String> {
+    if spelling.len() < 2 || !spelling.starts_with('"') || !spelling.ends_with('"') {
+        return Err(String::from("missing terminating \" character"));
+    }
+    unescape(&spelling[1..spelling.len() - 1])
+}
+
+/// Value of a character constant such as `'a'`, `'\n'`, `'\x41'` or `'\''`.
+/// Each byte is taken as unsigned, and a multi-character constant packs its
+/// bytes from the most significant end as GCC does.
+pub fn parse_char_literal(spelling: &str) -> Result<u32, String> {
+    if spelling.len() < 2 || !spelling.starts_with('\'') || !spelling.ends_with('\'') {
+        return Err(String::from("missing terminating ' character"));
+    }
+    let body = &spelling[1..spelling.len() - 1];
+    let bytes = unescape(body)?;
+    match bytes.len() {
+        0 => Err(String::from("empty character constant")),
+        1..=4 => Ok(bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | u32::from(*byte))),
+        _ => Err(format!(
+            "character constant {} is too long for its type",
+            spelling
+        )),
+    }
+}
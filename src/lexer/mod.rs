@@ -1,2 +1,4 @@
 pub mod lexer;
+pub mod literal;
+pub mod scanner;
 pub mod token;
@@ -0,0 +1,283 @@
+/// What a run of source text was read as, before it becomes a `Token`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Lexeme {
+    Comment,
+    UnterminatedComment,
+    Pragma,
+    Colon,
+    Question,
+    FloatNum,
+    IntNum,
+    Semi,
+    Va,
+    Str,
+    UnterminatedStr,
+    SquareS,
+    SquareE,
+    ParenS,
+    ParenE,
+    Dot,
+    CurlyS,
+    CurlyE,
+    Op,
+    Char,
+    UnterminatedChar,
+    Ide,
+    /// A character that starts no lexeme.
+    Unknown,
+}
+
+/// Reads lexemes in one pass over the bytes of the code, yielding each one with
+/// its byte span. Whitespace is skipped, and any other character that starts no
+/// lexeme is read as `Lexeme::Unknown`.
+pub struct Scanner<'a> {
+    code: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(code: &'a str) -> Scanner<'a> {
+        Scanner {
+            code,
+            bytes: code.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn byte(&self, idx: usize) -> u8 {
+        self.bytes.get(idx).cloned().unwrap_or(0)
+    }
+    fn char_len(&self, idx: usize) -> usize {
+        self.code[idx..].chars().next().map_or(1, |c| c.len_utf8())
+    }
+    fn count_while(&self, idx: usize, predicate: fn(&u8) -> bool) -> usize {
+        self.bytes[idx.min(self.bytes.len())..]
+            .iter()
+            .take_while(|c| predicate(c))
+            .count()
+    }
+
+    /// The lexeme starting at `start` and where it ends.
+    fn lexeme(&self, start: usize) -> Option<(Lexeme, usize)> {
+        let c = self.bytes[start];
+        let next = self.byte(start + 1);
+        let single = |lexeme| Some((lexeme, start + 1));
+        match c {
+            b'/' if next == b'/' => Some((Lexeme::Comment, self.line_comment_end(start + 2))),
+            b'/' if next == b'*' => match self.code[start + 2..].find("*/") {
+                Some(idx) => Some((Lexeme::Comment, start + 2 + idx + 2)),
+                None => Some((Lexeme::UnterminatedComment, start + 2)),
+            },
+            b'#' => match self.pragma_end(start) {
+                Some(end) => Some((Lexeme::Pragma, end)),
+                None => single(Lexeme::Unknown),
+            },
+            b':' => single(Lexeme::Colon),
+            b'?' => single(Lexeme::Question),
+            b'0'..=b'9' => Some(self.number(start)),
+            b'.' if next.is_ascii_digit() => Some(self.number(start)),
+            b';' => single(Lexeme::Semi),
+            b'.' if next == b'.' && self.byte(start + 2) == b'.' => Some((Lexeme::Va, start + 3)),
+            b'"' => match self.quoted_end(start, b'"') {
+                Some(end) => Some((Lexeme::Str, end)),
+                None => single(Lexeme::UnterminatedStr),
+            },
+            b']' => single(Lexeme::SquareE),
+            b')' => single(Lexeme::ParenE),
+            b'{' => single(Lexeme::CurlyS),
+            b'}' => single(Lexeme::CurlyE),
+            b'[' => single(Lexeme::SquareS),
+            b'(' => single(Lexeme::ParenS),
+            b'.' => single(Lexeme::Dot),
+            // whether an operator is unary, binary or postfix is up to the parser
+            b'&' | b'|' | b'+' | b'-' if next == c => Some((Lexeme::Op, start + 2)),
+            b'+' | b'=' if next == b'=' => Some((Lexeme::Op, start + 2)),
+            b'&' | b'>' | b'<' | b'+' | b'-' | b'*' | b'=' | b',' => single(Lexeme::Op),
+            b'\'' => match self.quoted_end(start, b'\'') {
+                Some(end) => Some((Lexeme::Char, end)),
+                None => single(Lexeme::UnterminatedChar),
+            },
+            b'A'..=b'Z' | b'a'..=b'z' | b'_' => {
+                let len = self.count_while(start, |c| c.is_ascii_alphanumeric() || *c == b'_');
+                Some((Lexeme::Ide, start + len))
+            }
+            _ => {
+                let c = self.code[start..].chars().next().unwrap();
+                if c.is_whitespace() {
+                    None
+                } else {
+                    Some((Lexeme::Unknown, start + c.len_utf8()))
+                }
+            }
+        }
+    }
+
+    /// A line comment goes on over backslash-newlines.
+    fn line_comment_end(&self, idx: usize) -> usize {
+        let mut idx = idx;
+        while idx < self.bytes.len() {
+            match self.bytes[idx] {
+                b'\\' if self.byte(idx + 1) == b'\n' => idx += 2,
+                b'\n' => break,
+                _ => idx += 1,
+            }
+        }
+        idx
+    }
+
+    /// `#pragma` up to the end of the line.
+    fn pragma_end(&self, start: usize) -> Option<usize> {
+        let idx = start + 1 + self.count_while(start + 1, |c| *c == b' ' || *c == b'\t');
+        if !self.code[idx..].starts_with("pragma") {
+            return None;
+        }
+        let after = idx + "pragma".len();
+        let is_word = self.code[after..]
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_alphanumeric() || c == '_');
+        if is_word {
+            return None;
+        }
+        Some(
+            self.code[after..]
+                .find('\n')
+                .map_or(self.bytes.len(), |idx| after + idx),
+        )
+    }
+
+    /// End of a string literal or character constant, which may not span lines
+    /// except through an escaped newline.
+    fn quoted_end(&self, start: usize, quote: u8) -> Option<usize> {
+        let mut idx = start + 1;
+        while idx < self.bytes.len() {
+            match self.bytes[idx] {
+                c if c == quote => return Some(idx + 1),
+                b'\\' if idx + 1 < self.bytes.len() => idx += 1 + self.char_len(idx + 1),
+                b'\\' | b'\n' => return None,
+                _ => idx += self.char_len(idx),
+            }
+        }
+        None
+    }
+
+    /// Integer and floating constants. Where both could start, a floating
+    /// constant is tried first.
+    fn number(&self, start: usize) -> (Lexeme, usize) {
+        match self.float_end(start) {
+            Some(end) => (Lexeme::FloatNum, end),
+            None => (Lexeme::IntNum, self.int_end(start)),
+        }
+    }
+
+    fn float_end(&self, start: usize) -> Option<usize> {
+        let is_hex_digit: fn(&u8) -> bool = |c| c.is_ascii_hexdigit();
+        let is_digit: fn(&u8) -> bool = |c| c.is_ascii_digit();
+        if self.byte(start) == b'0' && (self.byte(start + 1) | 0x20) == b'x' {
+            // h.hhh, hhh. or hhh followed by the binary exponent
+            let idx = start + 2;
+            let int_digits = self.count_while(idx, is_hex_digit);
+            let dot = idx + int_digits;
+            let has_dot = self.byte(dot) == b'.';
+            let fraction_digits = if has_dot {
+                self.count_while(dot + 1, is_hex_digit)
+            } else {
+                0
+            };
+            let mantissas = [
+                Some(dot + 1 + fraction_digits).filter(|_| has_dot && fraction_digits > 0),
+                Some(dot + 1).filter(|_| has_dot && int_digits > 0),
+                Some(dot).filter(|_| int_digits > 0),
+            ];
+            for mantissa in mantissas.iter().filter_map(|mantissa| *mantissa) {
+                if (self.byte(mantissa) | 0x20) == b'p' {
+                    if let Some(end) = self.exponent_digits_end(mantissa + 1) {
+                        return Some(self.float_suffix_end(end));
+                    }
+                }
+            }
+        }
+        let int_digits = self.count_while(start, is_digit);
+        let dot = start + int_digits;
+        if self.byte(dot) == b'.' && (int_digits > 0 || self.byte(dot + 1).is_ascii_digit()) {
+            let mut end = dot + 1 + self.count_while(dot + 1, is_digit);
+            if (self.byte(end) | 0x20) == b'e' {
+                if let Some(exponent_end) = self.exponent_digits_end(end + 1) {
+                    end = exponent_end;
+                }
+            }
+            return Some(self.float_suffix_end(end));
+        }
+        if int_digits > 0 && (self.byte(dot) | 0x20) == b'e' {
+            if let Some(end) = self.exponent_digits_end(dot + 1) {
+                return Some(self.float_suffix_end(end));
+            }
+        }
+        None
+    }
+
+    /// `[+-]?\d+` after the `e` or `p` of an exponent.
+    fn exponent_digits_end(&self, idx: usize) -> Option<usize> {
+        let idx = match self.byte(idx) {
+            b'+' | b'-' => idx + 1,
+            _ => idx,
+        };
+        match self.count_while(idx, |c| c.is_ascii_digit()) {
+            0 => None,
+            digits => Some(idx + digits),
+        }
+    }
+
+    fn float_suffix_end(&self, idx: usize) -> usize {
+        match self.byte(idx) {
+            b'f' | b'F' | b'l' | b'L' => idx + 1,
+            _ => idx,
+        }
+    }
+
+    fn int_end(&self, start: usize) -> usize {
+        let prefix = self.byte(start + 1) | 0x20;
+        let hex_digits = self.count_while(start + 2, |c| c.is_ascii_hexdigit());
+        let binary_digits = self.count_while(start + 2, |c| *c == b'0' || *c == b'1');
+        let end = if self.byte(start) == b'0' && prefix == b'x' && hex_digits > 0 {
+            start + 2 + hex_digits
+        } else if self.byte(start) == b'0' && prefix == b'b' && binary_digits > 0 {
+            start + 2 + binary_digits
+        } else {
+            start + self.count_while(start, |c| c.is_ascii_digit())
+        };
+        // u, l, ll, ul, ull, lu or llu in either case, where ll is never mixed case
+        let long_end = |idx: usize| match (self.byte(idx), self.byte(idx + 1)) {
+            (b'l', b'l') | (b'L', b'L') => idx + 2,
+            (b'l', _) | (b'L', _) => idx + 1,
+            _ => idx,
+        };
+        match self.byte(end) {
+            b'u' | b'U' => long_end(end + 1),
+            b'l' | b'L' => {
+                let end = long_end(end);
+                match self.byte(end) {
+                    b'u' | b'U' => end + 1,
+                    _ => end,
+                }
+            }
+            _ => end,
+        }
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = (Lexeme, usize, usize);
+    fn next(&mut self) -> Option<(Lexeme, usize, usize)> {
+        while self.pos < self.bytes.len() {
+            let start = self.pos;
+            if let Some((lexeme, end)) = self.lexeme(start) {
+                self.pos = end;
+                return Some((lexeme, start, end));
+            }
+            self.pos += self.char_len(start);
+        }
+        None
+    }
+}
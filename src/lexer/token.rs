@@ -1,5 +1,7 @@
-use std::fs::File;
-use std::io::prelude::*;
+use crate::diagnostic::Span;
+use crate::source_map::FileId;
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Associativity {
@@ -13,22 +15,41 @@ pub struct Property {
     pub associativity: Associativity,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// File name and line number of a token as seen through `#line` directives.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PresumedLocation {
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DebugInfo {
+    pub file: FileId,
+    /// Byte span in the file.
     pub start: usize,
     pub end: usize,
     pub s: String,
+    pub presumed: Option<PresumedLocation>,
+    /// Comments before the token, kept when the lexer is asked to. The `s` of a
+    /// comment is its text.
+    pub comments: Vec<DebugInfo>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Token {
     FloatNum(String, DebugInfo),
     IntNum(String, DebugInfo),
     Op(String, DebugInfo),
+    /// A unary operator, which the parser makes out of an `Op` before an operand.
     PrefixOp(String, DebugInfo),
+    /// `++` or `--` after an operand, made by the parser.
     SuffixOp(String, DebugInfo),
     Ide(String, DebugInfo),
-    Str(String, DebugInfo),
+    Str(Vec<u8>, DebugInfo),
+    /// `#pragma` left by the preprocessor, with the text after `pragma`.
+    Pragma(String, DebugInfo),
+    /// A keyword without a token of its own, such as `const`, `static` or `sizeof`.
+    Keyword(String, DebugInfo),
     Struct(DebugInfo),
     Enum(DebugInfo),
     Switch(DebugInfo),
@@ -46,20 +67,24 @@ pub enum Token {
     Va(DebugInfo),
     CurlyS(DebugInfo),
     CurlyE(DebugInfo),
+    SquareS(DebugInfo),
     SquareE(DebugInfo),
+    ParenS(DebugInfo),
     ParenE(DebugInfo),
+    Dot(DebugInfo),
     Colon(DebugInfo),
     Question(DebugInfo),
 }
 impl Token {
-    pub fn get_debug_info(self) -> String {
-        let debug_info = match self.clone() {
+    pub fn debug_info(&self) -> &DebugInfo {
+        match self {
             Token::Struct(d)
             | Token::Enum(d)
             | Token::Switch(d)
             | Token::Extern(d)
             | Token::Case(d)
             | Token::Str(_, d)
+            | Token::Pragma(_, d)
             | Token::Default(d)
             | Token::Return(d)
             | Token::If(d)
@@ -71,8 +96,11 @@ impl Token {
             | Token::Semi(d)
             | Token::CurlyS(d)
             | Token::CurlyE(d)
+            | Token::SquareS(d)
             | Token::SquareE(d)
+            | Token::ParenS(d)
             | Token::ParenE(d)
+            | Token::Dot(d)
             | Token::Colon(d)
             | Token::Va(d)
             | Token::Question(d) => d,
@@ -81,38 +109,135 @@ impl Token {
             | Token::Op(_, d)
             | Token::PrefixOp(_, d)
             | Token::SuffixOp(_, d)
+            | Token::Keyword(_, d)
             | Token::Ide(_, d) => d,
-        };
-
-        let mut file = File::open("target.c").unwrap();
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
-        let contents_to_err = String::from(&contents[0..debug_info.end]);
-        let num: usize = contents_to_err.split("\n").count();
-        let splited_contents: Vec<&str> = contents.split("\n").collect();
-        format!("target.c:{}:{}", num + 1, splited_contents[num])
+        }
+    }
+    pub fn debug_info_mut(&mut self) -> &mut DebugInfo {
+        match self {
+            Token::Struct(d)
+            | Token::Enum(d)
+            | Token::Switch(d)
+            | Token::Extern(d)
+            | Token::Case(d)
+            | Token::Str(_, d)
+            | Token::Pragma(_, d)
+            | Token::Default(d)
+            | Token::Return(d)
+            | Token::If(d)
+            | Token::Else(d)
+            | Token::While(d)
+            | Token::Break(d)
+            | Token::Continue(d)
+            | Token::For(d)
+            | Token::Semi(d)
+            | Token::CurlyS(d)
+            | Token::CurlyE(d)
+            | Token::SquareS(d)
+            | Token::SquareE(d)
+            | Token::ParenS(d)
+            | Token::ParenE(d)
+            | Token::Dot(d)
+            | Token::Colon(d)
+            | Token::Va(d)
+            | Token::Question(d) => d,
+            Token::FloatNum(_, d)
+            | Token::IntNum(_, d)
+            | Token::Op(_, d)
+            | Token::PrefixOp(_, d)
+            | Token::SuffixOp(_, d)
+            | Token::Keyword(_, d)
+            | Token::Ide(_, d) => d,
+        }
+    }
+    /// `const`, `volatile` and `restrict` are accepted and ignored.
+    pub fn is_type_qualifier(&self) -> bool {
+        match self {
+            Token::Keyword(keyword, _) => match keyword.as_ref() {
+                "const" | "volatile" | "restrict" => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+    pub fn span(&self) -> Span {
+        Span::from(self.debug_info())
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// A position in `Tokens` to rewind to. Later positions compare greater.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+/// The token stream read by the parser. Reading moves a cursor forward, so every
+/// operation is O(1) and lookahead never copies the stream.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Tokens {
     pub tokens: Vec<Token>,
+    #[serde(skip)]
+    pos: usize,
+    /// Comments after the last token, kept when the lexer is asked to.
+    #[serde(skip)]
+    pub trailing_comments: Vec<DebugInfo>,
 }
 impl Tokens {
+    pub fn new(tokens: Vec<Token>) -> Tokens {
+        Tokens {
+            tokens,
+            pos: 0,
+            trailing_comments: Vec::new(),
+        }
+    }
     pub fn pop(&mut self) -> Option<Token> {
-        self.tokens.reverse();
-        let token = self.tokens.pop();
-        self.tokens.reverse();
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
         token
     }
     pub fn peek(&self) -> Option<Token> {
-        let mut tokens = self.clone();
-        tokens.pop()
+        self.tokens.get(self.pos).cloned()
+    }
+    /// The `k`th token ahead without consuming anything. `peek_nth(0)` is the next token.
+    pub fn peek_nth(&self, k: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + k)
+    }
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+    /// The span of the tokens read since `start`. When none has been read, an empty
+    /// span where the next token would start.
+    pub fn span_from(&self, start: Checkpoint) -> Span {
+        if self.pos > start.0 {
+            let first = self.tokens[start.0].span();
+            return first.to(&self.tokens[self.pos - 1].span());
+        }
+        match (self.tokens.get(self.pos), self.tokens.last()) {
+            (Some(token), _) => {
+                let span = token.span();
+                Span {
+                    end: span.start,
+                    ..span
+                }
+            }
+            (None, Some(token)) => {
+                let span = token.span();
+                Span {
+                    start: span.end,
+                    ..span
+                }
+            }
+            (None, None) => Span::default(),
+        }
     }
-    pub fn push(&mut self, token: Token) {
-        self.tokens.push(token);
+    /// Go back to a checkpoint, so that the tokens read since are read again.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
     }
-    pub fn reverse(&mut self) {
-        self.tokens.reverse()
+    pub fn skip_type_qualifiers(&mut self) {
+        while let Some(true) = self.peek_nth(0).map(|token| token.is_type_qualifier()) {
+            self.pos += 1;
+        }
     }
 }
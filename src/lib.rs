@@ -0,0 +1,8 @@
+pub mod diagnostic;
+pub mod emitter;
+pub mod json;
+pub mod lexer;
+pub mod parser;
+pub mod preprocessor;
+pub mod source_map;
+pub mod unparser;
@@ -1,41 +1,181 @@
 use std::fs;
 use std::{env, process};
 
-mod emitter;
-mod lexer;
-mod parser;
+use rexparser::diagnostic::Diagnostic;
+use rexparser::emitter::builtin::emit_builtin;
+use rexparser::emitter::Emitter;
+use rexparser::json;
+use rexparser::lexer::lexer::Lexer;
+use rexparser::parser::{parser, ProgramNode};
+use rexparser::preprocessor::Preprocessor;
+use rexparser::source_map::SourceMap;
+use rexparser::unparser;
 
-use emitter::builtin::emit_builtin;
-use emitter::Emitter;
-use lexer::lexer::Lexer;
-use parser::parser;
-use std::io::prelude::*;
+/// What the compiler writes: LLVM IR to `compiled.ll`, or JSON to stdout.
+#[derive(PartialEq, Clone, Copy)]
+enum Emit {
+    LlvmIr,
+    TokensJson,
+    AstJson,
+}
 
-fn compiler(code: String) {
-    let mut file = fs::File::create("target.c").unwrap();
-    file.write_all(code.as_bytes()).unwrap();
+/// Print the diagnostics with the source lines they point at.
+fn report(diagnostics: &[Diagnostic], source_map: &SourceMap) {
+    for diagnostic in diagnostics {
+        eprint!("{}", diagnostic.render(source_map));
+    }
+}
+
+/// Compile the file. `source_map` gets the files read, to report the errors with.
+fn compiler(
+    filepath: &str,
+    code: String,
+    include_dirs: Vec<String>,
+    emit: Emit,
+    source_map: &mut SourceMap,
+) -> Result<(), Vec<Diagnostic>> {
+    let mut preprocessor = Preprocessor::new();
+    for dir in include_dirs {
+        preprocessor.add_include_dir(dir);
+    }
+    let preprocessed = preprocessor.preprocess(filepath, code);
+    *source_map = preprocessor.source_map().clone();
+    report(preprocessor.warnings(), source_map);
+    let preprocessed = preprocessed.map_err(|error| vec![error])?;
 
     let lexer = Lexer::new();
-    let mut tokens = lexer.lex(code);
-    //dbg!(tokens.clone());
-    let node = parser(&mut tokens);
-    // dbg!(node.clone());
+    let mut tokens = lexer.lex_mapped(preprocessed.code.clone(), &|debug_info| {
+        preprocessed.map_debug_info(debug_info)
+    })?;
+    if emit == Emit::TokensJson {
+        println!("{}", json::tokens_to_json(&tokens));
+        return Ok(());
+    }
+    let node = parser(&mut tokens)?;
+    emit_node(node, emit)
+}
+
+/// Compile an AST read from JSON.
+fn compiler_from_ast_json(code: String, emit: Emit) -> Result<(), Vec<Diagnostic>> {
+    let node = json::program_from_json(&code).map_err(|error| vec![error])?;
+    emit_node(node, emit)
+}
+
+fn emit_node(node: ProgramNode, emit: Emit) -> Result<(), Vec<Diagnostic>> {
+    if emit == Emit::AstJson {
+        println!("{}", json::program_to_json(&node));
+        return Ok(());
+    }
     let mut emitter = Emitter::new();
-    emitter.emit(node);
-    emitter.print_to_file();
+    emitter.emit(node).map_err(|error| vec![error])?;
+    emitter.print_to_file().map_err(|error| vec![error])?;
 
     // emit builtin functions.
-    emit_builtin();
+    emit_builtin().map_err(|error| vec![error])
+}
+
+/// Format the files in place.
+fn fmt(filepaths: Vec<String>) -> bool {
+    let mut is_success = true;
+    for filepath in filepaths {
+        let code = match fs::read_to_string(&filepath) {
+            Ok(code) => code,
+            Err(err) => {
+                let error = Diagnostic::error(format!("{}: {}", filepath, err));
+                report(&[error], &SourceMap::new());
+                is_success = false;
+                continue;
+            }
+        };
+        let mut source_map = SourceMap::new();
+        source_map.add_file(&filepath, &code);
+        match unparser::format(&code) {
+            Ok(formatted) if formatted == code => (),
+            Ok(formatted) => {
+                if let Err(err) = fs::write(&filepath, formatted) {
+                    let error = Diagnostic::error(format!("{}: {}", filepath, err));
+                    report(&[error], &source_map);
+                    is_success = false;
+                }
+            }
+            Err(errors) => {
+                report(&errors, &source_map);
+                is_success = false;
+            }
+        }
+    }
+    is_success
+}
+
+fn usage() -> ! {
+    eprintln!("Usage rexparser [-I<dir>]... [--emit=llvm-ir|tokens-json|ast-json] \"<filepath>\"");
+    eprintln!("      rexparser --input=ast-json [--emit=llvm-ir|ast-json] \"<filepath>\"");
+    eprintln!("      rexparser fmt \"<filepath>\"...");
+    process::exit(1);
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage rexparser \"<filepath>\"");
+    if env::args().nth(1).as_deref() == Some("fmt") {
+        let filepaths: Vec<String> = env::args().skip(2).collect();
+        if filepaths.is_empty() {
+            usage();
+        }
+        if !fmt(filepaths) {
+            process::exit(1);
+        }
+        return;
+    }
+    let mut include_dirs: Vec<String> = Vec::new();
+    let mut filepath: Option<String> = None;
+    let mut emit = Emit::LlvmIr;
+    let mut is_ast_json = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-I" {
+            match args.next() {
+                Some(dir) => include_dirs.push(dir),
+                None => usage(),
+            }
+        } else if arg.starts_with("-I") {
+            include_dirs.push(arg[2..].to_string());
+        } else if let Some(name) = arg.strip_prefix("--emit=") {
+            emit = match name {
+                "llvm-ir" => Emit::LlvmIr,
+                "tokens-json" => Emit::TokensJson,
+                "ast-json" => Emit::AstJson,
+                _ => usage(),
+            };
+        } else if arg == "--input=ast-json" {
+            is_ast_json = true;
+        } else if filepath.is_none() {
+            filepath = Some(arg);
+        } else {
+            usage();
+        }
+    }
+    let filepath = match filepath {
+        Some(filepath) => filepath,
+        None => usage(),
+    };
+    let code = match fs::read_to_string(&filepath) {
+        Ok(code) => code,
+        Err(err) => {
+            let error = Diagnostic::error(format!("{}: {}", filepath, err));
+            report(&[error], &SourceMap::new());
+            process::exit(1);
+        }
+    };
+    let mut source_map = SourceMap::new();
+    let result = if is_ast_json {
+        if emit == Emit::TokensJson {
+            usage();
+        }
+        compiler_from_ast_json(code, emit)
+    } else {
+        compiler(&filepath, code, include_dirs, emit, &mut source_map)
+    };
+    if let Err(errors) = result {
+        report(&errors, &source_map);
         process::exit(1);
     }
-    let filepath = args[1].to_string();
-    let code: String =
-        fs::read_to_string(filepath).expect("something went wrong reading the file.");
-    compiler(code);
 }
@@ -0,0 +1,229 @@
+//! The types of C as they are written in the program. A typedef name is kept as
+//! written, and the emitter looks it up.
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::lexer::is_type_specifier;
+use crate::lexer::token::*;
+use crate::parser::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum CType {
+    Void,
+    /// An integer of `bits` bits, from 1 for `_Bool` and 8 for `char` up to 64
+    /// for `long long`.
+    Int {
+        bits: u32,
+        is_signed: bool,
+    },
+    /// 32 bits for `float`, 64 for `double` and 128 for `long double`.
+    Float {
+        bits: u32,
+    },
+    Pointer(Box<CType>),
+    /// None for `[]`.
+    Array(Box<CType>, Option<u32>),
+    /// By tag, which is None for one defined in a typedef without a tag.
+    Struct(Option<String>),
+    Union(String),
+    Enum(Option<String>),
+    Function {
+        return_type: Box<CType>,
+        parameters: Vec<CType>,
+        is_var_args: bool,
+    },
+    Typedef(String),
+    Qualified(Box<CType>, Qualifiers),
+}
+impl CType {
+    pub fn int() -> CType {
+        CType::Int {
+            bits: 32,
+            is_signed: true,
+        }
+    }
+    /// The type the pointers, arrays and functions of this one are derived from.
+    pub fn base_mut(&mut self) -> &mut CType {
+        match self {
+            CType::Pointer(ctype) | CType::Array(ctype, _) | CType::Qualified(ctype, _) => {
+                ctype.base_mut()
+            }
+            CType::Function { return_type, .. } => return_type.base_mut(),
+            ctype => ctype,
+        }
+    }
+    /// `self` with `qualifiers` added to its own.
+    pub fn qualified(self, qualifiers: Qualifiers) -> CType {
+        match self {
+            _ if qualifiers == Qualifiers::default() => self,
+            CType::Qualified(ctype, own) => CType::Qualified(ctype, own.merge(qualifiers)),
+            ctype => CType::Qualified(Box::new(ctype), qualifiers),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+}
+impl Qualifiers {
+    /// Add the qualifier `token` is, or return false if it is none.
+    fn add(&mut self, token: &Token) -> bool {
+        match token {
+            Token::Keyword(keyword, _) => match keyword.as_ref() {
+                "const" => self.is_const = true,
+                "volatile" => self.is_volatile = true,
+                "restrict" => self.is_restrict = true,
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+    pub fn merge(self, other: Qualifiers) -> Qualifiers {
+        Qualifiers {
+            is_const: self.is_const || other.is_const,
+            is_volatile: self.is_volatile || other.is_volatile,
+            is_restrict: self.is_restrict || other.is_restrict,
+        }
+    }
+    /// The keywords, as in `const volatile`.
+    pub fn keywords(&self) -> Vec<&'static str> {
+        let mut keywords = Vec::new();
+        if self.is_const {
+            keywords.push("const");
+        }
+        if self.is_volatile {
+            keywords.push("volatile");
+        }
+        if self.is_restrict {
+            keywords.push("restrict");
+        }
+        keywords
+    }
+}
+
+/// Pop the qualifiers ahead, as after the `*` of a pointer.
+pub fn expect_qualifiers(tokens: &mut Tokens) -> Qualifiers {
+    let mut qualifiers = Qualifiers::default();
+    while let Some(token) = tokens.peek_nth(0) {
+        if !qualifiers.add(token) {
+            break;
+        }
+        tokens.pop(); // consume the qualifier
+    }
+    qualifiers
+}
+
+/// Pop the type a declaration starts with, such as `const int`, `unsigned long`
+/// or `struct s`.
+pub fn expect_type(tokens: &mut Tokens, typedefs: &TypedefTable) -> ParseResult<CType> {
+    let mut qualifiers = expect_qualifiers(tokens);
+    let ctype = match tokens.peek_nth(0) {
+        Some(Token::Struct(_)) => {
+            tokens.pop(); // consume struct
+            CType::Struct(Some(expect_identifier(tokens)?))
+        }
+        Some(Token::Enum(_)) => {
+            tokens.pop(); // consume enum
+            CType::Enum(Some(expect_identifier(tokens)?))
+        }
+        Some(Token::Keyword(keyword, _)) if keyword == "union" => {
+            tokens.pop(); // consume union
+            CType::Union(expect_identifier(tokens)?)
+        }
+        Some(Token::Ide(name, _)) if !is_type_specifier(name) => {
+            expect_typedef_name(tokens, typedefs)?
+        }
+        _ => expect_specifiers(tokens, &mut qualifiers)?,
+    };
+    Ok(ctype.qualified(qualifiers.merge(expect_qualifiers(tokens))))
+}
+
+/// Pop a typedef name.
+fn expect_typedef_name(tokens: &mut Tokens, typedefs: &TypedefTable) -> ParseResult<CType> {
+    let (name, span) = match tokens.peek_nth(0) {
+        Some(token @ Token::Ide(name, _)) => (name.clone(), token.span()),
+        _ => return Err(unexpected(tokens, "a type")),
+    };
+    match typedefs.get(&name) {
+        Some(typedef) if typedef.parameters.is_some() => {
+            return Err(Diagnostic::error(format!(
+                "the function type \"{}\" can only declare a function at file scope",
+                name
+            ))
+            .at(span))
+        }
+        Some(_) => (),
+        None => return Err(Diagnostic::error(format!("unknown type name \"{}\"", name)).at(span)),
+    }
+    tokens.pop(); // consume the name
+    Ok(CType::Typedef(name))
+}
+
+/// Pop type specifiers such as `unsigned long int`, in any order and with
+/// qualifiers between them.
+fn expect_specifiers(tokens: &mut Tokens, qualifiers: &mut Qualifiers) -> ParseResult<CType> {
+    let start = tokens.checkpoint();
+    let mut specifiers: Vec<String> = Vec::new();
+    while let Some(token) = tokens.peek_nth(0) {
+        match token {
+            Token::Ide(name, _) if is_type_specifier(name) => specifiers.push(name.clone()),
+            token if qualifiers.add(token) => (),
+            _ => break,
+        }
+        tokens.pop(); // consume the specifier or qualifier
+    }
+    if specifiers.is_empty() {
+        return Err(unexpected(tokens, "a type"));
+    }
+    let count = |name: &str| specifiers.iter().filter(|s| *s == name).count();
+    let signs = count("signed") + count("unsigned");
+    let is_signed = count("unsigned") == 0;
+    let others: Vec<&str> = specifiers
+        .iter()
+        .map(String::as_str)
+        .filter(|s| !["signed", "unsigned", "int", "short", "long"].contains(s))
+        .collect();
+    let ctype = match (
+        others.as_slice(),
+        count("int"),
+        count("short"),
+        count("long"),
+    ) {
+        _ if signs > 1 => None,
+        ([], 0..=1, 0, 0) => Some(CType::Int {
+            bits: 32,
+            is_signed,
+        }),
+        ([], 0..=1, 1, 0) => Some(CType::Int {
+            bits: 16,
+            is_signed,
+        }),
+        ([], 0..=1, 0, 1..=2) => Some(CType::Int {
+            bits: 64,
+            is_signed,
+        }),
+        (["char"], 0, 0, 0) => Some(CType::Int { bits: 8, is_signed }),
+        _ if signs > 0 => None,
+        (["void"], 0, 0, 0) => Some(CType::Void),
+        (["_Bool"], 0, 0, 0) => Some(CType::Int {
+            bits: 1,
+            is_signed: false,
+        }),
+        (["float"], 0, 0, 0) => Some(CType::Float { bits: 32 }),
+        (["double"], 0, 0, 0) => Some(CType::Float { bits: 64 }),
+        (["double"], 0, 0, 1) => Some(CType::Float { bits: 128 }),
+        _ => None,
+    };
+    ctype.ok_or_else(|| {
+        Diagnostic::error(format!(
+            "invalid combination of type specifiers \"{}\"",
+            specifiers.join(" ")
+        ))
+        .at(tokens.span_from(start))
+    })
+}
@@ -0,0 +1,253 @@
+//! Declarators, which make a type of the one a declaration starts with and name
+//! what is declared: `**pp`, `*argv[]`, `(*rows)[8]` or `(*cb)(int, int)`.
+
+use crate::lexer::literal::*;
+use crate::lexer::token::*;
+use crate::parser::ctype::*;
+use crate::parser::declare::*;
+use crate::parser::expression::*;
+use crate::parser::*;
+
+use std::collections::VecDeque;
+
+/// A declarator read from the tokens. The declarator of a parameter may be
+/// abstract, which leaves out the name as in `int f(char *)`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Declarator {
+    pub identifier: Option<String>,
+    /// Applied in order to the type the declaration starts with.
+    pub derivations: Vec<Derivation>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Derivation {
+    Pointer(Qualifiers),
+    /// None for `[]`.
+    Array(Option<u32>),
+    /// The parameters, and whether they end with `...`.
+    Function(VecDeque<DeclareVariableNode>, bool),
+}
+
+impl Declarator {
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+        is_abstract: bool,
+    ) -> ParseResult<Declarator> {
+        let mut pointers: Vec<Derivation> = Vec::new();
+        while let Some(Token::Op(op, _)) = tokens.peek_nth(0) {
+            if op != "*" {
+                break;
+            }
+            tokens.pop(); // consume *
+            pointers.push(Derivation::Pointer(expect_qualifiers(tokens)));
+        }
+
+        let (identifier, inner) = match tokens.peek_nth(0) {
+            Some(Token::ParenS(_)) if is_nested(tokens, is_abstract) => {
+                tokens.pop(); // consume (
+                let inner = Declarator::new(tokens, typedefs, is_abstract)?;
+                expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+                (inner.identifier, inner.derivations)
+            }
+            Some(Token::Ide(name, _)) if !is_abstract || !typedefs.is_type_name(name) => {
+                let name = name.clone();
+                tokens.pop(); // consume the name
+                (Some(name), Vec::new())
+            }
+            _ if is_abstract => (None, Vec::new()),
+            _ => return Err(unexpected(tokens, "an identifier")),
+        };
+
+        let mut suffixes: Vec<Derivation> = Vec::new();
+        loop {
+            match tokens.peek_nth(0) {
+                Some(Token::SquareS(_)) => {
+                    tokens.pop(); // consume [
+                    suffixes.push(Derivation::Array(array_size(tokens)?));
+                }
+                Some(Token::ParenS(_)) => {
+                    // the parameters are in scope in the parameter list only
+                    let (parameters, is_var_args) = in_scope(tokens, typedefs, parameters)?;
+                    suffixes.push(Derivation::Function(parameters, is_var_args));
+                }
+                _ => break,
+            }
+        }
+
+        // `*a[3]` is an array of pointers: the suffixes bind tighter, and the
+        // one written last applies first
+        let mut derivations = pointers;
+        derivations.extend(suffixes.into_iter().rev());
+        derivations.extend(inner);
+        Ok(Declarator {
+            identifier,
+            derivations,
+        })
+    }
+}
+
+/// Whether the `(` ahead opens a declarator in parentheses such as `(*p)`, rather
+/// than the parameters of a function.
+fn is_nested(tokens: &Tokens, is_abstract: bool) -> bool {
+    if !is_abstract {
+        return true;
+    }
+    match tokens.peek_nth(1) {
+        Some(Token::Op(op, _)) => op == "*",
+        Some(Token::ParenS(_)) | Some(Token::SquareS(_)) => true,
+        _ => false,
+    }
+}
+
+/// Parse the size of an array after its `[`, up to the `]`.
+fn array_size(tokens: &mut Tokens) -> ParseResult<Option<u32>> {
+    let size = match tokens.peek_nth(0) {
+        Some(Token::SquareE(_)) => None,
+        Some(_) => Some(integer_constant(tokens, "an array size")?),
+        None => return Err(end_of_input(tokens, "an array size")),
+    };
+    expect(tokens, "]", |token| matches!(token, Token::SquareE(_)))?;
+    Ok(size)
+}
+
+/// Pop an integer constant expression, such as `8` or `N - 1` once `N` is
+/// expanded, and fold it. Only integer literals are folded, so an enumerator or
+/// a `const` variable is refused. `what` names the constant in errors.
+pub fn integer_constant(tokens: &mut Tokens, what: &str) -> ParseResult<u32> {
+    let node = ExpressionNode::new(tokens, None)?;
+    let value = fold(&node, what)?;
+    if value < 0 {
+        return Err(Diagnostic::error(format!("{} is negative", what)).at(node.span()));
+    }
+    if value > i128::from(u32::MAX) {
+        return Err(
+            Diagnostic::error(format!("{} of {} is too large", what, value)).at(node.span()),
+        );
+    }
+    Ok(value as u32)
+}
+
+/// The value of an integer constant expression made of integer literals.
+fn fold(node: &ExpressionNode, what: &str) -> ParseResult<i128> {
+    let not_constant = || {
+        Diagnostic::error(format!(
+            "{} must be an integer constant made of integer literals",
+            what
+        ))
+        .at(node.span())
+    };
+    match node {
+        ExpressionNode::Token(TokenNode {
+            token: token @ Token::IntNum(num, _),
+        }) => match parse_int_literal(num) {
+            Ok(literal) => Ok(i128::from(literal.value)),
+            Err(message) => Err(Diagnostic::error(message).at(token.span())),
+        },
+        ExpressionNode::Token(TokenNode {
+            token: Token::Ide(name, debug_info),
+        }) => Err(Diagnostic::error(format!(
+            "{} can only use integer literals, not \"{}\"",
+            what, name
+        ))
+        .at(Span::from(debug_info))),
+        ExpressionNode::Prefix(prefix) => {
+            let value = fold(&prefix.node, what)?;
+            match &prefix.prefix.token {
+                Token::PrefixOp(op, _) if op == "+" => Ok(value),
+                Token::PrefixOp(op, _) if op == "-" => Ok(-value),
+                _ => Err(not_constant()),
+            }
+        }
+        ExpressionNode::TernaryExp(ternary) => match fold(&ternary.condition, what)? {
+            0 => fold(&ternary.rhs, what),
+            _ => fold(&ternary.lhs, what),
+        },
+        ExpressionNode::BinExp(bin_exp) => {
+            let op = match &bin_exp.op.token {
+                Token::Op(op, _) => op.as_str(),
+                _ => return Err(not_constant()),
+            };
+            let lhs = fold(&bin_exp.lhs, what)?;
+            let rhs = fold(&bin_exp.rhs, what)?;
+            let value = match op {
+                "+" => lhs.checked_add(rhs),
+                "-" => lhs.checked_sub(rhs),
+                "*" => lhs.checked_mul(rhs),
+                "&" => Some(lhs & rhs),
+                "<" => Some((lhs < rhs) as i128),
+                ">" => Some((lhs > rhs) as i128),
+                "==" => Some((lhs == rhs) as i128),
+                "&&" => Some((lhs != 0 && rhs != 0) as i128),
+                "||" => Some((lhs != 0 || rhs != 0) as i128),
+                _ => return Err(not_constant()),
+            };
+            value.ok_or_else(|| {
+                Diagnostic::error(format!("{} is out of range", what)).at(node.span())
+            })
+        }
+        _ => Err(not_constant()),
+    }
+}
+
+/// Parse a parenthesized parameter list, and whether it ends with `...`.
+pub fn parameters(
+    tokens: &mut Tokens,
+    typedefs: &mut TypedefTable,
+) -> ParseResult<(VecDeque<DeclareVariableNode>, bool)> {
+    expect(tokens, "(", |token| matches!(token, Token::ParenS(_)))?;
+    let mut parameters: VecDeque<DeclareVariableNode> = VecDeque::new();
+    // `(void)` declares no parameters
+    if let (Some(Token::Ide(name, _)), Some(Token::ParenE(_))) =
+        (tokens.peek_nth(0), tokens.peek_nth(1))
+    {
+        if name == "void" {
+            tokens.pop(); // consume void
+        }
+    }
+    let is_var_args = loop {
+        match tokens.peek() {
+            Some(token) => match token {
+                Token::ParenE(_) => {
+                    tokens.pop(); // consume )
+                    break false;
+                }
+                Token::Va(_) => {
+                    tokens.pop(); // consume ...
+                    expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+                    break true;
+                }
+                _ => (),
+            },
+            None => return Err(end_of_input(tokens, "\")\"")),
+        }
+        let declare_variable_node = DeclareVariableNode::new(tokens, typedefs, true, None)?;
+        parameters.push_back(declare_variable_node);
+        if let Some(Token::Op(op, _)) = tokens.peek() {
+            if op == "," {
+                tokens.pop(); // consume ,
+            }
+        }
+    };
+    Ok((parameters, is_var_args))
+}
+
+/// The type `derivations` make of `value_type`.
+pub fn derive_type(value_type: CType, derivations: &[Derivation]) -> CType {
+    derivations
+        .iter()
+        .fold(value_type, |value_type, derivation| match derivation {
+            Derivation::Pointer(qualifiers) => {
+                CType::Pointer(Box::new(value_type)).qualified(*qualifiers)
+            }
+            Derivation::Array(size) => CType::Array(Box::new(value_type), *size),
+            Derivation::Function(parameters, is_var_args) => CType::Function {
+                return_type: Box::new(value_type),
+                parameters: parameters
+                    .iter()
+                    .map(|parameter| parameter.value_type.clone())
+                    .collect(),
+                is_var_args: *is_var_args,
+            },
+        })
+}
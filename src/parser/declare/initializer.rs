@@ -0,0 +1,120 @@
+//! Initializers, the values after the `=` of a declaration: an expression or a
+//! list in braces such as `{1, 2, 3}`, `{{1, 2}, {3}}` or `{.x = 1, [2] = 3}`.
+
+use crate::diagnostic::Span;
+use crate::lexer::token::*;
+use crate::parser::declare::declarator::integer_constant;
+use crate::parser::expression::*;
+use crate::parser::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Initializer {
+    Expression(ExpressionNode),
+    List(InitializerListNode),
+}
+impl Initializer {
+    /// An expression ends at `break_op` outside of parentheses, as at the `,`
+    /// between declarators.
+    pub fn new(tokens: &mut Tokens, break_op: Option<String>) -> ParseResult<Initializer> {
+        match tokens.peek_nth(0) {
+            Some(Token::CurlyS(_)) => Ok(Initializer::List(InitializerListNode::new(tokens)?)),
+            _ => Ok(Initializer::Expression(ExpressionNode::new(
+                tokens, break_op,
+            )?)),
+        }
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Expression(node) => node.span(),
+            Initializer::List(node) => node.span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InitializerListNode {
+    pub items: Vec<InitializerItem>,
+    pub span: Span,
+}
+impl InitializerListNode {
+    /// Parse up to the closing `}`. The last item may be followed by a `,`.
+    pub fn new(tokens: &mut Tokens) -> ParseResult<InitializerListNode> {
+        let start = tokens.checkpoint();
+        expect(tokens, "{", |token| matches!(token, Token::CurlyS(_)))?;
+        let mut items: Vec<InitializerItem> = Vec::new();
+        loop {
+            match tokens.peek_nth(0) {
+                Some(Token::CurlyE(_)) => {
+                    tokens.pop(); // consume }
+                    break;
+                }
+                None => return Err(end_of_input(tokens, "\"}\"")),
+                _ => (),
+            }
+            items.push(InitializerItem::new(tokens)?);
+            match tokens.peek_nth(0) {
+                Some(Token::Op(op, _)) if op == "," => {
+                    tokens.pop(); // consume ,
+                }
+                Some(Token::CurlyE(_)) => (),
+                _ => return Err(unexpected(tokens, "\",\" or \"}\"")),
+            }
+        }
+        Ok(InitializerListNode {
+            items,
+            span: tokens.span_from(start),
+        })
+    }
+}
+
+/// An item of an initializer list, which its designators put at a member or
+/// element other than the next one.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InitializerItem {
+    pub designators: Vec<Designator>,
+    pub initializer: Initializer,
+}
+impl InitializerItem {
+    pub fn new(tokens: &mut Tokens) -> ParseResult<InitializerItem> {
+        let mut designators: Vec<Designator> = Vec::new();
+        loop {
+            match tokens.peek_nth(0) {
+                Some(Token::Dot(_)) => {
+                    tokens.pop(); // consume .
+                    designators.push(Designator::Field(expect_identifier(tokens)?));
+                }
+                Some(Token::SquareS(_)) => {
+                    tokens.pop(); // consume [
+                    designators.push(Designator::Index(integer_constant(
+                        tokens,
+                        "an array index",
+                    )?));
+                    expect(tokens, "]", |token| matches!(token, Token::SquareE(_)))?;
+                }
+                _ => break,
+            }
+        }
+        if !designators.is_empty() {
+            expect(
+                tokens,
+                "=",
+                |token| matches!(token, Token::Op(op, _) if op == "="),
+            )?;
+        }
+        let initializer = Initializer::new(tokens, Some(String::from(",")))?;
+        Ok(InitializerItem {
+            designators,
+            initializer,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Designator {
+    /// `.x`
+    Field(String),
+    /// `[2]`
+    Index(u32),
+}
@@ -1,45 +1,77 @@
+pub mod declarator;
+pub mod initializer;
+
+use crate::diagnostic::{Diagnostic, Span};
 use crate::lexer::token::*;
-use crate::parser::expression::*;
+use crate::parser::ctype::*;
+use crate::parser::declare::declarator::*;
+use crate::parser::declare::initializer::*;
 use crate::parser::statement::*;
+use crate::parser::*;
+
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum DeclareNode {
     Function(FunctionNode),
     Variable(DeclareStatementNode),
+    Pragma(PragmaNode),
+    Typedef(TypedefNode),
+    Error(ErrorNode),
 }
 impl DeclareNode {
-    pub fn new(tokens: &mut Tokens) -> DeclareNode {
-        let mut cloned_token = tokens.clone();
-        while let Some(token) = cloned_token.pop() {
-            match token {
-                Token::SuffixOp(_, _) => return DeclareNode::Function(FunctionNode::new(tokens)),
-                Token::Semi(_) => return DeclareNode::Variable(DeclareStatementNode::new(tokens)),
-                _ => (),
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<DeclareNode> {
+        match tokens.peek_nth(0) {
+            Some(Token::Pragma(_, _)) => return Ok(DeclareNode::Pragma(PragmaNode::new(tokens)?)),
+            Some(Token::Keyword(keyword, _)) if keyword == "typedef" => {
+                return Ok(DeclareNode::Typedef(TypedefNode::new(tokens, typedefs)?))
             }
+            _ => (),
+        }
+        // a function declared with a typedef of its type, such as `binop add;`
+        let k = match tokens.peek_nth(0) {
+            Some(Token::Extern(_)) => 1,
+            _ => 0,
+        };
+        if function_typedef(tokens, typedefs, k).is_some() {
+            return Ok(DeclareNode::Function(FunctionNode::new(tokens, typedefs)?));
+        }
+        let start = tokens.checkpoint();
+        let is_function = is_function_declaration(tokens, typedefs);
+        tokens.rewind(start);
+        if is_function {
+            Ok(DeclareNode::Function(FunctionNode::new(tokens, typedefs)?))
+        } else {
+            Ok(DeclareNode::Variable(DeclareStatementNode::new(
+                tokens, typedefs,
+            )?))
+        }
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            DeclareNode::Function(node) => node.span.clone(),
+            DeclareNode::Variable(node) => node.span.clone(),
+            DeclareNode::Pragma(node) => node.span.clone(),
+            DeclareNode::Typedef(node) => node.span.clone(),
+            DeclareNode::Error(node) => node.span.clone(),
         }
-        panic!("unexpected")
     }
 }
 
-fn to_pointer_value(value_string: String) -> String {
-    value_string + "*"
-}
-fn to_array_value(value_string: String, size: u32) -> String {
-    value_string + "[" + &size.to_string() + "]"
-}
-
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionNode {
     pub identifier: String,
-    pub return_type: String,
+    pub return_type: CType,
     pub parameters: VecDeque<DeclareVariableNode>,
     pub statements: Option<VecDeque<StatementNode>>,
     pub is_extern: bool,
     pub is_var_args: bool,
+    pub span: Span,
 }
 impl FunctionNode {
-    pub fn new(tokens: &mut Tokens) -> FunctionNode {
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<FunctionNode> {
+        let start = tokens.checkpoint();
         let is_extern = if let Some(Token::Extern(_)) = tokens.peek() {
             tokens.pop();
             true
@@ -47,171 +79,274 @@ impl FunctionNode {
             false
         };
 
-        let return_type = match tokens.pop().unwrap() {
-            Token::Ide(val, _) => val,
-            _ => panic!(),
+        let type_start = tokens.checkpoint();
+        tokens.skip_type_qualifiers();
+        if let Some(typedef) = function_typedef(tokens, typedefs, 0) {
+            tokens.pop(); // consume the typedef name
+            let identifier = expect_identifier(tokens)?;
+            expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+            typedefs.declare(&identifier, None);
+            return Ok(FunctionNode {
+                identifier,
+                return_type: typedef.value_type,
+                parameters: typedef.parameters.unwrap_or_default(),
+                statements: None,
+                is_extern,
+                is_var_args: typedef.is_var_args,
+                span: tokens.span_from(start),
+            });
+        }
+        tokens.rewind(type_start);
+        let return_type = expect_type(tokens, typedefs)?;
+        let declarator_start = tokens.checkpoint();
+        let declarator = Declarator::new(tokens, typedefs, false)?;
+        let mut derivations = declarator.derivations;
+        let (parameters, is_var_args) = match derivations.pop() {
+            Some(Derivation::Function(parameters, is_var_args)) => (parameters, is_var_args),
+            _ => {
+                return Err(Diagnostic::error("expected a function declarator")
+                    .at(tokens.span_from(declarator_start)))
+            }
         };
-        let identifier = match tokens.pop().unwrap() {
-            Token::Ide(val, _) => val,
-            _ => panic!(),
-        }; // consume function name
-        tokens.pop(); // consume (
+        let return_type = derive_type(return_type, &derivations);
+        let identifier = declarator
+            .identifier
+            .expect("a declarator that is not abstract to have a name");
+        typedefs.declare(&identifier, None);
 
-        let mut parameters: VecDeque<DeclareVariableNode> = VecDeque::new();
-        let is_var_args = loop {
-            match tokens.peek() {
-                Some(token) => match token {
-                    Token::ParenE(_) => {
-                        tokens.pop(); // consume )
-                        break false;
-                    }
-                    Token::Va(_) => {
-                        tokens.pop(); // consume ...
-                        tokens.pop(); // consume );
-                        break true;
-                    }
-                    _ => (),
-                },
-                None => panic!("unexpected"),
-            }
-            let declare_variable_node = DeclareVariableNode::new(tokens, true, None);
-            parameters.push_back(declare_variable_node);
-            if let Some(Token::Op(op, _)) = tokens.peek() {
-                if op == "," {
-                    tokens.pop(); // consume ,
-                }
+        // the parameters are in scope in the body only
+        let statements = in_scope(tokens, typedefs, |tokens, typedefs| {
+            for parameter in &parameters {
+                typedefs.declare(&parameter.identifier, None);
             }
-        };
-        let statements = match tokens.pop() {
-            Some(token) => match token {
-                Token::CurlyS(_) => {
+            let statements = match tokens.peek_nth(0) {
+                Some(Token::CurlyS(_)) => {
+                    tokens.pop(); // consume {
                     let mut statements: VecDeque<StatementNode> = VecDeque::new();
                     loop {
-                        if let Some(Token::CurlyE(_)) = tokens.peek() {
-                            tokens.pop(); // consume }
-                            break;
+                        match tokens.peek_nth(0) {
+                            Some(Token::CurlyE(_)) => {
+                                tokens.pop(); // consume }
+                                break;
+                            }
+                            None => return Err(end_of_input(tokens, "\"}\"")),
+                            _ => (),
                         }
-                        let statement = StatementNode::new(tokens);
+                        let statement = StatementNode::new(tokens, typedefs)?;
                         statements.push_back(statement);
                     }
                     Some(statements)
                 }
-                Token::Semi(_) => None,
-                _ => panic!("unexpected"),
-            },
-            None => panic!("unexpected"),
-        };
-        FunctionNode {
+                Some(Token::Semi(_)) => {
+                    tokens.pop(); // consume ;
+                    None
+                }
+                _ => return Err(unexpected(tokens, "\"{\" or \";\"")),
+            };
+            Ok(statements)
+        })?;
+        Ok(FunctionNode {
             identifier,
             return_type,
             parameters,
             statements,
             is_extern,
             is_var_args,
-        }
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DeclareVariableNode {
-    pub value_type: String,
+    pub value_type: CType,
+    /// Empty for a parameter without a name.
     pub identifier: String,
-    pub initialize_expression: Option<ExpressionNode>,
+    pub initializer: Option<Initializer>,
+    pub span: Span,
 }
 impl DeclareVariableNode {
     pub fn new(
         tokens: &mut Tokens,
-        is_function_declare: bool,
+        typedefs: &mut TypedefTable,
+        is_parameter: bool,
         break_op: Option<String>,
-    ) -> DeclareVariableNode {
-        let mut value_type = match tokens.pop().unwrap() {
-            Token::Struct(_) => {
-                let identifier = match tokens.pop().unwrap() {
-                    Token::Ide(val, _) => val,
-                    _ => panic!("unexpected"),
-                };
-                identifier
-            }
-            Token::Enum(_) => {
-                if let Some(Token::Ide(_, _)) = tokens.peek() {
-                    tokens.pop();
+    ) -> ParseResult<DeclareVariableNode> {
+        let start = tokens.checkpoint();
+        let value_type = expect_type(tokens, typedefs)?;
+        let node = DeclareVariableNode::new_with_type(
+            tokens,
+            typedefs,
+            value_type,
+            is_parameter,
+            break_op,
+        )?;
+        Ok(DeclareVariableNode {
+            span: tokens.span_from(start),
+            ..node
+        })
+    }
+    /// Declare a variable of a type that has already been read. A parameter may
+    /// leave out its name, and one of an array or function type is a pointer.
+    pub fn new_with_type(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+        value_type: CType,
+        is_parameter: bool,
+        break_op: Option<String>,
+    ) -> ParseResult<DeclareVariableNode> {
+        let start = tokens.checkpoint();
+        let declarator = Declarator::new(tokens, typedefs, is_parameter)?;
+        let mut derivations = declarator.derivations;
+        if is_parameter {
+            match derivations.last() {
+                Some(Derivation::Array(_)) => {
+                    derivations.pop();
+                    derivations.push(Derivation::Pointer(Qualifiers::default()));
                 }
-                String::from("int")
-            }
-            Token::Ide(type_string, _) => type_string,
-            _ => panic!(),
-        };
-
-        if let Some(Token::Op(op, _)) = tokens.peek() {
-            if op == "*" {
-                value_type = to_pointer_value(value_type);
-                tokens.pop();
+                Some(Derivation::Function(_, _)) => {
+                    derivations.push(Derivation::Pointer(Qualifiers::default()))
+                }
+                _ => (),
             }
         }
-
-        let identifier = match tokens.pop().unwrap() {
-            Token::Ide(val, _) => val,
-            _ => panic!(),
-        };
-
-        if let Some(Token::SuffixOp(_, _)) = tokens.peek() {
-            if is_function_declare {
-                value_type = get_array_type_at_function_declare(value_type, tokens);
-            } else {
-                value_type = get_array_type(value_type, tokens);
-            }
+        let value_type = derive_type(value_type, &derivations);
+        let identifier = declarator.identifier.unwrap_or_default();
+        if !identifier.is_empty() {
+            typedefs.declare(&identifier, None);
         }
-        let mut initialize_expression = None;
+
+        let mut initializer = None;
         if let Some(Token::Op(op, _)) = tokens.peek() {
             if op == "=" {
                 tokens.pop();
-                initialize_expression = Some(ExpressionNode::new(tokens, break_op));
+                initializer = Some(Initializer::new(tokens, break_op)?);
             }
         }
-        DeclareVariableNode {
+        Ok(DeclareVariableNode {
             value_type,
             identifier,
-            initialize_expression,
-        }
+            initializer,
+            span: tokens.span_from(start),
+        })
     }
 }
 
-pub fn get_array_type_at_function_declare(value_type: String, tokens: &mut Tokens) -> String {
-    let mut value_type = value_type;
-    if let Some(Token::SuffixOp(op, _)) = tokens.peek() {
-        if op == "[" {
-            tokens.pop(); // consume [
-            if let Some(Token::SquareE(_)) = tokens.peek() {
-                tokens.pop(); // consume ]
-            } else {
-                tokens.pop();
-                tokens.pop(); // consume ]
-            }
+/// Whether the declaration ahead is of a function, as `int *f(int a)` is and
+/// `int (*f)(int a)` is not. It reads the tokens, and is false on an error. The
+/// names it declares go in a scope of their own, so that it declares none once
+/// the tokens are read again.
+fn is_function_declaration(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> bool {
+    in_scope(tokens, typedefs, |tokens, typedefs| {
+        if let Some(Token::Extern(_)) = tokens.peek_nth(0) {
+            tokens.pop(); // consume extern
         }
+        expect_type(tokens, typedefs)?;
+        let declarator = Declarator::new(tokens, typedefs, false)?;
+        Ok(matches!(
+            declarator.derivations.last(),
+            Some(Derivation::Function(_, _))
+        ))
+    })
+    .unwrap_or(false)
+}
+
+/// The typedef of a function type named by the `k`th token ahead.
+fn function_typedef(tokens: &Tokens, typedefs: &TypedefTable, k: usize) -> Option<TypedefNode> {
+    match tokens.peek_nth(k) {
+        Some(Token::Ide(name, _)) => typedefs
+            .get(name)
+            .filter(|typedef| typedef.parameters.is_some())
+            .cloned(),
+        _ => None,
     }
-    value_type = to_pointer_value(value_type);
-    value_type = get_array_type(value_type, tokens);
-    value_type
 }
 
-fn get_array_type(value_type: String, tokens: &mut Tokens) -> String {
-    let mut value_type = value_type;
-    let mut array_size_vec: Vec<u32> = Vec::new();
-    while let Some(Token::SuffixOp(op, _)) = tokens.peek() {
-        if op == "[" {
-            tokens.pop(); // consume [
-            let num = match tokens.pop().unwrap() {
-                Token::IntNum(num, _) => num.parse().unwrap(),
-                _ => panic!(),
-            };
-            array_size_vec.push(num);
-            tokens.pop(); // consume ]
-        } else {
-            break;
+/// `typedef int *T;`. A use of `T` keeps the name, which the emitter looks up.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TypedefNode {
+    pub identifier: String,
+    /// The return type of a function type. A struct or enum defined without a tag
+    /// has none in it.
+    pub value_type: CType,
+    /// The parameters of a function type, which the parser copies wherever the
+    /// typedef declares a function.
+    pub parameters: Option<VecDeque<DeclareVariableNode>>,
+    pub is_var_args: bool,
+    /// The struct or enum defined with the typedef, as in `typedef struct { int x; } P;`.
+    pub definition: Option<TypeDefinitionNode>,
+    pub span: Span,
+}
+impl TypedefNode {
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<TypedefNode> {
+        let start = tokens.checkpoint();
+        tokens.pop(); // consume typedef
+        let qualifiers = expect_qualifiers(tokens);
+        let is_definition = matches!(tokens.peek_nth(1), Some(Token::CurlyS(_)))
+            || matches!(tokens.peek_nth(2), Some(Token::CurlyS(_)));
+        let (value_type, definition) = match tokens.peek_nth(0) {
+            Some(Token::Struct(_)) if is_definition => {
+                let node = StructDefinitionNode::new(tokens, typedefs)?;
+                let value_type = CType::Struct(node.tag.clone());
+                (value_type, Some(TypeDefinitionNode::Struct(node)))
+            }
+            Some(Token::Enum(_)) if is_definition => {
+                let node = EnumDefinitionNode::new(tokens, typedefs)?;
+                let value_type = CType::Enum(node.tag.clone());
+                (value_type, Some(TypeDefinitionNode::Enum(node)))
+            }
+            _ => match function_typedef(tokens, typedefs, 0) {
+                // another name for a function type
+                Some(typedef) => {
+                    tokens.pop(); // consume the typedef name
+                    let identifier = expect_identifier(tokens)?;
+                    expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+                    let node = TypedefNode {
+                        identifier,
+                        definition: None,
+                        span: tokens.span_from(start),
+                        ..typedef
+                    };
+                    typedefs.declare(&node.identifier, Some(node.clone()));
+                    return Ok(node);
+                }
+                None => (expect_type(tokens, typedefs)?, None),
+            },
+        };
+
+        let value_type = value_type.qualified(qualifiers.merge(expect_qualifiers(tokens)));
+        let declarator = Declarator::new(tokens, typedefs, false)?;
+        let mut derivations = declarator.derivations;
+        let (parameters, is_var_args) = match derivations.pop() {
+            Some(Derivation::Function(parameters, is_var_args)) => (Some(parameters), is_var_args),
+            derivation => {
+                derivations.extend(derivation);
+                (None, false)
+            }
+        };
+        if let Some(token @ Token::Op(op, _)) = tokens.peek_nth(0) {
+            if op == "=" {
+                return Err(Diagnostic::error("a typedef cannot be initialized").at(token.span()));
+            }
         }
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        let node = TypedefNode {
+            identifier: declarator
+                .identifier
+                .expect("a declarator that is not abstract to have a name"),
+            value_type: derive_type(value_type, &derivations),
+            parameters,
+            is_var_args,
+            definition,
+            span: tokens.span_from(start),
+        };
+        typedefs.declare(&node.identifier, Some(node.clone()));
+        Ok(node)
     }
-    while let Some(size) = array_size_vec.pop() {
-        value_type = to_array_value(value_type, size);
-    }
-    value_type
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum TypeDefinitionNode {
+    Struct(StructDefinitionNode),
+    Enum(EnumDefinitionNode),
 }
@@ -1,9 +1,13 @@
+use crate::diagnostic::{Diagnostic, Span};
 use crate::lexer::token::Property;
-use crate::lexer::token::{Associativity, Token, Tokens};
+use crate::lexer::token::{Associativity, Checkpoint, Token, Tokens};
+use crate::parser::{end_of_input, expect, unexpected, ParseResult};
+
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ExpressionNodes {
     pub nodes: Vec<ExpressionNode>,
 }
@@ -15,15 +19,13 @@ pub fn get_property(op: &String) -> Property {
     map.insert("+=", (2, Associativity::Right));
     map.insert("||", (4, Associativity::Left));
     map.insert("&&", (5, Associativity::Left));
+    map.insert("&", (8, Associativity::Left));
     map.insert("==", (9, Associativity::Left));
     map.insert(">", (10, Associativity::Left));
     map.insert("<", (10, Associativity::Left));
     map.insert("+", (12, Associativity::Left));
     map.insert("-", (12, Associativity::Left));
     map.insert("*", (13, Associativity::Left));
-    map.insert("[", (16, Associativity::Left));
-    map.insert("(", (16, Associativity::Left));
-    map.insert(".", (16, Associativity::Left));
     let op: &str = &op;
     let (precedence, associativity): (u32, Associativity) = map[op].clone();
     Property {
@@ -32,7 +34,7 @@ pub fn get_property(op: &String) -> Property {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum ExpressionNode {
     BinExp(BinExpNode),
     TernaryExp(TernaryExpNode),
@@ -42,92 +44,97 @@ pub enum ExpressionNode {
     FunctionCall(FunctionCallNode),
     Access(AccessNode),
     Token(TokenNode),
-    Empty,
+    /// No expression, such as the arguments of `f()`. The span is empty.
+    Empty(Span),
 }
 impl ExpressionNode {
-    pub fn new(tokens: &mut Tokens, break_op: Option<String>) -> ExpressionNode {
-        let exp = BinExpNode::new(tokens, break_op);
-        match tokens.peek().unwrap() {
-            Token::Question(_) => TernaryExpNode::new(exp, tokens),
-            _ => exp,
+    pub fn new(tokens: &mut Tokens, break_op: Option<String>) -> ParseResult<ExpressionNode> {
+        let start = tokens.checkpoint();
+        let exp = BinExpNode::new(tokens, break_op.clone())?;
+        match tokens.peek_nth(0) {
+            Some(Token::Question(_)) => TernaryExpNode::new(exp, start, tokens, break_op),
+            _ => Ok(exp),
         }
     }
-    pub fn new_node(tokens: &mut Tokens) -> ExpressionNode {
-        let lhs = ExpressionNode::new_with_prefix(tokens);
-        let lhs = ExpressionNode::new_with_suffix(lhs, tokens);
-        lhs
+    pub fn span(&self) -> Span {
+        match self {
+            ExpressionNode::BinExp(node) => node.span.clone(),
+            ExpressionNode::TernaryExp(node) => node.span.clone(),
+            ExpressionNode::Prefix(node) => node.span.clone(),
+            ExpressionNode::Suffix(node) => node.span.clone(),
+            ExpressionNode::ArrayIndex(node) => node.span.clone(),
+            ExpressionNode::FunctionCall(node) => node.span.clone(),
+            ExpressionNode::Access(node) => node.span.clone(),
+            ExpressionNode::Token(node) => node.span(),
+            ExpressionNode::Empty(span) => span.clone(),
+        }
     }
-    fn new_with_prefix(tokens: &mut Tokens) -> ExpressionNode {
-        match tokens.peek() {
-            Some(token) => match token {
-                Token::Ide(_, _)
-                | Token::IntNum(_, _)
-                | Token::FloatNum(_, _)
-                | Token::Str(_, _) => TokenNode::new(tokens),
-                Token::PrefixOp(_, _) => PrefixNode::new(tokens),
-                Token::Op(op, debug_info) => match op.as_ref() {
-                    // treat as an operator
-                    "+" | "-" | "*" => {
-                        tokens.pop(); // consume "+" | "-"
-                        let node = ExpressionNode::new_node(tokens);
-                        ExpressionNode::Prefix(PrefixNode {
-                            prefix: TokenNode {
-                                token: Token::PrefixOp(op, debug_info),
-                            },
-                            node: Box::new(node),
-                        })
-                    }
-                    _ => panic!(),
-                },
-                Token::SuffixOp(suffix, _) => match suffix.as_ref() {
-                    // treat as a parenthesis expression
-                    "(" => {
-                        tokens.pop(); // consume "("
-                        let node = BinExpNode::new(tokens, None);
-                        tokens.pop(); // consume ")"
-                        node
-                    }
-                    _ => panic!(),
-                },
-                _ => panic!(format!("Expect a primary token, but this is {:?}", token)),
-            },
-            None => panic!(),
+    pub fn new_node(tokens: &mut Tokens) -> ParseResult<ExpressionNode> {
+        let start = tokens.checkpoint();
+        let lhs = ExpressionNode::new_with_prefix(tokens)?;
+        ExpressionNode::new_with_suffix(lhs, start, tokens)
+    }
+    /// An operator before an operand is unary: `&`, `*`, `+`, `-`, `++` and `--`.
+    fn new_with_prefix(tokens: &mut Tokens) -> ParseResult<ExpressionNode> {
+        match tokens.peek_nth(0) {
+            Some(Token::Ide(_, _))
+            | Some(Token::IntNum(_, _))
+            | Some(Token::FloatNum(_, _))
+            | Some(Token::Str(_, _)) => Ok(TokenNode::new(tokens)),
+            Some(Token::Op(_, _)) => PrefixNode::new(tokens),
+            // treat as a parenthesis expression
+            Some(Token::ParenS(_)) => {
+                tokens.pop(); // consume "("
+                let node = BinExpNode::new(tokens, None)?;
+                expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+                Ok(node)
+            }
+            _ => Err(unexpected(tokens, "an expression")),
         }
     }
-    fn new_with_suffix(lhs: ExpressionNode, tokens: &mut Tokens) -> ExpressionNode {
-        match tokens.peek() {
-            Some(token) => match token {
-                Token::SuffixOp(_, _) => {
-                    let mut node = SuffixNode::new(lhs, tokens);
-                    while let Some(Token::SuffixOp(_, _)) = tokens.peek() {
-                        node = SuffixNode::new(node, tokens);
-                    }
-                    node
-                }
-                _ => lhs,
-            },
-            None => lhs,
+    /// Calls, subscripts, member accesses and `++` or `--` right after an operand.
+    fn new_with_suffix(
+        lhs: ExpressionNode,
+        start: Checkpoint,
+        tokens: &mut Tokens,
+    ) -> ParseResult<ExpressionNode> {
+        let mut node = lhs;
+        while let Some(token) = tokens.peek_nth(0) {
+            let is_suffix = match token {
+                Token::ParenS(_) | Token::SquareS(_) | Token::Dot(_) => true,
+                Token::Op(op, _) => op == "++" || op == "--",
+                _ => false,
+            };
+            if !is_suffix {
+                break;
+            }
+            node = SuffixNode::new(node, start, tokens)?;
         }
+        Ok(node)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BinExpNode {
     pub op: TokenNode,
     pub lhs: Box<ExpressionNode>,
     pub rhs: Box<ExpressionNode>,
+    pub span: Span,
 }
 impl BinExpNode {
-    pub fn new(tokens: &mut Tokens, break_op: Option<String>) -> ExpressionNode {
-        let lhs = ExpressionNode::new_node(tokens);
-        BinExpNode::binary_expression(lhs, tokens, 0, break_op)
+    pub fn new(tokens: &mut Tokens, break_op: Option<String>) -> ParseResult<ExpressionNode> {
+        let start = tokens.checkpoint();
+        let lhs = ExpressionNode::new_node(tokens)?;
+        BinExpNode::binary_expression(lhs, start, tokens, 0, break_op)
     }
+    /// `start` is where `lhs` starts.
     fn binary_expression(
         mut lhs: ExpressionNode,
+        start: Checkpoint,
         tokens: &mut Tokens,
         min_precedence: u32,
         break_op: Option<String>,
-    ) -> ExpressionNode {
+    ) -> ParseResult<ExpressionNode> {
         while let Some(token) = tokens.peek() {
             match token {
                 Token::Op(op, debug_info) => {
@@ -147,8 +154,8 @@ impl BinExpNode {
                     let op = TokenNode {
                         token: Token::Op(op, debug_info),
                     };
-                    // TODO: impl error handling
-                    let mut rhs = ExpressionNode::new_node(tokens);
+                    let rhs_start = tokens.checkpoint();
+                    let mut rhs = ExpressionNode::new_node(tokens)?;
                     while let Some(Token::Op(op2, _)) = tokens.peek() {
                         let property2 = get_property(&op2);
                         let (precedence, _associativity) =
@@ -165,140 +172,198 @@ impl BinExpNode {
                                 }
                             }
                         }
-                        rhs =
-                            BinExpNode::binary_expression(rhs, tokens, precedence, break_op.clone())
+                        rhs = BinExpNode::binary_expression(
+                            rhs,
+                            rhs_start,
+                            tokens,
+                            precedence,
+                            break_op.clone(),
+                        )?
                     }
                     lhs = ExpressionNode::BinExp(BinExpNode {
                         op,
                         lhs: Box::new(lhs),
                         rhs: Box::new(rhs),
+                        span: tokens.span_from(start),
                     });
                 }
                 _ => break,
             }
         }
-        lhs
+        Ok(lhs)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TernaryExpNode {
     pub condition: Box<ExpressionNode>,
     pub lhs: Box<ExpressionNode>,
     pub rhs: Box<ExpressionNode>,
+    pub span: Span,
 }
 impl TernaryExpNode {
-    pub fn new(lhs: ExpressionNode, tokens: &mut Tokens) -> ExpressionNode {
+    /// `start` is where the condition `lhs` starts. Like the condition, the
+    /// expression after `:` ends at `break_op`.
+    pub fn new(
+        lhs: ExpressionNode,
+        start: Checkpoint,
+        tokens: &mut Tokens,
+        break_op: Option<String>,
+    ) -> ParseResult<ExpressionNode> {
         let condition = lhs.clone();
-        let _question = tokens.pop();
-        let ternary_lhs = BinExpNode::new(tokens, None);
-        let _colon = tokens.pop();
-        let ternary_rhs = BinExpNode::new(tokens, None);
-        ExpressionNode::TernaryExp(TernaryExpNode {
+        expect(tokens, "?", |token| matches!(token, Token::Question(_)))?;
+        let ternary_lhs = BinExpNode::new(tokens, None)?;
+        expect(tokens, ":", |token| matches!(token, Token::Colon(_)))?;
+        let ternary_rhs = BinExpNode::new(tokens, break_op)?;
+        Ok(ExpressionNode::TernaryExp(TernaryExpNode {
             condition: Box::new(condition),
             lhs: Box::new(ternary_lhs),
             rhs: Box::new(ternary_rhs),
-        })
+            span: tokens.span_from(start),
+        }))
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ArrayIndexNode {
     pub array: Box<ExpressionNode>,
     pub index: Box<ExpressionNode>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionCallNode {
     pub identifier: TokenNode,
     pub parameters: Box<ExpressionNode>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct PrefixNode {
     pub prefix: TokenNode,
     pub node: Box<ExpressionNode>,
+    pub span: Span,
 }
 impl PrefixNode {
-    pub fn new(tokens: &mut Tokens) -> ExpressionNode {
-        let token = tokens.pop().unwrap();
-        let node = ExpressionNode::new_node(tokens);
-        ExpressionNode::Prefix(PrefixNode {
-            prefix: TokenNode { token },
+    pub fn new(tokens: &mut Tokens) -> ParseResult<ExpressionNode> {
+        let start = tokens.checkpoint();
+        let prefix = match tokens.peek() {
+            Some(Token::Op(op, debug_info)) => match op.as_ref() {
+                "&" | "*" | "+" | "-" | "++" | "--" => Token::PrefixOp(op, debug_info),
+                _ => {
+                    return Err(Diagnostic::error(format!(
+                        "expected an expression before \"{}\"",
+                        op
+                    ))
+                    .at(Token::Op(op.clone(), debug_info).span()))
+                }
+            },
+            _ => return Err(unexpected(tokens, "a unary operator")),
+        };
+        tokens.pop(); // consume the operator
+        let node = ExpressionNode::new_node(tokens)?;
+        Ok(ExpressionNode::Prefix(PrefixNode {
+            prefix: TokenNode { token: prefix },
             node: Box::new(node),
-        })
+            span: tokens.span_from(start),
+        }))
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SuffixNode {
     pub suffix: TokenNode,
     pub node: Box<ExpressionNode>,
+    pub span: Span,
 }
 impl SuffixNode {
-    fn new(lhs: ExpressionNode, tokens: &mut Tokens) -> ExpressionNode {
-        match tokens.pop().unwrap() {
-            Token::SuffixOp(suffix, debug_info) => match suffix.as_ref() {
-                "++" => ExpressionNode::Suffix(SuffixNode {
-                    suffix: TokenNode {
-                        token: Token::SuffixOp(suffix, debug_info),
-                    },
-                    node: Box::new(lhs),
-                }),
-                "[" => {
-                    let index = BinExpNode::new(tokens, None);
-                    let array = ExpressionNode::ArrayIndex(ArrayIndexNode {
-                        array: Box::new(lhs),
-                        index: Box::new(index),
-                    });
-                    tokens.pop(); // consume "["
-                    array
-                }
-                "(" => {
-                    if let ExpressionNode::Token(token_node) = lhs {
-                        let parameters = match tokens.peek() {
-                            Some(token) => match token {
-                                Token::ParenE(_) => Box::new(ExpressionNode::Empty),
-                                _ => Box::new(BinExpNode::new(tokens, None)),
-                            },
-                            None => panic!(),
-                        };
-                        tokens.pop(); // consume ParanE TODO: impl error handling
-                        ExpressionNode::FunctionCall(FunctionCallNode {
-                            identifier: token_node,
-                            parameters,
-                        })
-                    } else {
-                        panic!("Expect a token node as lhs.")
+    /// `start` is where the operand `lhs` starts.
+    fn new(
+        lhs: ExpressionNode,
+        start: Checkpoint,
+        tokens: &mut Tokens,
+    ) -> ParseResult<ExpressionNode> {
+        let token = match tokens.pop() {
+            Some(token) => token,
+            None => return Err(end_of_input(tokens, "a suffix operator")),
+        };
+        match token {
+            Token::Op(suffix, debug_info) => Ok(ExpressionNode::Suffix(SuffixNode {
+                suffix: TokenNode {
+                    token: Token::SuffixOp(suffix, debug_info),
+                },
+                span: tokens.span_from(start),
+                node: Box::new(lhs),
+            })),
+            Token::SquareS(_) => {
+                let index = BinExpNode::new(tokens, None)?;
+                expect(tokens, "]", |token| matches!(token, Token::SquareE(_)))?;
+                Ok(ExpressionNode::ArrayIndex(ArrayIndexNode {
+                    span: tokens.span_from(start),
+                    array: Box::new(lhs),
+                    index: Box::new(index),
+                }))
+            }
+            Token::ParenS(_) => {
+                let token_node = match lhs {
+                    ExpressionNode::Token(token_node) => token_node,
+                    _ => {
+                        return Err(Diagnostic::error("only a function name can be called")
+                            .at(token.span()))
                     }
-                }
-                "." => {
-                    let access_identifier = tokens.pop().unwrap();
-                    ExpressionNode::Access(AccessNode {
-                        access_identifier,
-                        node: Box::new(lhs),
-                    })
-                }
-                _ => panic!(),
-            },
-            _ => panic!("Expect a suffix operator."),
+                };
+                let parameters = match tokens.peek_nth(0) {
+                    Some(Token::ParenE(_)) => {
+                        Box::new(ExpressionNode::Empty(tokens.span_from(tokens.checkpoint())))
+                    }
+                    _ => Box::new(BinExpNode::new(tokens, None)?),
+                };
+                expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+                Ok(ExpressionNode::FunctionCall(FunctionCallNode {
+                    span: tokens.span_from(start),
+                    identifier: token_node,
+                    parameters,
+                }))
+            }
+            Token::Dot(_) => {
+                let access_identifier = match tokens.peek_nth(0) {
+                    Some(Token::Ide(_, _)) => tokens.pop().unwrap(),
+                    _ => return Err(unexpected(tokens, "a member name")),
+                };
+                Ok(ExpressionNode::Access(AccessNode {
+                    access_identifier,
+                    span: tokens.span_from(start),
+                    node: Box::new(lhs),
+                }))
+            }
+            token => Err(Diagnostic::error(format!(
+                "expected a suffix operator but found \"{}\"",
+                token.debug_info().s
+            ))
+            .at(token.span())),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AccessNode {
     pub access_identifier: Token,
     pub node: Box<ExpressionNode>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TokenNode {
     pub token: Token,
 }
 impl TokenNode {
+    /// Pop the next token as an operand. The caller checks that there is one.
     pub fn new(tokens: &mut Tokens) -> ExpressionNode {
-        let token = tokens.pop().unwrap();
+        let token = tokens.pop().expect("a token to read as an operand");
         ExpressionNode::Token(TokenNode { token })
     }
+    /// The span of the token itself.
+    pub fn span(&self) -> Span {
+        self.token.span()
+    }
 }
@@ -1,75 +1,318 @@
+pub mod ctype;
 pub mod declare;
 pub mod expression;
 pub mod statement;
+pub mod visitor;
 
+use crate::diagnostic::{Diagnostic, Span};
+use crate::lexer::lexer::{is_keyword, is_type_specifier};
 use crate::lexer::token::*;
+use crate::parser::ctype::CType;
 use crate::parser::declare::*;
-use crate::parser::expression::*;
 use crate::parser::statement::*;
+use crate::parser::visitor::Visitor;
 
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone)]
+use std::collections::{HashMap, VecDeque};
+
+pub type ParseResult<T> = Result<T, Diagnostic>;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ProgramNode {
     pub declares: VecDeque<DeclareNode>,
 }
 impl ProgramNode {
+    /// Parse the whole program. A declaration with a syntax error is kept as
+    /// `DeclareNode::Error` and parsing goes on with the next one.
     pub fn new(tokens: &mut Tokens) -> ProgramNode {
+        let mut typedefs = TypedefTable::new();
         let mut declares: VecDeque<DeclareNode> = VecDeque::new();
         while let Some(_) = tokens.peek() {
-            let declare = DeclareNode::new(tokens);
+            let start = tokens.checkpoint();
+            let declare = match DeclareNode::new(tokens, &mut typedefs) {
+                Ok(declare) => declare,
+                Err(diagnostic) => {
+                    skip_declaration(tokens, &typedefs, start);
+                    DeclareNode::Error(ErrorNode {
+                        diagnostic,
+                        span: tokens.span_from(start),
+                    })
+                }
+            };
             declares.push_back(declare);
         }
         ProgramNode { declares }
     }
+    /// The syntax errors of the program in source order.
+    pub fn errors(&self) -> Vec<Diagnostic> {
+        let mut collector = ErrorCollector { errors: Vec::new() };
+        collector.visit_program(self);
+        collector.errors
+    }
+}
+
+struct ErrorCollector {
+    errors: Vec<Diagnostic>,
+}
+impl Visitor for ErrorCollector {
+    fn visit_error(&mut self, node: &ErrorNode) {
+        self.errors.push(node.diagnostic.clone());
+    }
 }
 
-pub fn parser(tokens: &mut Tokens) -> ProgramNode {
-    ProgramNode::new(tokens)
+/// Parse the program, reporting every syntax error found.
+pub fn parser(tokens: &mut Tokens) -> Result<ProgramNode, Vec<Diagnostic>> {
+    let node = ProgramNode::new(tokens);
+    let errors = node.errors();
+    if errors.is_empty() {
+        Ok(node)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Stands for a statement or declaration with a syntax error, which has been
+/// skipped.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ErrorNode {
+    pub diagnostic: Diagnostic,
+    /// The skipped tokens.
+    pub span: Span,
 }
 
+/// Skip a top-level declaration with a syntax error, from its `start`: up to its
+/// `;`, through the `}` of a function body, or up to the next declaration after
+/// the error.
+fn skip_declaration(tokens: &mut Tokens, typedefs: &TypedefTable, start: Checkpoint) {
+    let error = tokens.checkpoint();
+    tokens.rewind(start);
+    let (mut braces, mut parens) = (0, 0);
+    while let Some(token) = tokens.peek_nth(0) {
+        let is_after_error = tokens.checkpoint() >= error;
+        match token {
+            Token::Semi(_) if braces == 0 && parens == 0 && is_after_error => {
+                tokens.pop();
+                return;
+            }
+            Token::ParenS(_) => parens += 1,
+            Token::ParenE(_) if parens > 0 => parens -= 1,
+            Token::CurlyS(_) => braces += 1,
+            Token::CurlyE(_) if braces <= 1 && is_after_error => {
+                tokens.pop();
+                // as after a struct definition
+                if let Some(Token::Semi(_)) = tokens.peek_nth(0) {
+                    tokens.pop();
+                }
+                return;
+            }
+            Token::CurlyE(_) if braces > 0 => braces -= 1,
+            token
+                if braces == 0
+                    && parens == 0
+                    && is_declaration_start(typedefs, token)
+                    && is_after_error
+                    && tokens.checkpoint() != start =>
+            {
+                return
+            }
+            _ => (),
+        }
+        tokens.pop();
+    }
+}
+
+fn is_declaration_start(typedefs: &TypedefTable, token: &Token) -> bool {
+    match token {
+        Token::Extern(_) | Token::Struct(_) | Token::Enum(_) | Token::Pragma(_, _) => true,
+        Token::Ide(val, _) => typedefs.is_type_name(val),
+        Token::Keyword(keyword, _) if keyword == "typedef" || keyword == "union" => true,
+        token => token.is_type_qualifier(),
+    }
+}
+
+/// The names declared in each scope, so that a typedef name can be told from
+/// other identifiers. A name declared in an inner scope hides a typedef of it.
 #[derive(Debug, PartialEq, Clone)]
+pub struct TypedefTable {
+    scopes: Vec<HashMap<String, Option<TypedefNode>>>,
+}
+impl TypedefTable {
+    pub fn new() -> TypedefTable {
+        TypedefTable {
+            scopes: vec![HashMap::new()],
+        }
+    }
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+    /// Declare `name` in the innermost scope as a typedef, or as another
+    /// identifier when `typedef` is None.
+    pub fn declare(&mut self, name: &str, typedef: Option<TypedefNode>) {
+        let scope = self.scopes.last_mut().expect("a scope to declare in");
+        scope.insert(name.to_string(), typedef);
+    }
+    /// The typedef `name` refers to in the innermost scope declaring it.
+    pub fn get(&self, name: &str) -> Option<&TypedefNode> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .and_then(|typedef| typedef.as_ref())
+    }
+    pub fn is_type_name(&self, name: &str) -> bool {
+        is_type_specifier(name) || self.get(name).is_some()
+    }
+}
+impl Default for TypedefTable {
+    fn default() -> TypedefTable {
+        TypedefTable::new()
+    }
+}
+
+/// Parse with `parse` in a new scope of names.
+pub fn in_scope<T>(
+    tokens: &mut Tokens,
+    typedefs: &mut TypedefTable,
+    parse: impl FnOnce(&mut Tokens, &mut TypedefTable) -> ParseResult<T>,
+) -> ParseResult<T> {
+    typedefs.push_scope();
+    let node = parse(tokens, typedefs);
+    typedefs.pop_scope();
+    node
+}
+
+/// An error for the next token, which is not the `expected` one.
+pub fn unexpected(tokens: &Tokens, expected: &str) -> Diagnostic {
+    match tokens.peek_nth(0) {
+        Some(token) => Diagnostic::error(format!(
+            "expected {} but found \"{}\"",
+            expected,
+            token.debug_info().s
+        ))
+        .at(token.span()),
+        None => end_of_input(tokens, expected),
+    }
+}
+
+/// An error for input that ends where `expected` should be, pointing right after
+/// the last token.
+pub fn end_of_input(tokens: &Tokens, expected: &str) -> Diagnostic {
+    let diagnostic = Diagnostic::error(format!(
+        "expected {} but reached the end of the input",
+        expected
+    ));
+    match tokens.tokens.last() {
+        Some(token) => {
+            let span = token.span();
+            diagnostic.at(Span {
+                start: span.end,
+                ..span
+            })
+        }
+        None => diagnostic,
+    }
+}
+
+/// Pop the punctuator `punct`, which `is_expected` tells from other tokens.
+pub fn expect(
+    tokens: &mut Tokens,
+    punct: &str,
+    is_expected: fn(&Token) -> bool,
+) -> ParseResult<Token> {
+    match tokens.peek_nth(0) {
+        Some(token) if is_expected(token) => Ok(tokens.pop().unwrap()),
+        _ => Err(unexpected(tokens, &format!("\"{}\"", punct))),
+    }
+}
+
+/// Pop an identifier. Keywords cannot be used as names.
+pub fn expect_identifier(tokens: &mut Tokens) -> ParseResult<String> {
+    match tokens.peek_nth(0) {
+        Some(Token::Ide(val, _)) if is_keyword(val) => Err(Diagnostic::error(format!(
+            "expected identifier but found keyword \"{}\"",
+            val
+        ))
+        .at(tokens.peek_nth(0).unwrap().span())),
+        Some(Token::Ide(val, _)) => {
+            let val = val.clone();
+            tokens.pop();
+            Ok(val)
+        }
+        _ => Err(unexpected(tokens, "identifier")),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct StructDefinitionNode {
-    pub identifier: String,
-    pub members: Vec<(String, String)>,
+    /// None for a struct defined in a typedef without a tag.
+    pub tag: Option<String>,
+    pub members: Vec<DeclareVariableNode>,
+    pub span: Span,
 }
 impl StructDefinitionNode {
-    pub fn new(tokens: &mut Tokens) -> StructDefinitionNode {
+    /// Parse up to the closing `}`.
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<StructDefinitionNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume "struct"
-        let identifier = match tokens.pop().unwrap() {
-            Token::Ide(val, _) => val,
-            _ => panic!(),
-        }; // get type name
-        tokens.pop(); // consume {
+        let tag = match tokens.peek_nth(0) {
+            Some(Token::CurlyS(_)) => None,
+            _ => Some(expect_identifier(tokens)?),
+        };
+        expect(tokens, "{", |token| matches!(token, Token::CurlyS(_)))?;
 
-        let mut members: Vec<(String, String)> = Vec::new();
-        loop {
-            if let Some(Token::CurlyE(_)) = tokens.peek() {
-                tokens.pop(); // consume }
-                tokens.pop(); // consume ;
-                break;
+        // members are not ordinary identifiers, so they hide no typedef
+        let members = in_scope(tokens, typedefs, |tokens, typedefs| {
+            let mut members: Vec<DeclareVariableNode> = Vec::new();
+            loop {
+                match tokens.peek_nth(0) {
+                    Some(Token::CurlyE(_)) => {
+                        tokens.pop(); // consume }
+                        break;
+                    }
+                    None => return Err(end_of_input(tokens, "\"}\"")),
+                    _ => (),
+                }
+                let declare_statement_node = DeclareStatementNode::new(tokens, typedefs)?;
+                for declare_variable_node in declare_statement_node.declare_variable_nodes {
+                    if declare_variable_node.initializer.is_some() {
+                        return Err(Diagnostic::error(String::from(
+                            "a struct member cannot have an initializer",
+                        ))
+                        .at(declare_variable_node.span));
+                    }
+                    members.push(declare_variable_node);
+                }
             }
-            let declare_statement_node = DeclareStatementNode::new(tokens);
-            let declare_variable_node = declare_statement_node.declare_variable_node;
-            members.push((
-                declare_variable_node.identifier,
-                declare_variable_node.value_type,
-            ));
-        }
-        StructDefinitionNode {
-            identifier,
+            Ok(members)
+        })?;
+        Ok(StructDefinitionNode {
+            tag,
             members,
-        }
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct EnumDefinitionNode {
     pub tag: Option<String>,
     pub enums: Vec<DeclareVariableNode>,
+    pub span: Span,
 }
 impl EnumDefinitionNode {
-    pub fn new(tokens: &mut Tokens) -> EnumDefinitionNode {
+    /// Parse up to the closing `}`.
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<EnumDefinitionNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume "enum"
         let tag = if let Some(Token::Ide(ide, _)) = tokens.peek() {
             tokens.pop();
@@ -77,56 +320,29 @@ impl EnumDefinitionNode {
         } else {
             None
         };
-        tokens.pop(); // consume {
+        expect(tokens, "{", |token| matches!(token, Token::CurlyS(_)))?;
 
         let mut enums: Vec<DeclareVariableNode> = Vec::new();
-
-        let mut cnt = 0;
-        let debug_info = DebugInfo {
-            start: 0,
-            end: 0,
-            s: String::from("dummy token for enum value"),
-        };
-        let mut pre_init_expression = ExpressionNode::Token(TokenNode {
-            token: Token::IntNum("0".to_string(), debug_info.clone()),
-        });
         loop {
-            if let Some(Token::CurlyE(_)) = tokens.peek() {
-                tokens.pop(); // consume }
-                tokens.pop(); // consume ;
-                break;
-            }
-
-            // input dummy token to treat the enum as a variable declare.
-            // Ex. GREEN = 10 -> int GREEN = 10
-            tokens.reverse();
-            tokens.push(Token::Ide(String::from("int"), debug_info.clone()));
-            tokens.reverse();
-
-            let mut declare_variable_node =
-                DeclareVariableNode::new(tokens, true, Some(String::from(",")));
-
-            match declare_variable_node.clone().initialize_expression {
-                Some(init_expression) => pre_init_expression = init_expression,
-                None => {
-                    if cnt != 0 {
-                        if let ExpressionNode::Token(TokenNode {
-                            token: Token::IntNum(num_str, _),
-                        }) = pre_init_expression.clone()
-                        {
-                            let num: u64 = num_str.parse().unwrap();
-                            pre_init_expression = ExpressionNode::Token(TokenNode {
-                                token: Token::IntNum((num + 1).to_string(), debug_info.clone()),
-                            });
-                        } else {
-                            panic!()
-                        };
-                    }
-                    declare_variable_node.initialize_expression = Some(pre_init_expression.clone());
+            match tokens.peek_nth(0) {
+                Some(Token::CurlyE(_)) => {
+                    tokens.pop(); // consume }
+                    break;
                 }
+                None => return Err(end_of_input(tokens, "\"}\"")),
+                _ => (),
             }
-            cnt += 1;
 
+            // treat the enum as a variable declare.
+            // Ex. GREEN = 10 -> int GREEN = 10
+            // An enumerator without a value is given one by the emitter.
+            let declare_variable_node = DeclareVariableNode::new_with_type(
+                tokens,
+                typedefs,
+                CType::int(),
+                false,
+                Some(String::from(",")),
+            )?;
             enums.push(declare_variable_node);
             if let Some(Token::Op(op, _)) = tokens.peek() {
                 if op == "," {
@@ -135,6 +351,10 @@ impl EnumDefinitionNode {
             }
         }
 
-        EnumDefinitionNode { tag, enums }
+        Ok(EnumDefinitionNode {
+            tag,
+            enums,
+            span: tokens.span_from(start),
+        })
     }
 }
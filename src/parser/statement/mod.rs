@@ -1,11 +1,15 @@
+use crate::diagnostic::{Diagnostic, Span};
 use crate::lexer::token::*;
+use crate::parser::ctype::expect_type;
 use crate::parser::declare::*;
 use crate::parser::expression::ExpressionNode;
 use crate::parser::*;
 
+use serde::{Deserialize, Serialize};
+
 use std::collections::VecDeque;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StatementNode {
     Expression(ExpressionStatementNode),
     Return(ReturnStatementNode),
@@ -21,332 +25,548 @@ pub enum StatementNode {
     Default(DefaultStatementNode),
     Break(BreakStatementNode),
     Continue(ContinueStatementNode),
-    Undetermined(UndeterminedStatementNode),
-    Empty,
+    Typedef(TypedefNode),
+    Pragma(PragmaNode),
+    /// A lone `;`.
+    Empty(Span),
+    Error(ErrorNode),
 }
 impl StatementNode {
-    pub fn new(tokens: &mut Tokens) -> StatementNode {
-        match tokens.peek().unwrap() {
-            Token::Ide(_, _) => StatementNode::Undetermined(UndeterminedStatementNode::new(tokens)),
-            Token::Struct(_) => StatementNode::Struct(StructStatementNode::new(tokens)),
-            Token::Enum(_) => StatementNode::Enum(EnumStatementNode::new(tokens)),
-            Token::Return(_) => StatementNode::Return(ReturnStatementNode::new(tokens)),
-            Token::CurlyS(_) => StatementNode::Compound(CompoundStatementNode::new(tokens)),
-            Token::If(_) => StatementNode::If(IfStatementNode::new(tokens)),
-            Token::While(_) => StatementNode::While(WhileStatementNode::new(tokens)),
-            Token::Switch(_) => StatementNode::Switch(SwitchStatementNode::new(tokens)),
-            Token::Case(_) => StatementNode::Case(CaseStatementNode::new(tokens)),
-            Token::Default(_) => StatementNode::Default(DefaultStatementNode::new(tokens)),
-            Token::Break(_) => StatementNode::Break(BreakStatementNode::new(tokens)),
-            Token::Continue(_) => StatementNode::Continue(ContinueStatementNode::new(tokens)),
-            Token::For(_) => StatementNode::For(ForStatementNode::new(tokens)),
+    /// A statement with a syntax error is skipped and kept as `StatementNode::Error`,
+    /// unless the input ends inside it.
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<StatementNode> {
+        let start = tokens.checkpoint();
+        match StatementNode::new_without_recovery(tokens, typedefs) {
+            Ok(statement) => Ok(statement),
+            Err(diagnostic) => {
+                skip_statement(tokens, start);
+                match tokens.peek_nth(0) {
+                    Some(_) => Ok(StatementNode::Error(ErrorNode {
+                        diagnostic,
+                        span: tokens.span_from(start),
+                    })),
+                    None => Err(diagnostic),
+                }
+            }
+        }
+    }
+    fn new_without_recovery(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<StatementNode> {
+        let token = match tokens.peek() {
+            Some(token) => token,
+            None => return Err(end_of_input(tokens, "a statement")),
+        };
+        let statement = match token {
+            // the typedef names in scope tell a declaration from an expression
+            Token::Ide(ref name, _) if typedefs.is_type_name(name) => {
+                StatementNode::Declare(DeclareStatementNode::new(tokens, typedefs)?)
+            }
+            Token::Struct(_) => StatementNode::Struct(StructStatementNode::new(tokens, typedefs)?),
+            Token::Enum(_) => StatementNode::Enum(EnumStatementNode::new(tokens, typedefs)?),
+            Token::Return(_) => StatementNode::Return(ReturnStatementNode::new(tokens)?),
+            Token::CurlyS(_) => {
+                StatementNode::Compound(CompoundStatementNode::new(tokens, typedefs)?)
+            }
+            Token::If(_) => StatementNode::If(IfStatementNode::new(tokens, typedefs)?),
+            Token::While(_) => StatementNode::While(WhileStatementNode::new(tokens, typedefs)?),
+            Token::Switch(_) => StatementNode::Switch(SwitchStatementNode::new(tokens, typedefs)?),
+            Token::Case(_) => StatementNode::Case(CaseStatementNode::new(tokens, typedefs)?),
+            Token::Default(_) => {
+                StatementNode::Default(DefaultStatementNode::new(tokens, typedefs)?)
+            }
+            Token::Break(_) => StatementNode::Break(BreakStatementNode::new(tokens)?),
+            Token::Continue(_) => StatementNode::Continue(ContinueStatementNode::new(tokens)?),
+            Token::For(_) => StatementNode::For(ForStatementNode::new(tokens, typedefs)?),
+            Token::Pragma(_, _) => StatementNode::Pragma(PragmaNode::new(tokens)?),
             Token::Semi(_) => {
+                let start = tokens.checkpoint();
                 tokens.pop();
-                StatementNode::Empty
+                StatementNode::Empty(tokens.span_from(start))
             }
-            _ => StatementNode::Expression(ExpressionStatementNode::new(tokens)),
+            Token::Keyword(ref keyword, _) if keyword == "typedef" => {
+                StatementNode::Typedef(TypedefNode::new(tokens, typedefs)?)
+            }
+            Token::Keyword(ref keyword, _) => {
+                if keyword != "union" && !token.is_type_qualifier() {
+                    return Err(
+                        Diagnostic::error(format!("unsupported keyword \"{}\"", keyword))
+                            .at(token.span()),
+                    );
+                }
+                StatementNode::Declare(DeclareStatementNode::new(tokens, typedefs)?)
+            }
+            _ => StatementNode::Expression(ExpressionStatementNode::new(tokens)?),
+        };
+        Ok(statement)
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            StatementNode::Expression(node) => node.span.clone(),
+            StatementNode::Return(node) => node.span.clone(),
+            StatementNode::Declare(node) => node.span.clone(),
+            StatementNode::Struct(node) => node.span(),
+            StatementNode::Enum(node) => node.span(),
+            StatementNode::Compound(node) => node.span.clone(),
+            StatementNode::If(node) => node.span.clone(),
+            StatementNode::For(node) => node.span.clone(),
+            StatementNode::While(node) => node.span.clone(),
+            StatementNode::Switch(node) => node.span.clone(),
+            StatementNode::Case(node) => node.span.clone(),
+            StatementNode::Default(node) => node.span.clone(),
+            StatementNode::Break(node) => node.span.clone(),
+            StatementNode::Continue(node) => node.span.clone(),
+            StatementNode::Typedef(node) => node.span.clone(),
+            StatementNode::Pragma(node) => node.span.clone(),
+            StatementNode::Empty(span) => span.clone(),
+            StatementNode::Error(node) => node.span.clone(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Skip a statement with a syntax error, from its `start`: up to its `;`, through
+/// its last block, or up to the `}` closing the enclosing block.
+fn skip_statement(tokens: &mut Tokens, start: Checkpoint) {
+    let error = tokens.checkpoint();
+    tokens.rewind(start);
+    let (mut braces, mut parens) = (0, 0);
+    while let Some(token) = tokens.peek_nth(0) {
+        let is_after_error = tokens.checkpoint() >= error;
+        match token {
+            Token::Semi(_) if braces == 0 && parens == 0 && is_after_error => {
+                tokens.pop();
+                return;
+            }
+            Token::ParenS(_) => parens += 1,
+            Token::ParenE(_) if parens > 0 => parens -= 1,
+            Token::CurlyS(_) => braces += 1,
+            Token::CurlyE(_) if braces == 0 => return,
+            Token::CurlyE(_) if braces == 1 && is_after_error => {
+                tokens.pop();
+                // the else branch belongs to the skipped if statement
+                if let Some(Token::Else(_)) = tokens.peek_nth(0) {
+                    braces = 0;
+                    continue;
+                }
+                return;
+            }
+            Token::CurlyE(_) => braces -= 1,
+            _ => (),
+        }
+        tokens.pop();
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct CompoundStatementNode {
     pub statements: VecDeque<StatementNode>,
+    pub span: Span,
 }
 impl CompoundStatementNode {
-    pub fn new(tokens: &mut Tokens) -> CompoundStatementNode {
-        tokens.pop(); // consume {
-        let mut statements: VecDeque<StatementNode> = VecDeque::new();
-        loop {
-            if let Some(Token::CurlyE(_)) = tokens.peek() {
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<CompoundStatementNode> {
+        let start = tokens.checkpoint();
+        expect(tokens, "{", |token| matches!(token, Token::CurlyS(_)))?;
+        let statements = in_scope(tokens, typedefs, |tokens, typedefs| {
+            let mut statements: VecDeque<StatementNode> = VecDeque::new();
+            loop {
+                match tokens.peek_nth(0) {
+                    Some(Token::CurlyE(_)) => {
+                        tokens.pop();
+                        break;
+                    }
+                    None => return Err(end_of_input(tokens, "\"}\"")),
+                    _ => (),
+                }
+                let statement = StatementNode::new(tokens, typedefs)?;
+                statements.push_back(statement);
+            }
+            Ok(statements)
+        })?;
+        Ok(CompoundStatementNode {
+            statements,
+            span: tokens.span_from(start),
+        })
+    }
+}
+
+/// A `#pragma` the preprocessor did not handle, kept for later stages.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PragmaNode {
+    pub pragma: String,
+    pub span: Span,
+}
+impl PragmaNode {
+    pub fn new(tokens: &mut Tokens) -> ParseResult<PragmaNode> {
+        let start = tokens.checkpoint();
+        match tokens.peek() {
+            Some(Token::Pragma(pragma, _)) => {
                 tokens.pop();
-                break;
+                Ok(PragmaNode {
+                    pragma,
+                    span: tokens.span_from(start),
+                })
             }
-            let statement = StatementNode::new(tokens);
-            statements.push_back(statement);
+            _ => Err(unexpected(tokens, "#pragma")),
         }
-        CompoundStatementNode { statements }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ExpressionStatementNode {
     pub expression: ExpressionNode,
+    pub span: Span,
 }
 impl ExpressionStatementNode {
-    pub fn new(tokens: &mut Tokens) -> ExpressionStatementNode {
-        let expression = ExpressionNode::new(tokens, None);
-        match tokens.pop() {
-            Some(token) => match token {
-                Token::Semi(_) => (),
-                _ => panic!(token.get_debug_info()),
-            },
-            None => panic!("Expect an expression statement but got EOF"),
-        };
-        ExpressionStatementNode { expression }
+    pub fn new(tokens: &mut Tokens) -> ParseResult<ExpressionStatementNode> {
+        let start = tokens.checkpoint();
+        let expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        Ok(ExpressionStatementNode {
+            expression,
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ReturnStatementNode {
     pub expression: ExpressionNode,
+    pub span: Span,
 }
 impl ReturnStatementNode {
-    pub fn new(tokens: &mut Tokens) -> ReturnStatementNode {
+    pub fn new(tokens: &mut Tokens) -> ParseResult<ReturnStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume return
-        let expression = ExpressionNode::new(tokens, None);
-        match tokens.pop().unwrap() {
-            Token::Semi(_) => (),
-            _ => panic!(),
-        };
-        ReturnStatementNode { expression }
+        let expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        Ok(ReturnStatementNode {
+            expression,
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StructStatementNode {
-    Declare(DeclareStatementNode),
+    Declare(Box<DeclareStatementNode>),
     Definition(StructDefinitionNode),
 }
 impl StructStatementNode {
-    pub fn new(tokens: &mut Tokens) -> StructStatementNode {
-        let mut cloned_tokens = tokens.clone();
-        cloned_tokens.pop(); // consume "struct"
-        cloned_tokens.pop(); // consume identifier
-        match cloned_tokens.pop().unwrap() {
-            Token::CurlyS(_) => StructStatementNode::Definition(StructDefinitionNode::new(tokens)),
-            _ => StructStatementNode::Declare(DeclareStatementNode::new(tokens)),
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<StructStatementNode> {
+        // "struct" identifier "{"
+        match tokens.peek_nth(2) {
+            Some(Token::CurlyS(_)) => {
+                let start = tokens.checkpoint();
+                let node = StructDefinitionNode::new(tokens, typedefs)?;
+                expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+                Ok(StructStatementNode::Definition(StructDefinitionNode {
+                    span: tokens.span_from(start),
+                    ..node
+                }))
+            }
+            _ => Ok(StructStatementNode::Declare(Box::new(
+                DeclareStatementNode::new(tokens, typedefs)?,
+            ))),
+        }
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            StructStatementNode::Declare(node) => node.span.clone(),
+            StructStatementNode::Definition(node) => node.span.clone(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum EnumStatementNode {
-    Declare(DeclareStatementNode),
+    Declare(Box<DeclareStatementNode>),
     Definition(EnumDefinitionNode),
 }
 impl EnumStatementNode {
-    pub fn new(tokens: &mut Tokens) -> EnumStatementNode {
-        let mut cloned_tokens = tokens.clone();
-        cloned_tokens.pop(); // consume "enum"
-
-        if let Some(Token::Ide(_, _)) = cloned_tokens.peek() {
-            cloned_tokens.pop(); // consume enum tag
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<EnumStatementNode> {
+        // "enum" followed by an optional tag
+        let k = match tokens.peek_nth(1) {
+            Some(Token::Ide(_, _)) => 2,
+            _ => 1,
+        };
+        match tokens.peek_nth(k) {
+            Some(Token::CurlyS(_)) => {
+                let start = tokens.checkpoint();
+                let node = EnumDefinitionNode::new(tokens, typedefs)?;
+                expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+                Ok(EnumStatementNode::Definition(EnumDefinitionNode {
+                    span: tokens.span_from(start),
+                    ..node
+                }))
+            }
+            _ => Ok(EnumStatementNode::Declare(Box::new(
+                DeclareStatementNode::new(tokens, typedefs)?,
+            ))),
         }
-        match cloned_tokens.pop().unwrap() {
-            Token::CurlyS(_) => EnumStatementNode::Definition(EnumDefinitionNode::new(tokens)),
-            _ => EnumStatementNode::Declare(DeclareStatementNode::new(tokens)),
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            EnumStatementNode::Declare(node) => node.span.clone(),
+            EnumStatementNode::Definition(node) => node.span.clone(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DeclareStatementNode {
-    pub declare_variable_node: DeclareVariableNode,
+    /// One for each declarator, as in `int a, *p;`, with the type written out
+    /// in each.
+    pub declare_variable_nodes: Vec<DeclareVariableNode>,
+    pub span: Span,
 }
 impl DeclareStatementNode {
-    pub fn new(tokens: &mut Tokens) -> DeclareStatementNode {
-        let declare_variable_node = DeclareVariableNode::new(tokens, false, None);
-        match tokens.pop().unwrap() {
-            Token::Semi(_) => (),
-            _ => panic!(),
-        };
-        DeclareStatementNode {
-            declare_variable_node,
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<DeclareStatementNode> {
+        let start = tokens.checkpoint();
+        let value_type = expect_type(tokens, typedefs)?;
+        let mut declare_variable_nodes: Vec<DeclareVariableNode> = Vec::new();
+        loop {
+            let declare_variable_node = DeclareVariableNode::new_with_type(
+                tokens,
+                typedefs,
+                value_type.clone(),
+                false,
+                Some(String::from(",")),
+            )?;
+            declare_variable_nodes.push(declare_variable_node);
+            match tokens.peek_nth(0) {
+                Some(Token::Op(op, _)) if op == "," => {
+                    tokens.pop(); // consume ,
+                }
+                _ => break,
+            }
         }
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        Ok(DeclareStatementNode {
+            declare_variable_nodes,
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct IfStatementNode {
     pub condition_expression: ExpressionNode,
     pub block: Box<StatementNode>,
     pub else_block: Option<Box<StatementNode>>,
+    pub span: Span,
 }
 impl IfStatementNode {
-    pub fn new(tokens: &mut Tokens) -> IfStatementNode {
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<IfStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume if
-        tokens.pop(); // consume (
-        let condition_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume )
-        let block = Box::new(StatementNode::new(tokens));
+        expect(tokens, "(", |token| matches!(token, Token::ParenS(_)))?;
+        let condition_expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+        let block = Box::new(StatementNode::new(tokens, typedefs)?);
         let else_block = match tokens.peek() {
             Some(token) => match token {
                 Token::Else(_) => {
                     tokens.pop(); // consume else
-                    Some(Box::new(StatementNode::new(tokens)))
+                    Some(Box::new(StatementNode::new(tokens, typedefs)?))
                 }
                 _ => None,
             },
             None => None,
         };
-        IfStatementNode {
+        Ok(IfStatementNode {
             condition_expression,
             block,
             else_block,
-        }
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct WhileStatementNode {
     pub condition_expression: ExpressionNode,
     pub block: Box<StatementNode>,
+    pub span: Span,
 }
 impl WhileStatementNode {
-    pub fn new(tokens: &mut Tokens) -> WhileStatementNode {
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<WhileStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume while
-        tokens.pop(); // consume (
-        let condition_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume )
-        let block = Box::new(StatementNode::new(tokens));
-        WhileStatementNode {
+        expect(tokens, "(", |token| matches!(token, Token::ParenS(_)))?;
+        let condition_expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+        let block = Box::new(StatementNode::new(tokens, typedefs)?);
+        Ok(WhileStatementNode {
             condition_expression,
             block,
-        }
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SwitchStatementNode {
     pub condition_expression: ExpressionNode,
     pub statements: CompoundStatementNode,
+    pub span: Span,
 }
 impl SwitchStatementNode {
-    pub fn new(tokens: &mut Tokens) -> SwitchStatementNode {
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<SwitchStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume switch
-        tokens.pop(); // consume (
-        let condition_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume )
-        let statements = CompoundStatementNode::new(tokens);
-        SwitchStatementNode {
+        expect(tokens, "(", |token| matches!(token, Token::ParenS(_)))?;
+        let condition_expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+        let statements = CompoundStatementNode::new(tokens, typedefs)?;
+        Ok(SwitchStatementNode {
             condition_expression,
             statements,
-        }
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DefaultStatementNode {
     pub statements: CompoundStatementNode,
+    pub span: Span,
 }
 impl DefaultStatementNode {
-    pub fn new(tokens: &mut Tokens) -> DefaultStatementNode {
+    pub fn new(
+        tokens: &mut Tokens,
+        typedefs: &mut TypedefTable,
+    ) -> ParseResult<DefaultStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume default
-        tokens.pop(); // consume :
-
-        let mut statements: VecDeque<StatementNode> = VecDeque::new();
-        loop {
-            if let Some(token) = tokens.peek() {
-                match token {
-                    Token::Case(_) | Token::Default(_) | Token::CurlyE(_) => break,
-                    _ => {
-                        let statement = StatementNode::new(tokens);
-                        statements.push_back(statement);
-                    }
-                }
-            }
-        }
-        let statements = CompoundStatementNode { statements };
-        DefaultStatementNode { statements }
+        expect(tokens, ":", |token| matches!(token, Token::Colon(_)))?;
+        let statements = case_statements(tokens, typedefs)?;
+        Ok(DefaultStatementNode {
+            statements,
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct CaseStatementNode {
     pub condition_expression: ExpressionNode,
     pub statements: CompoundStatementNode,
+    pub span: Span,
 }
 impl CaseStatementNode {
-    pub fn new(tokens: &mut Tokens) -> CaseStatementNode {
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<CaseStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume case
-        let condition_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume :
-
-        let mut statements: VecDeque<StatementNode> = VecDeque::new();
-        loop {
-            if let Some(token) = tokens.peek() {
-                match token {
-                    Token::Case(_) | Token::Default(_) | Token::CurlyE(_) => break,
-                    _ => {
-                        let statement = StatementNode::new(tokens);
-                        statements.push_back(statement);
-                    }
-                }
-            }
-        }
-        let statements = CompoundStatementNode { statements };
-        CaseStatementNode {
+        let condition_expression = ExpressionNode::new(tokens, None)?;
+        expect(tokens, ":", |token| matches!(token, Token::Colon(_)))?;
+        let statements = case_statements(tokens, typedefs)?;
+        Ok(CaseStatementNode {
             condition_expression,
             statements,
+            span: tokens.span_from(start),
+        })
+    }
+}
+
+/// The statements after a `case` or `default` label, up to the next label or the
+/// end of the `switch`.
+fn case_statements(
+    tokens: &mut Tokens,
+    typedefs: &mut TypedefTable,
+) -> ParseResult<CompoundStatementNode> {
+    let start = tokens.checkpoint();
+    let mut statements: VecDeque<StatementNode> = VecDeque::new();
+    loop {
+        match tokens.peek_nth(0) {
+            Some(Token::Case(_)) | Some(Token::Default(_)) | Some(Token::CurlyE(_)) => break,
+            Some(_) => {
+                let statement = StatementNode::new(tokens, typedefs)?;
+                statements.push_back(statement);
+            }
+            None => return Err(end_of_input(tokens, "\"}\"")),
         }
     }
+    Ok(CompoundStatementNode {
+        statements,
+        span: tokens.span_from(start),
+    })
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct BreakStatementNode {}
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BreakStatementNode {
+    pub span: Span,
+}
 impl BreakStatementNode {
-    pub fn new(tokens: &mut Tokens) -> BreakStatementNode {
+    pub fn new(tokens: &mut Tokens) -> ParseResult<BreakStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume break
-        tokens.pop(); // consume ;
-        BreakStatementNode {}
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        Ok(BreakStatementNode {
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct ContinueStatementNode {}
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ContinueStatementNode {
+    pub span: Span,
+}
 impl ContinueStatementNode {
-    pub fn new(tokens: &mut Tokens) -> ContinueStatementNode {
+    pub fn new(tokens: &mut Tokens) -> ParseResult<ContinueStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume continue
-        tokens.pop(); // consume ;
-        ContinueStatementNode {}
+        expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+        Ok(ContinueStatementNode {
+            span: tokens.span_from(start),
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ForStatementNode {
     pub first_statement: Box<StatementNode>,
     pub condition_expression: ExpressionNode,
     pub loop_expression: ExpressionNode,
     pub block: Box<StatementNode>,
+    pub span: Span,
 }
 impl ForStatementNode {
-    pub fn new(tokens: &mut Tokens) -> ForStatementNode {
+    pub fn new(tokens: &mut Tokens, typedefs: &mut TypedefTable) -> ParseResult<ForStatementNode> {
+        let start = tokens.checkpoint();
         tokens.pop(); // consume for
-        tokens.pop(); // consume (
+        expect(tokens, "(", |token| matches!(token, Token::ParenS(_)))?;
 
-        let first_statement = Box::new(StatementNode::new(tokens));
-        let condition_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume ;
-        let loop_expression = ExpressionNode::new(tokens, None);
-        tokens.pop(); // consume )
-        let block = Box::new(StatementNode::new(tokens));
-        ForStatementNode {
-            first_statement,
-            condition_expression,
-            loop_expression,
-            block,
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct UndeterminedStatementNode {
-    pub tokens: Tokens,
-}
-impl UndeterminedStatementNode {
-    pub fn new(tokens: &mut Tokens) -> UndeterminedStatementNode {
-        let mut statement_tokens: Vec<Token> = Vec::new();
-        while let Some(token) = tokens.pop() {
-            statement_tokens.push(token.clone());
-            if let Token::Semi(_) = token {
-                break;
-            }
-        }
-        UndeterminedStatementNode {
-            tokens: Tokens {
-                tokens: statement_tokens,
-            },
-        }
+        // a variable declared in the first statement is in scope in the loop only
+        in_scope(tokens, typedefs, |tokens, typedefs| {
+            let first_statement = Box::new(StatementNode::new(tokens, typedefs)?);
+            let condition_expression = ExpressionNode::new(tokens, None)?;
+            expect(tokens, ";", |token| matches!(token, Token::Semi(_)))?;
+            let loop_expression = ExpressionNode::new(tokens, None)?;
+            expect(tokens, ")", |token| matches!(token, Token::ParenE(_)))?;
+            let block = Box::new(StatementNode::new(tokens, typedefs)?);
+            Ok(ForStatementNode {
+                first_statement,
+                condition_expression,
+                loop_expression,
+                block,
+                span: tokens.span_from(start),
+            })
+        })
     }
 }
@@ -0,0 +1,678 @@
+//! Traversal of the AST. A pass implements `Visitor` or `VisitorMut` and overrides
+//! only the nodes it cares about; the default methods call the `walk_*` functions,
+//! which visit the children of a node in source order. An overriding method calls
+//! the `walk_*` function itself to keep going into the children.
+
+use crate::parser::declare::initializer::*;
+use crate::parser::declare::*;
+use crate::parser::expression::*;
+use crate::parser::statement::*;
+use crate::parser::*;
+
+pub trait Visitor {
+    fn visit_program(&mut self, node: &ProgramNode) {
+        walk_program(self, node)
+    }
+    fn visit_declare(&mut self, node: &DeclareNode) {
+        walk_declare(self, node)
+    }
+    fn visit_function(&mut self, node: &FunctionNode) {
+        walk_function(self, node)
+    }
+    fn visit_declare_variable(&mut self, node: &DeclareVariableNode) {
+        walk_declare_variable(self, node)
+    }
+    fn visit_initializer(&mut self, node: &Initializer) {
+        walk_initializer(self, node)
+    }
+    fn visit_initializer_list(&mut self, node: &InitializerListNode) {
+        walk_initializer_list(self, node)
+    }
+    fn visit_struct_definition(&mut self, node: &StructDefinitionNode) {
+        walk_struct_definition(self, node)
+    }
+    fn visit_enum_definition(&mut self, node: &EnumDefinitionNode) {
+        walk_enum_definition(self, node)
+    }
+    fn visit_typedef(&mut self, node: &TypedefNode) {
+        walk_typedef(self, node)
+    }
+    fn visit_error(&mut self, _node: &ErrorNode) {}
+
+    fn visit_statement(&mut self, node: &StatementNode) {
+        walk_statement(self, node)
+    }
+    fn visit_compound_statement(&mut self, node: &CompoundStatementNode) {
+        walk_compound_statement(self, node)
+    }
+    fn visit_pragma(&mut self, _node: &PragmaNode) {}
+    fn visit_expression_statement(&mut self, node: &ExpressionStatementNode) {
+        walk_expression_statement(self, node)
+    }
+    fn visit_return_statement(&mut self, node: &ReturnStatementNode) {
+        walk_return_statement(self, node)
+    }
+    fn visit_struct_statement(&mut self, node: &StructStatementNode) {
+        walk_struct_statement(self, node)
+    }
+    fn visit_enum_statement(&mut self, node: &EnumStatementNode) {
+        walk_enum_statement(self, node)
+    }
+    fn visit_declare_statement(&mut self, node: &DeclareStatementNode) {
+        walk_declare_statement(self, node)
+    }
+    fn visit_if_statement(&mut self, node: &IfStatementNode) {
+        walk_if_statement(self, node)
+    }
+    fn visit_while_statement(&mut self, node: &WhileStatementNode) {
+        walk_while_statement(self, node)
+    }
+    fn visit_for_statement(&mut self, node: &ForStatementNode) {
+        walk_for_statement(self, node)
+    }
+    fn visit_switch_statement(&mut self, node: &SwitchStatementNode) {
+        walk_switch_statement(self, node)
+    }
+    fn visit_case_statement(&mut self, node: &CaseStatementNode) {
+        walk_case_statement(self, node)
+    }
+    fn visit_default_statement(&mut self, node: &DefaultStatementNode) {
+        walk_default_statement(self, node)
+    }
+    fn visit_break_statement(&mut self, _node: &BreakStatementNode) {}
+    fn visit_continue_statement(&mut self, _node: &ContinueStatementNode) {}
+
+    fn visit_expression(&mut self, node: &ExpressionNode) {
+        walk_expression(self, node)
+    }
+    fn visit_bin_exp(&mut self, node: &BinExpNode) {
+        walk_bin_exp(self, node)
+    }
+    fn visit_ternary_exp(&mut self, node: &TernaryExpNode) {
+        walk_ternary_exp(self, node)
+    }
+    fn visit_prefix(&mut self, node: &PrefixNode) {
+        walk_prefix(self, node)
+    }
+    fn visit_suffix(&mut self, node: &SuffixNode) {
+        walk_suffix(self, node)
+    }
+    fn visit_array_index(&mut self, node: &ArrayIndexNode) {
+        walk_array_index(self, node)
+    }
+    fn visit_function_call(&mut self, node: &FunctionCallNode) {
+        walk_function_call(self, node)
+    }
+    fn visit_access(&mut self, node: &AccessNode) {
+        walk_access(self, node)
+    }
+    fn visit_token(&mut self, _node: &TokenNode) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, node: &ProgramNode) {
+    for declare in &node.declares {
+        visitor.visit_declare(declare);
+    }
+}
+
+pub fn walk_declare<V: Visitor + ?Sized>(visitor: &mut V, node: &DeclareNode) {
+    match node {
+        DeclareNode::Function(node) => visitor.visit_function(node),
+        DeclareNode::Variable(node) => visitor.visit_declare_statement(node),
+        DeclareNode::Pragma(node) => visitor.visit_pragma(node),
+        DeclareNode::Typedef(node) => visitor.visit_typedef(node),
+        DeclareNode::Error(node) => visitor.visit_error(node),
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, node: &FunctionNode) {
+    for parameter in &node.parameters {
+        visitor.visit_declare_variable(parameter);
+    }
+    for statement in node.statements.iter().flatten() {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_declare_variable<V: Visitor + ?Sized>(visitor: &mut V, node: &DeclareVariableNode) {
+    if let Some(initializer) = &node.initializer {
+        visitor.visit_initializer(initializer);
+    }
+}
+
+pub fn walk_initializer<V: Visitor + ?Sized>(visitor: &mut V, node: &Initializer) {
+    match node {
+        Initializer::Expression(node) => visitor.visit_expression(node),
+        Initializer::List(node) => visitor.visit_initializer_list(node),
+    }
+}
+
+pub fn walk_initializer_list<V: Visitor + ?Sized>(visitor: &mut V, node: &InitializerListNode) {
+    for item in &node.items {
+        visitor.visit_initializer(&item.initializer);
+    }
+}
+
+pub fn walk_struct_definition<V: Visitor + ?Sized>(visitor: &mut V, node: &StructDefinitionNode) {
+    for member in &node.members {
+        visitor.visit_declare_variable(member);
+    }
+}
+
+pub fn walk_enum_definition<V: Visitor + ?Sized>(visitor: &mut V, node: &EnumDefinitionNode) {
+    for enumerator in &node.enums {
+        visitor.visit_declare_variable(enumerator);
+    }
+}
+
+pub fn walk_typedef<V: Visitor + ?Sized>(visitor: &mut V, node: &TypedefNode) {
+    match &node.definition {
+        Some(TypeDefinitionNode::Struct(node)) => visitor.visit_struct_definition(node),
+        Some(TypeDefinitionNode::Enum(node)) => visitor.visit_enum_definition(node),
+        None => (),
+    }
+    for parameter in node.parameters.iter().flatten() {
+        visitor.visit_declare_variable(parameter);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &StatementNode) {
+    match node {
+        StatementNode::Expression(node) => visitor.visit_expression_statement(node),
+        StatementNode::Return(node) => visitor.visit_return_statement(node),
+        StatementNode::Declare(node) => visitor.visit_declare_statement(node),
+        StatementNode::Struct(node) => visitor.visit_struct_statement(node),
+        StatementNode::Enum(node) => visitor.visit_enum_statement(node),
+        StatementNode::Compound(node) => visitor.visit_compound_statement(node),
+        StatementNode::If(node) => visitor.visit_if_statement(node),
+        StatementNode::For(node) => visitor.visit_for_statement(node),
+        StatementNode::While(node) => visitor.visit_while_statement(node),
+        StatementNode::Switch(node) => visitor.visit_switch_statement(node),
+        StatementNode::Case(node) => visitor.visit_case_statement(node),
+        StatementNode::Default(node) => visitor.visit_default_statement(node),
+        StatementNode::Break(node) => visitor.visit_break_statement(node),
+        StatementNode::Continue(node) => visitor.visit_continue_statement(node),
+        StatementNode::Typedef(node) => visitor.visit_typedef(node),
+        StatementNode::Pragma(node) => visitor.visit_pragma(node),
+        StatementNode::Empty(_) => (),
+        StatementNode::Error(node) => visitor.visit_error(node),
+    }
+}
+
+pub fn walk_compound_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &CompoundStatementNode) {
+    for statement in &node.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_expression_statement<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    node: &ExpressionStatementNode,
+) {
+    visitor.visit_expression(&node.expression);
+}
+
+pub fn walk_return_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &ReturnStatementNode) {
+    visitor.visit_expression(&node.expression);
+}
+
+pub fn walk_struct_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &StructStatementNode) {
+    match node {
+        StructStatementNode::Declare(node) => visitor.visit_declare_statement(node),
+        StructStatementNode::Definition(node) => visitor.visit_struct_definition(node),
+    }
+}
+
+pub fn walk_enum_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &EnumStatementNode) {
+    match node {
+        EnumStatementNode::Declare(node) => visitor.visit_declare_statement(node),
+        EnumStatementNode::Definition(node) => visitor.visit_enum_definition(node),
+    }
+}
+
+pub fn walk_declare_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &DeclareStatementNode) {
+    for declare_variable_node in &node.declare_variable_nodes {
+        visitor.visit_declare_variable(declare_variable_node);
+    }
+}
+
+pub fn walk_if_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &IfStatementNode) {
+    visitor.visit_expression(&node.condition_expression);
+    visitor.visit_statement(&node.block);
+    if let Some(else_block) = &node.else_block {
+        visitor.visit_statement(else_block);
+    }
+}
+
+pub fn walk_while_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &WhileStatementNode) {
+    visitor.visit_expression(&node.condition_expression);
+    visitor.visit_statement(&node.block);
+}
+
+pub fn walk_for_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &ForStatementNode) {
+    visitor.visit_statement(&node.first_statement);
+    visitor.visit_expression(&node.condition_expression);
+    visitor.visit_expression(&node.loop_expression);
+    visitor.visit_statement(&node.block);
+}
+
+pub fn walk_switch_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &SwitchStatementNode) {
+    visitor.visit_expression(&node.condition_expression);
+    visitor.visit_compound_statement(&node.statements);
+}
+
+pub fn walk_case_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &CaseStatementNode) {
+    visitor.visit_expression(&node.condition_expression);
+    visitor.visit_compound_statement(&node.statements);
+}
+
+pub fn walk_default_statement<V: Visitor + ?Sized>(visitor: &mut V, node: &DefaultStatementNode) {
+    visitor.visit_compound_statement(&node.statements);
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, node: &ExpressionNode) {
+    match node {
+        ExpressionNode::BinExp(node) => visitor.visit_bin_exp(node),
+        ExpressionNode::TernaryExp(node) => visitor.visit_ternary_exp(node),
+        ExpressionNode::Prefix(node) => visitor.visit_prefix(node),
+        ExpressionNode::Suffix(node) => visitor.visit_suffix(node),
+        ExpressionNode::ArrayIndex(node) => visitor.visit_array_index(node),
+        ExpressionNode::FunctionCall(node) => visitor.visit_function_call(node),
+        ExpressionNode::Access(node) => visitor.visit_access(node),
+        ExpressionNode::Token(node) => visitor.visit_token(node),
+        ExpressionNode::Empty(_) => (),
+    }
+}
+
+pub fn walk_bin_exp<V: Visitor + ?Sized>(visitor: &mut V, node: &BinExpNode) {
+    visitor.visit_expression(&node.lhs);
+    visitor.visit_token(&node.op);
+    visitor.visit_expression(&node.rhs);
+}
+
+pub fn walk_ternary_exp<V: Visitor + ?Sized>(visitor: &mut V, node: &TernaryExpNode) {
+    visitor.visit_expression(&node.condition);
+    visitor.visit_expression(&node.lhs);
+    visitor.visit_expression(&node.rhs);
+}
+
+pub fn walk_prefix<V: Visitor + ?Sized>(visitor: &mut V, node: &PrefixNode) {
+    visitor.visit_token(&node.prefix);
+    visitor.visit_expression(&node.node);
+}
+
+pub fn walk_suffix<V: Visitor + ?Sized>(visitor: &mut V, node: &SuffixNode) {
+    visitor.visit_expression(&node.node);
+    visitor.visit_token(&node.suffix);
+}
+
+pub fn walk_array_index<V: Visitor + ?Sized>(visitor: &mut V, node: &ArrayIndexNode) {
+    visitor.visit_expression(&node.array);
+    visitor.visit_expression(&node.index);
+}
+
+pub fn walk_function_call<V: Visitor + ?Sized>(visitor: &mut V, node: &FunctionCallNode) {
+    visitor.visit_token(&node.identifier);
+    visitor.visit_expression(&node.parameters);
+}
+
+pub fn walk_access<V: Visitor + ?Sized>(visitor: &mut V, node: &AccessNode) {
+    visitor.visit_expression(&node.node);
+}
+
+/// Like `Visitor`, for passes that rewrite the AST in place.
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, node: &mut ProgramNode) {
+        walk_program_mut(self, node)
+    }
+    fn visit_declare_mut(&mut self, node: &mut DeclareNode) {
+        walk_declare_mut(self, node)
+    }
+    fn visit_function_mut(&mut self, node: &mut FunctionNode) {
+        walk_function_mut(self, node)
+    }
+    fn visit_declare_variable_mut(&mut self, node: &mut DeclareVariableNode) {
+        walk_declare_variable_mut(self, node)
+    }
+    fn visit_initializer_mut(&mut self, node: &mut Initializer) {
+        walk_initializer_mut(self, node)
+    }
+    fn visit_initializer_list_mut(&mut self, node: &mut InitializerListNode) {
+        walk_initializer_list_mut(self, node)
+    }
+    fn visit_struct_definition_mut(&mut self, node: &mut StructDefinitionNode) {
+        walk_struct_definition_mut(self, node)
+    }
+    fn visit_enum_definition_mut(&mut self, node: &mut EnumDefinitionNode) {
+        walk_enum_definition_mut(self, node)
+    }
+    fn visit_typedef_mut(&mut self, node: &mut TypedefNode) {
+        walk_typedef_mut(self, node)
+    }
+    fn visit_error_mut(&mut self, _node: &mut ErrorNode) {}
+
+    fn visit_statement_mut(&mut self, node: &mut StatementNode) {
+        walk_statement_mut(self, node)
+    }
+    fn visit_compound_statement_mut(&mut self, node: &mut CompoundStatementNode) {
+        walk_compound_statement_mut(self, node)
+    }
+    fn visit_pragma_mut(&mut self, _node: &mut PragmaNode) {}
+    fn visit_expression_statement_mut(&mut self, node: &mut ExpressionStatementNode) {
+        walk_expression_statement_mut(self, node)
+    }
+    fn visit_return_statement_mut(&mut self, node: &mut ReturnStatementNode) {
+        walk_return_statement_mut(self, node)
+    }
+    fn visit_struct_statement_mut(&mut self, node: &mut StructStatementNode) {
+        walk_struct_statement_mut(self, node)
+    }
+    fn visit_enum_statement_mut(&mut self, node: &mut EnumStatementNode) {
+        walk_enum_statement_mut(self, node)
+    }
+    fn visit_declare_statement_mut(&mut self, node: &mut DeclareStatementNode) {
+        walk_declare_statement_mut(self, node)
+    }
+    fn visit_if_statement_mut(&mut self, node: &mut IfStatementNode) {
+        walk_if_statement_mut(self, node)
+    }
+    fn visit_while_statement_mut(&mut self, node: &mut WhileStatementNode) {
+        walk_while_statement_mut(self, node)
+    }
+    fn visit_for_statement_mut(&mut self, node: &mut ForStatementNode) {
+        walk_for_statement_mut(self, node)
+    }
+    fn visit_switch_statement_mut(&mut self, node: &mut SwitchStatementNode) {
+        walk_switch_statement_mut(self, node)
+    }
+    fn visit_case_statement_mut(&mut self, node: &mut CaseStatementNode) {
+        walk_case_statement_mut(self, node)
+    }
+    fn visit_default_statement_mut(&mut self, node: &mut DefaultStatementNode) {
+        walk_default_statement_mut(self, node)
+    }
+    fn visit_break_statement_mut(&mut self, _node: &mut BreakStatementNode) {}
+    fn visit_continue_statement_mut(&mut self, _node: &mut ContinueStatementNode) {}
+
+    fn visit_expression_mut(&mut self, node: &mut ExpressionNode) {
+        walk_expression_mut(self, node)
+    }
+    fn visit_bin_exp_mut(&mut self, node: &mut BinExpNode) {
+        walk_bin_exp_mut(self, node)
+    }
+    fn visit_ternary_exp_mut(&mut self, node: &mut TernaryExpNode) {
+        walk_ternary_exp_mut(self, node)
+    }
+    fn visit_prefix_mut(&mut self, node: &mut PrefixNode) {
+        walk_prefix_mut(self, node)
+    }
+    fn visit_suffix_mut(&mut self, node: &mut SuffixNode) {
+        walk_suffix_mut(self, node)
+    }
+    fn visit_array_index_mut(&mut self, node: &mut ArrayIndexNode) {
+        walk_array_index_mut(self, node)
+    }
+    fn visit_function_call_mut(&mut self, node: &mut FunctionCallNode) {
+        walk_function_call_mut(self, node)
+    }
+    fn visit_access_mut(&mut self, node: &mut AccessNode) {
+        walk_access_mut(self, node)
+    }
+    fn visit_token_mut(&mut self, _node: &mut TokenNode) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut ProgramNode) {
+    for declare in &mut node.declares {
+        visitor.visit_declare_mut(declare);
+    }
+}
+
+pub fn walk_declare_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut DeclareNode) {
+    match node {
+        DeclareNode::Function(node) => visitor.visit_function_mut(node),
+        DeclareNode::Variable(node) => visitor.visit_declare_statement_mut(node),
+        DeclareNode::Pragma(node) => visitor.visit_pragma_mut(node),
+        DeclareNode::Typedef(node) => visitor.visit_typedef_mut(node),
+        DeclareNode::Error(node) => visitor.visit_error_mut(node),
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut FunctionNode) {
+    for parameter in &mut node.parameters {
+        visitor.visit_declare_variable_mut(parameter);
+    }
+    for statement in node.statements.iter_mut().flatten() {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_declare_variable_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut DeclareVariableNode,
+) {
+    if let Some(initializer) = &mut node.initializer {
+        visitor.visit_initializer_mut(initializer);
+    }
+}
+
+pub fn walk_initializer_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut Initializer) {
+    match node {
+        Initializer::Expression(node) => visitor.visit_expression_mut(node),
+        Initializer::List(node) => visitor.visit_initializer_list_mut(node),
+    }
+}
+
+pub fn walk_initializer_list_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut InitializerListNode,
+) {
+    for item in &mut node.items {
+        visitor.visit_initializer_mut(&mut item.initializer);
+    }
+}
+
+pub fn walk_struct_definition_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut StructDefinitionNode,
+) {
+    for member in &mut node.members {
+        visitor.visit_declare_variable_mut(member);
+    }
+}
+
+pub fn walk_enum_definition_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut EnumDefinitionNode,
+) {
+    for enumerator in &mut node.enums {
+        visitor.visit_declare_variable_mut(enumerator);
+    }
+}
+
+pub fn walk_typedef_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut TypedefNode) {
+    match &mut node.definition {
+        Some(TypeDefinitionNode::Struct(node)) => visitor.visit_struct_definition_mut(node),
+        Some(TypeDefinitionNode::Enum(node)) => visitor.visit_enum_definition_mut(node),
+        None => (),
+    }
+    for parameter in node.parameters.iter_mut().flatten() {
+        visitor.visit_declare_variable_mut(parameter);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut StatementNode) {
+    match node {
+        StatementNode::Expression(node) => visitor.visit_expression_statement_mut(node),
+        StatementNode::Return(node) => visitor.visit_return_statement_mut(node),
+        StatementNode::Declare(node) => visitor.visit_declare_statement_mut(node),
+        StatementNode::Struct(node) => visitor.visit_struct_statement_mut(node),
+        StatementNode::Enum(node) => visitor.visit_enum_statement_mut(node),
+        StatementNode::Compound(node) => visitor.visit_compound_statement_mut(node),
+        StatementNode::If(node) => visitor.visit_if_statement_mut(node),
+        StatementNode::For(node) => visitor.visit_for_statement_mut(node),
+        StatementNode::While(node) => visitor.visit_while_statement_mut(node),
+        StatementNode::Switch(node) => visitor.visit_switch_statement_mut(node),
+        StatementNode::Case(node) => visitor.visit_case_statement_mut(node),
+        StatementNode::Default(node) => visitor.visit_default_statement_mut(node),
+        StatementNode::Break(node) => visitor.visit_break_statement_mut(node),
+        StatementNode::Continue(node) => visitor.visit_continue_statement_mut(node),
+        StatementNode::Typedef(node) => visitor.visit_typedef_mut(node),
+        StatementNode::Pragma(node) => visitor.visit_pragma_mut(node),
+        StatementNode::Empty(_) => (),
+        StatementNode::Error(node) => visitor.visit_error_mut(node),
+    }
+}
+
+pub fn walk_compound_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut CompoundStatementNode,
+) {
+    for statement in &mut node.statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_expression_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut ExpressionStatementNode,
+) {
+    visitor.visit_expression_mut(&mut node.expression);
+}
+
+pub fn walk_return_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut ReturnStatementNode,
+) {
+    visitor.visit_expression_mut(&mut node.expression);
+}
+
+pub fn walk_struct_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut StructStatementNode,
+) {
+    match node {
+        StructStatementNode::Declare(node) => visitor.visit_declare_statement_mut(node),
+        StructStatementNode::Definition(node) => visitor.visit_struct_definition_mut(node),
+    }
+}
+
+pub fn walk_enum_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut EnumStatementNode,
+) {
+    match node {
+        EnumStatementNode::Declare(node) => visitor.visit_declare_statement_mut(node),
+        EnumStatementNode::Definition(node) => visitor.visit_enum_definition_mut(node),
+    }
+}
+
+pub fn walk_declare_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut DeclareStatementNode,
+) {
+    for declare_variable_node in &mut node.declare_variable_nodes {
+        visitor.visit_declare_variable_mut(declare_variable_node);
+    }
+}
+
+pub fn walk_if_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut IfStatementNode) {
+    visitor.visit_expression_mut(&mut node.condition_expression);
+    visitor.visit_statement_mut(&mut node.block);
+    if let Some(else_block) = &mut node.else_block {
+        visitor.visit_statement_mut(else_block);
+    }
+}
+
+pub fn walk_while_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut WhileStatementNode,
+) {
+    visitor.visit_expression_mut(&mut node.condition_expression);
+    visitor.visit_statement_mut(&mut node.block);
+}
+
+pub fn walk_for_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut ForStatementNode,
+) {
+    visitor.visit_statement_mut(&mut node.first_statement);
+    visitor.visit_expression_mut(&mut node.condition_expression);
+    visitor.visit_expression_mut(&mut node.loop_expression);
+    visitor.visit_statement_mut(&mut node.block);
+}
+
+pub fn walk_switch_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut SwitchStatementNode,
+) {
+    visitor.visit_expression_mut(&mut node.condition_expression);
+    visitor.visit_compound_statement_mut(&mut node.statements);
+}
+
+pub fn walk_case_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut CaseStatementNode,
+) {
+    visitor.visit_expression_mut(&mut node.condition_expression);
+    visitor.visit_compound_statement_mut(&mut node.statements);
+}
+
+pub fn walk_default_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut DefaultStatementNode,
+) {
+    visitor.visit_compound_statement_mut(&mut node.statements);
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut ExpressionNode) {
+    match node {
+        ExpressionNode::BinExp(node) => visitor.visit_bin_exp_mut(node),
+        ExpressionNode::TernaryExp(node) => visitor.visit_ternary_exp_mut(node),
+        ExpressionNode::Prefix(node) => visitor.visit_prefix_mut(node),
+        ExpressionNode::Suffix(node) => visitor.visit_suffix_mut(node),
+        ExpressionNode::ArrayIndex(node) => visitor.visit_array_index_mut(node),
+        ExpressionNode::FunctionCall(node) => visitor.visit_function_call_mut(node),
+        ExpressionNode::Access(node) => visitor.visit_access_mut(node),
+        ExpressionNode::Token(node) => visitor.visit_token_mut(node),
+        ExpressionNode::Empty(_) => (),
+    }
+}
+
+pub fn walk_bin_exp_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut BinExpNode) {
+    visitor.visit_expression_mut(&mut node.lhs);
+    visitor.visit_token_mut(&mut node.op);
+    visitor.visit_expression_mut(&mut node.rhs);
+}
+
+pub fn walk_ternary_exp_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut TernaryExpNode) {
+    visitor.visit_expression_mut(&mut node.condition);
+    visitor.visit_expression_mut(&mut node.lhs);
+    visitor.visit_expression_mut(&mut node.rhs);
+}
+
+pub fn walk_prefix_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut PrefixNode) {
+    visitor.visit_token_mut(&mut node.prefix);
+    visitor.visit_expression_mut(&mut node.node);
+}
+
+pub fn walk_suffix_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut SuffixNode) {
+    visitor.visit_expression_mut(&mut node.node);
+    visitor.visit_token_mut(&mut node.suffix);
+}
+
+pub fn walk_array_index_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut ArrayIndexNode) {
+    visitor.visit_expression_mut(&mut node.array);
+    visitor.visit_expression_mut(&mut node.index);
+}
+
+pub fn walk_function_call_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    node: &mut FunctionCallNode,
+) {
+    visitor.visit_token_mut(&mut node.identifier);
+    visitor.visit_expression_mut(&mut node.parameters);
+}
+
+pub fn walk_access_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut AccessNode) {
+    visitor.visit_expression_mut(&mut node.node);
+}
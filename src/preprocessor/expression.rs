@@ -0,0 +1,374 @@
+use crate::lexer::literal::*;
+use crate::preprocessor::line_map::*;
+use crate::preprocessor::macros::*;
+use crate::preprocessor::token::*;
+
+/// Value of a preprocessor expression. Every integer is computed in the widest
+/// type, signed or unsigned, as `intmax_t` and `uintmax_t` in C.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PpValue {
+    pub value: i64,
+    pub is_unsigned: bool,
+}
+impl PpValue {
+    fn signed(value: i64) -> PpValue {
+        PpValue {
+            value,
+            is_unsigned: false,
+        }
+    }
+    fn boolean(value: bool) -> PpValue {
+        PpValue::signed(value as i64)
+    }
+    pub fn is_true(&self) -> bool {
+        self.value != 0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PpExpressionError {
+    pub token: Option<PpToken>,
+    pub message: String,
+}
+
+/// Evaluate the controlling expression of `#if` and `#elif`.
+///
+/// `defined X` and `defined(X)` are resolved first, then the remaining tokens are
+/// macro expanded and identifiers left after expansion are replaced with 0.
+pub fn evaluate(
+    tokens: Vec<PpToken>,
+    macros: &MacroTable,
+    line_map: &LineMap,
+) -> Result<PpValue, PpExpressionError> {
+    let tokens = replace_defined(tokens, macros)?;
+    let tokens = match macros.expand(tokens, line_map) {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            return Err(PpExpressionError {
+                token: Some(err.token),
+                message: err.message,
+            })
+        }
+    };
+    if tokens.is_empty() {
+        return Err(PpExpressionError {
+            token: None,
+            message: String::from("#if with no expression"),
+        });
+    }
+    let mut parser = PpExpressionParser { tokens, pos: 0 };
+    let value = parser.conditional(true)?;
+    match parser.peek() {
+        Some(token) => Err(parser.error(&format!(
+            "missing binary operator before token \"{}\"",
+            token.text
+        ))),
+        None => Ok(value),
+    }
+}
+
+fn replace_defined(
+    tokens: Vec<PpToken>,
+    macros: &MacroTable,
+) -> Result<Vec<PpToken>, PpExpressionError> {
+    let mut output: Vec<PpToken> = Vec::new();
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        if !(token.is_ide() && token.text == "defined") {
+            output.push(token);
+            continue;
+        }
+        let operand = match tokens.next() {
+            Some(ref paren_s) if paren_s.is_punct("(") => {
+                let operand = tokens.next();
+                match tokens.next() {
+                    Some(ref paren_e) if paren_e.is_punct(")") => operand,
+                    _ => {
+                        return Err(PpExpressionError {
+                            token: Some(token),
+                            message: String::from("missing ')' after \"defined\""),
+                        })
+                    }
+                }
+            }
+            operand => operand,
+        };
+        match operand {
+            Some(ref operand) if operand.is_ide() => {
+                let mut value = token.clone();
+                value.kind = PpKind::Num;
+                value.text = if macros.is_defined(&operand.text) {
+                    String::from("1")
+                } else {
+                    String::from("0")
+                };
+                output.push(value);
+            }
+            _ => {
+                return Err(PpExpressionError {
+                    token: Some(token),
+                    message: String::from("operator \"defined\" requires an identifier"),
+                })
+            }
+        }
+    }
+    Ok(output)
+}
+
+struct PpExpressionParser {
+    tokens: Vec<PpToken>,
+    pos: usize,
+}
+impl PpExpressionParser {
+    fn peek(&self) -> Option<&PpToken> {
+        self.tokens.get(self.pos)
+    }
+    fn next(&mut self) -> Option<PpToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    fn error(&self, message: &str) -> PpExpressionError {
+        PpExpressionError {
+            token: self
+                .tokens
+                .get(self.pos)
+                .or_else(|| self.tokens.last())
+                .cloned(),
+            message: message.to_string(),
+        }
+    }
+    fn expect(&mut self, punct: &str) -> Result<(), PpExpressionError> {
+        match self.peek() {
+            Some(token) if token.is_punct(punct) => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}' in preprocessor expression", punct))),
+        }
+    }
+
+    /// `cond ? lhs : rhs`. Only the selected operand is evaluated, so `1 ? 2 : 1 / 0` is fine.
+    fn conditional(&mut self, evaluate: bool) -> Result<PpValue, PpExpressionError> {
+        let condition = self.binary(0, evaluate)?;
+        if !self.peek().map_or(false, |token| token.is_punct("?")) {
+            return Ok(condition);
+        }
+        self.pos += 1;
+        let lhs = self.conditional(evaluate && condition.is_true())?;
+        self.expect(":")?;
+        let rhs = self.conditional(evaluate && !condition.is_true())?;
+        let is_unsigned = lhs.is_unsigned || rhs.is_unsigned;
+        let value = if condition.is_true() { lhs } else { rhs };
+        Ok(PpValue {
+            value: value.value,
+            is_unsigned,
+        })
+    }
+
+    /// Precedence climbing over the binary operators.
+    fn binary(
+        &mut self,
+        min_precedence: u32,
+        evaluate: bool,
+    ) -> Result<PpValue, PpExpressionError> {
+        let mut lhs = self.unary(evaluate)?;
+        loop {
+            let op = match self.peek() {
+                Some(token) if token.kind == PpKind::Punct => token.text.clone(),
+                _ => break,
+            };
+            let precedence = match binary_precedence(&op) {
+                Some(precedence) if precedence >= min_precedence => precedence,
+                _ => break,
+            };
+            self.pos += 1;
+            lhs = match op.as_ref() {
+                "&&" => {
+                    let rhs = self.binary(precedence + 1, evaluate && lhs.is_true())?;
+                    PpValue::boolean(lhs.is_true() && rhs.is_true())
+                }
+                "||" => {
+                    let rhs = self.binary(precedence + 1, evaluate && !lhs.is_true())?;
+                    PpValue::boolean(lhs.is_true() || rhs.is_true())
+                }
+                _ => {
+                    let rhs = self.binary(precedence + 1, evaluate)?;
+                    self.apply(&op, lhs, rhs, evaluate)?
+                }
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn apply(
+        &self,
+        op: &str,
+        lhs: PpValue,
+        rhs: PpValue,
+        evaluate: bool,
+    ) -> Result<PpValue, PpExpressionError> {
+        let is_unsigned = lhs.is_unsigned || rhs.is_unsigned;
+        let (l, r) = (lhs.value, rhs.value);
+        let (ul, ur) = (l as u64, r as u64);
+        let arithmetic = |signed: i64, unsigned: u64| PpValue {
+            value: if is_unsigned { unsigned as i64 } else { signed },
+            is_unsigned,
+        };
+        let value = match op {
+            "*" => arithmetic(l.wrapping_mul(r), ul.wrapping_mul(ur)),
+            "/" | "%" => {
+                if r == 0 {
+                    if evaluate {
+                        return Err(self.error("division by zero in #if"));
+                    }
+                    return Ok(PpValue::signed(0));
+                }
+                if op == "/" {
+                    arithmetic(l.wrapping_div(r), ul / ur)
+                } else {
+                    arithmetic(l.wrapping_rem(r), ul % ur)
+                }
+            }
+            "+" => arithmetic(l.wrapping_add(r), ul.wrapping_add(ur)),
+            "-" => arithmetic(l.wrapping_sub(r), ul.wrapping_sub(ur)),
+            // the type of a shift is the type of its left operand
+            "<<" => PpValue {
+                value: l.wrapping_shl(r as u32),
+                is_unsigned: lhs.is_unsigned,
+            },
+            ">>" => PpValue {
+                value: if lhs.is_unsigned {
+                    ul.wrapping_shr(r as u32) as i64
+                } else {
+                    l.wrapping_shr(r as u32)
+                },
+                is_unsigned: lhs.is_unsigned,
+            },
+            "<" => PpValue::boolean(if is_unsigned { ul < ur } else { l < r }),
+            ">" => PpValue::boolean(if is_unsigned { ul > ur } else { l > r }),
+            "<=" => PpValue::boolean(if is_unsigned { ul <= ur } else { l <= r }),
+            ">=" => PpValue::boolean(if is_unsigned { ul >= ur } else { l >= r }),
+            "==" => PpValue::boolean(l == r),
+            "!=" => PpValue::boolean(l != r),
+            "&" => arithmetic(l & r, ul & ur),
+            "^" => arithmetic(l ^ r, ul ^ ur),
+            "|" => arithmetic(l | r, ul | ur),
+            _ => {
+                return Err(self.error(&format!(
+                    "token \"{}\" is not valid in preprocessor expressions",
+                    op
+                )))
+            }
+        };
+        Ok(value)
+    }
+
+    fn unary(&mut self, evaluate: bool) -> Result<PpValue, PpExpressionError> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => {
+                self.pos -= 1;
+                return Err(self.error("expected value in preprocessor expression"));
+            }
+        };
+        match token.kind {
+            PpKind::Punct => match token.text.as_ref() {
+                "(" => {
+                    let value = self.comma(evaluate)?;
+                    self.expect(")")?;
+                    Ok(value)
+                }
+                "+" => self.unary(evaluate),
+                "-" => {
+                    let value = self.unary(evaluate)?;
+                    Ok(PpValue {
+                        value: value.value.wrapping_neg(),
+                        is_unsigned: value.is_unsigned,
+                    })
+                }
+                "~" => {
+                    let value = self.unary(evaluate)?;
+                    Ok(PpValue {
+                        value: !value.value,
+                        is_unsigned: value.is_unsigned,
+                    })
+                }
+                "!" => {
+                    let value = self.unary(evaluate)?;
+                    Ok(PpValue::boolean(!value.is_true()))
+                }
+                _ => {
+                    self.pos -= 1;
+                    Err(self.error(&format!(
+                        "token \"{}\" is not valid in preprocessor expressions",
+                        token.text
+                    )))
+                }
+            },
+            PpKind::Num => match parse_integer(&token.text) {
+                Some(value) => Ok(value),
+                None => {
+                    self.pos -= 1;
+                    Err(self.error(&format!(
+                        "invalid integer constant \"{}\" in preprocessor expression",
+                        token.text
+                    )))
+                }
+            },
+            PpKind::Char => match parse_char_literal(&token.text) {
+                Ok(value) => Ok(PpValue::signed(i64::from(value))),
+                Err(message) => {
+                    self.pos -= 1;
+                    Err(self.error(&message))
+                }
+            },
+            // identifiers that are not macros evaluate to 0
+            PpKind::Ide => Ok(PpValue::signed(0)),
+            _ => {
+                self.pos -= 1;
+                Err(self.error(&format!(
+                    "token \"{}\" is not valid in preprocessor expressions",
+                    token.text
+                )))
+            }
+        }
+    }
+
+    fn comma(&mut self, evaluate: bool) -> Result<PpValue, PpExpressionError> {
+        let mut value = self.conditional(evaluate)?;
+        while self.peek().map_or(false, |token| token.is_punct(",")) {
+            self.pos += 1;
+            value = self.conditional(evaluate)?;
+        }
+        Ok(value)
+    }
+}
+
+fn binary_precedence(op: &str) -> Option<u32> {
+    let precedence = match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | ">" | "<=" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    };
+    Some(precedence)
+}
+
+/// In `#if` every integer constant has the width of `intmax_t`.
+fn parse_integer(text: &str) -> Option<PpValue> {
+    let literal = parse_int_literal(text).ok()?;
+    // a constant too large for intmax_t is unsigned
+    let is_unsigned = text.contains(['u', 'U']) || literal.value > i64::MAX as u64;
+    Some(PpValue {
+        value: literal.value as i64,
+        is_unsigned,
+    })
+}
@@ -0,0 +1,12 @@
+/// Minimal standard headers bundled with the compiler. They only declare what the
+/// emitter can lower, so `putchar` is left out as it is always provided as a builtin.
+pub fn get_builtin_header(name: &str) -> Option<&'static str> {
+    match name {
+        "stdio.h" => Some(include_str!("include/stdio.h")),
+        "stdlib.h" => Some(include_str!("include/stdlib.h")),
+        "string.h" => Some(include_str!("include/string.h")),
+        "stddef.h" => Some(include_str!("include/stddef.h")),
+        "stdbool.h" => Some(include_str!("include/stdbool.h")),
+        _ => None,
+    }
+}
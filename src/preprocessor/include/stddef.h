@@ -0,0 +1,8 @@
+#ifndef _STDDEF_H
+#define _STDDEF_H
+
+#ifndef NULL
+#define NULL 0
+#endif
+
+#endif
@@ -0,0 +1,12 @@
+#ifndef _STDIO_H
+#define _STDIO_H
+
+#define EOF (-1)
+
+extern int printf(const char *__format, ...);
+extern int sprintf(char *__s, const char *__format, ...);
+extern int scanf(const char *__format, ...);
+extern int puts(const char *__s);
+extern int getchar();
+
+#endif
@@ -0,0 +1,16 @@
+#ifndef _STDLIB_H
+#define _STDLIB_H
+
+#ifndef NULL
+#define NULL 0
+#endif
+#define EXIT_SUCCESS 0
+#define EXIT_FAILURE 1
+#define RAND_MAX 2147483647
+
+extern int abs(int __x);
+extern int atoi(const char *__nptr);
+extern int rand();
+extern int system(const char *__command);
+
+#endif
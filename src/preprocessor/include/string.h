@@ -0,0 +1,11 @@
+#ifndef _STRING_H
+#define _STRING_H
+
+#ifndef NULL
+#define NULL 0
+#endif
+
+extern int strcmp(const char *__s1, const char *__s2);
+extern int strlen(const char *__s);
+
+#endif
@@ -0,0 +1,56 @@
+use crate::source_map::line_starts;
+
+/// Presumed file names and line numbers of a source file, which `#line` can change.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LineMap {
+    /// Byte offsets where each physical line starts.
+    line_starts: Vec<usize>,
+    markers: Vec<LineMarker>,
+}
+
+/// From `offset` on, the physical line containing `offset` is presumed to be `line` of `file`.
+#[derive(Debug, PartialEq, Clone)]
+struct LineMarker {
+    offset: usize,
+    file: String,
+    line: usize,
+}
+
+impl LineMap {
+    pub fn new(file: &str, code: &str) -> LineMap {
+        LineMap {
+            line_starts: line_starts(code),
+            markers: vec![LineMarker {
+                offset: 0,
+                file: file.to_string(),
+                line: 1,
+            }],
+        }
+    }
+    /// Physical line number of a byte offset, starting from 1.
+    pub fn physical_line(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        }
+    }
+    /// Presume the line starting at `offset` to be `line`, as `#line line "file"` does.
+    /// The file name stays the same when it is not given.
+    pub fn set_line(&mut self, offset: usize, line: usize, file: Option<String>) {
+        let file = match file {
+            Some(file) => file,
+            None => self.presumed(offset).0.to_string(),
+        };
+        self.markers.push(LineMarker { offset, file, line });
+    }
+    pub fn presumed(&self, offset: usize) -> (&str, usize) {
+        let marker = self
+            .markers
+            .iter()
+            .rev()
+            .find(|marker| marker.offset <= offset)
+            .unwrap_or(&self.markers[0]);
+        let line = marker.line + self.physical_line(offset) - self.physical_line(marker.offset);
+        (&marker.file, line)
+    }
+}
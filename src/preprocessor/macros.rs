@@ -0,0 +1,481 @@
+use crate::preprocessor::line_map::*;
+use crate::preprocessor::token::*;
+
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Macro {
+    pub name: String,
+    /// `None` for object-like macros. A variadic macro has `__VA_ARGS__` as its last parameter.
+    pub parameters: Option<Vec<String>>,
+    pub is_variadic: bool,
+    pub replacement: Vec<PpToken>,
+}
+impl Macro {
+    /// Two definitions are the same when their parameters and replacement lists are
+    /// spelled identically, including the presence of whitespace between tokens.
+    pub fn is_same_definition(&self, other: &Macro) -> bool {
+        self.parameters == other.parameters
+            && self.is_variadic == other.is_variadic
+            && self.replacement.len() == other.replacement.len()
+            && self
+                .replacement
+                .iter()
+                .zip(other.replacement.iter())
+                .enumerate()
+                .all(|(i, (a, b))| {
+                    a.text == b.text && (i == 0 || a.leading_space == b.leading_space)
+                })
+    }
+    fn parameter_index(&self, token: &PpToken) -> Option<usize> {
+        if !token.is_ide() {
+            return None;
+        }
+        match &self.parameters {
+            Some(parameters) => parameters
+                .iter()
+                .position(|parameter| *parameter == token.text),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MacroError {
+    pub token: PpToken,
+    pub message: String,
+}
+
+/// Macros whose replacement depends on where they are used.
+pub const DYNAMIC_MACROS: [&str; 2] = ["__FILE__", "__LINE__"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MacroTable {
+    macros: HashMap<String, Macro>,
+}
+impl MacroTable {
+    pub fn new() -> MacroTable {
+        MacroTable {
+            macros: HashMap::new(),
+        }
+    }
+    pub fn define(&mut self, new_macro: Macro) -> Option<Macro> {
+        self.macros.insert(new_macro.name.clone(), new_macro)
+    }
+    pub fn undef(&mut self, name: &str) -> Option<Macro> {
+        self.macros.remove(name)
+    }
+    pub fn get(&self, name: &str) -> Option<&Macro> {
+        self.macros.get(name)
+    }
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name) || DYNAMIC_MACROS.contains(&name)
+    }
+
+    /// Expand every macro invocation in a sequence of tokens.
+    ///
+    /// The replacement list is pushed back in front of the remaining input so that
+    /// it is rescanned together with the rest of the text. Each replaced token
+    /// carries the macro name in its hide set, which stops a macro from expanding
+    /// itself again (ex. `#define foo foo + 1`).
+    /// `__FILE__` and `__LINE__` are resolved with the line map of the current file.
+    pub fn expand(
+        &self,
+        tokens: Vec<PpToken>,
+        line_map: &LineMap,
+    ) -> Result<Vec<PpToken>, MacroError> {
+        let mut input: VecDeque<PpToken> = tokens.into_iter().collect();
+        let mut output: Vec<PpToken> = Vec::new();
+        while let Some(token) = input.pop_front() {
+            if !token.is_ide() || token.is_hidden(&token.text) {
+                output.push(token);
+                continue;
+            }
+            if let Some(dynamic) = expand_dynamic(&token, line_map) {
+                output.push(dynamic);
+                continue;
+            }
+            let definition = match self.macros.get(&token.text) {
+                Some(definition) => definition,
+                None => {
+                    output.push(token);
+                    continue;
+                }
+            };
+            let replacement = match definition.parameters {
+                None => {
+                    let mut hide_set = token.hide_set.clone();
+                    hide_set.push(token.text.clone());
+                    substitute_origin(&definition.replacement, &token, &hide_set)
+                }
+                Some(_) => {
+                    // a function-like macro name not followed by ( is an ordinary identifier
+                    let is_invocation = input
+                        .iter()
+                        .find(|next| next.kind != PpKind::NewLine)
+                        .map_or(false, |next| next.is_punct("("));
+                    if !is_invocation {
+                        output.push(token);
+                        continue;
+                    }
+                    let (arguments, paren_e) = collect_arguments(&token, &mut input)?;
+                    let arguments = check_arguments(definition, &token, arguments)?;
+                    let hide_set: Vec<String> = token
+                        .hide_set
+                        .iter()
+                        .filter(|name| paren_e.is_hidden(name))
+                        .cloned()
+                        .chain(vec![token.text.clone()])
+                        .collect();
+                    let mut origin = token.clone();
+                    origin.start = token.start.min(paren_e.start);
+                    origin.end = token.end.max(paren_e.end);
+                    let body = self.substitute(definition, &origin, &arguments, line_map)?;
+                    substitute_origin(&body, &token, &hide_set)
+                }
+            };
+            for replaced in replacement.into_iter().rev() {
+                input.push_front(replaced);
+            }
+        }
+        Ok(output
+            .into_iter()
+            .filter(|token| token.kind != PpKind::Placemarker)
+            .collect())
+    }
+
+    /// Replace parameters in the body of a function-like macro with their arguments,
+    /// applying `#` stringification and `##` pasting.
+    fn substitute(
+        &self,
+        definition: &Macro,
+        invocation: &PpToken,
+        arguments: &[Vec<PpToken>],
+        line_map: &LineMap,
+    ) -> Result<Vec<PpToken>, MacroError> {
+        let body = &definition.replacement;
+        let mut output: Vec<PpToken> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            let next = body.get(i + 1);
+
+            if token.is_punct("#") {
+                let argument = next
+                    .and_then(|next| definition.parameter_index(next))
+                    .map(|idx| &arguments[idx]);
+                if let Some(argument) = argument {
+                    output.push(stringify(argument, token.leading_space, invocation));
+                    i += 2;
+                    continue;
+                }
+            }
+
+            if token.is_punct("##") {
+                let rhs: Vec<PpToken> = match next {
+                    Some(next) => match definition.parameter_index(next) {
+                        Some(idx) => arguments[idx].clone(),
+                        None => vec![with_origin(next, invocation)],
+                    },
+                    None => vec![],
+                };
+                let rhs: Vec<PpToken> = rhs
+                    .into_iter()
+                    .map(|mut token| {
+                        token.expanded = true;
+                        token
+                    })
+                    .collect();
+                // GNU extension: `, ## __VA_ARGS__` drops the comma when no variadic arguments are given
+                let is_va_comma = definition.is_variadic
+                    && next.map_or(false, |next| next.text == "__VA_ARGS__")
+                    && output.last().map_or(false, |lhs| lhs.is_punct(","));
+                if is_va_comma {
+                    if rhs.is_empty() {
+                        output.pop();
+                    }
+                    output.extend(rhs);
+                    i += 2;
+                    continue;
+                }
+                let lhs = output.pop().unwrap_or_else(|| placemarker(token));
+                let mut rhs = rhs.into_iter();
+                let first = rhs.next().unwrap_or_else(|| placemarker(token));
+                output.push(paste(&lhs, &first)?);
+                output.extend(rhs);
+                i += 2;
+                continue;
+            }
+
+            if let Some(idx) = definition.parameter_index(token) {
+                let is_pasted = next.map_or(false, |next| next.is_punct("##"));
+                let mut argument = if is_pasted {
+                    arguments[idx].clone()
+                } else {
+                    // argument prescan: arguments are fully expanded before substitution
+                    self.expand(arguments[idx].clone(), line_map)?
+                };
+                if argument.is_empty() {
+                    argument.push(placemarker(token));
+                }
+                for substituted in argument.iter_mut() {
+                    substituted.expanded = true;
+                }
+                argument[0].leading_space = token.leading_space;
+                output.extend(argument);
+                i += 1;
+                continue;
+            }
+
+            output.push(with_origin(token, invocation));
+            i += 1;
+        }
+        Ok(output)
+    }
+}
+
+/// Read the parenthesized arguments of a function-like macro invocation,
+/// returning the arguments and the closing parenthesis.
+fn collect_arguments(
+    invocation: &PpToken,
+    input: &mut VecDeque<PpToken>,
+) -> Result<(Vec<Vec<PpToken>>, PpToken), MacroError> {
+    let mut arguments: Vec<Vec<PpToken>> = vec![Vec::new()];
+    let mut depth = 0;
+    let mut leading_space = false;
+    while let Some(token) = input.pop_front() {
+        if token.kind == PpKind::NewLine {
+            leading_space = true;
+            continue;
+        }
+        let mut token = token;
+        token.leading_space = token.leading_space || leading_space;
+        leading_space = false;
+
+        if token.is_punct("(") {
+            depth += 1;
+            if depth == 1 {
+                continue;
+            }
+        } else if token.is_punct(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Ok((arguments, token));
+            }
+        } else if token.is_punct(",") && depth == 1 {
+            arguments.push(Vec::new());
+            continue;
+        }
+        if let Some(argument) = arguments.last_mut() {
+            argument.push(token);
+        }
+    }
+    Err(MacroError {
+        token: invocation.clone(),
+        message: format!(
+            "unterminated argument list invoking macro \"{}\"",
+            invocation.text
+        ),
+    })
+}
+
+/// Match the arguments with the parameters. Extra arguments of a variadic macro
+/// are joined back together with their commas into `__VA_ARGS__`.
+fn check_arguments(
+    definition: &Macro,
+    invocation: &PpToken,
+    arguments: Vec<Vec<PpToken>>,
+) -> Result<Vec<Vec<PpToken>>, MacroError> {
+    let parameter_len = definition.parameters.as_ref().map_or(0, |p| p.len());
+    let mut arguments = arguments;
+    // `F()` passes one empty argument, which is no argument for a macro without parameters
+    if parameter_len == 0 && arguments.len() == 1 && arguments[0].is_empty() {
+        arguments.clear();
+    }
+
+    if definition.is_variadic {
+        if arguments.len() + 1 < parameter_len {
+            return Err(argument_count_error(
+                definition,
+                invocation,
+                arguments.len(),
+            ));
+        }
+        let mut fixed: Vec<Vec<PpToken>> = arguments
+            .drain(..arguments.len().min(parameter_len - 1))
+            .collect();
+        let mut variadic: Vec<PpToken> = Vec::new();
+        for (i, argument) in arguments.into_iter().enumerate() {
+            if i > 0 {
+                let mut comma = argument
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| placemarker(invocation));
+                comma.kind = PpKind::Punct;
+                comma.text = String::from(",");
+                comma.leading_space = false;
+                variadic.push(comma);
+            }
+            variadic.extend(argument);
+        }
+        fixed.push(variadic);
+        Ok(fixed)
+    } else if arguments.len() != parameter_len {
+        Err(argument_count_error(
+            definition,
+            invocation,
+            arguments.len(),
+        ))
+    } else {
+        Ok(arguments)
+    }
+}
+
+fn argument_count_error(definition: &Macro, invocation: &PpToken, given: usize) -> MacroError {
+    let parameter_len = definition.parameters.as_ref().map_or(0, |p| p.len());
+    let required = if definition.is_variadic {
+        parameter_len - 1
+    } else {
+        parameter_len
+    };
+    MacroError {
+        token: invocation.clone(),
+        message: format!(
+            "macro \"{}\" requires {} arguments, but {} given",
+            definition.name, required, given
+        ),
+    }
+}
+
+/// `#param`: spell the unexpanded argument as a string literal.
+fn stringify(argument: &[PpToken], leading_space: bool, invocation: &PpToken) -> PpToken {
+    let mut text = String::from("\"");
+    for (i, token) in argument
+        .iter()
+        .filter(|token| token.kind != PpKind::Placemarker)
+        .enumerate()
+    {
+        if i > 0 && token.leading_space {
+            text.push(' ');
+        }
+        match token.kind {
+            PpKind::Str | PpKind::Char => {
+                for c in token.text.chars() {
+                    if c == '"' || c == '\\' {
+                        text.push('\\');
+                    }
+                    text.push(c);
+                }
+            }
+            _ => text.push_str(&token.text),
+        }
+    }
+    text.push('"');
+    PpToken {
+        kind: PpKind::Str,
+        text,
+        start: invocation.start,
+        end: invocation.end,
+        leading_space,
+        expanded: true,
+        hide_set: Vec::new(),
+    }
+}
+
+fn expand_dynamic(token: &PpToken, line_map: &LineMap) -> Option<PpToken> {
+    let (file, line) = line_map.presumed(token.start);
+    let (kind, text) = match token.text.as_ref() {
+        "__FILE__" => {
+            let file = file.replace('\\', "\\\\").replace('"', "\\\"");
+            (PpKind::Str, format!("\"{}\"", file))
+        }
+        "__LINE__" => (PpKind::Num, line.to_string()),
+        _ => return None,
+    };
+    let mut token = token.clone();
+    token.kind = kind;
+    token.text = text;
+    token.expanded = true;
+    Some(token)
+}
+
+/// `lhs ## rhs`: the spellings must form exactly one preprocessing token.
+fn paste(lhs: &PpToken, rhs: &PpToken) -> Result<PpToken, MacroError> {
+    if lhs.kind == PpKind::Placemarker {
+        let mut rhs = rhs.clone();
+        rhs.leading_space = lhs.leading_space;
+        return Ok(rhs);
+    }
+    if rhs.kind == PpKind::Placemarker {
+        return Ok(lhs.clone());
+    }
+    let text = format!("{}{}", lhs.text, rhs.text);
+    let mut pasted = tokenize(&text);
+    if pasted.len() != 1 {
+        return Err(MacroError {
+            token: lhs.clone(),
+            message: format!(
+                "pasting \"{}\" and \"{}\" does not give a valid preprocessing token",
+                lhs.text, rhs.text
+            ),
+        });
+    }
+    let mut token = pasted.remove(0);
+    token.start = lhs.start.min(rhs.start);
+    token.end = lhs.end.max(rhs.end);
+    token.leading_space = lhs.leading_space;
+    token.expanded = true;
+    token.hide_set = lhs.hide_set.clone();
+    Ok(token)
+}
+
+fn with_origin(token: &PpToken, invocation: &PpToken) -> PpToken {
+    let mut token = token.clone();
+    token.start = invocation.start;
+    token.end = invocation.end;
+    token.expanded = true;
+    token
+}
+
+fn placemarker(origin: &PpToken) -> PpToken {
+    PpToken {
+        kind: PpKind::Placemarker,
+        text: String::new(),
+        start: origin.start,
+        end: origin.end,
+        leading_space: origin.leading_space,
+        expanded: true,
+        hide_set: Vec::new(),
+    }
+}
+
+/// Give the tokens of an expansion the location of the invocation so that
+/// diagnostics point back at the code the user wrote, and extend their hide sets.
+/// Tokens substituted from arguments keep their own location.
+fn substitute_origin(
+    replacement: &[PpToken],
+    invocation: &PpToken,
+    hide_set: &[String],
+) -> Vec<PpToken> {
+    replacement
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            let mut token = token.clone();
+            if !token.expanded {
+                token.start = invocation.start;
+                token.end = invocation.end;
+                token.expanded = true;
+            }
+            for name in hide_set {
+                if !token.is_hidden(name) {
+                    token.hide_set.push(name.clone());
+                }
+            }
+            if i == 0 {
+                token.leading_space = invocation.leading_space;
+            }
+            token
+        })
+        .collect()
+}
@@ -1 +1,848 @@
+pub mod expression;
+pub mod headers;
+pub mod line_map;
+pub mod macros;
+pub mod predefined;
+pub mod token;
 
+use crate::diagnostic::{Diagnostic, Span};
+use crate::lexer::literal::parse_string_literal;
+use crate::lexer::token::{DebugInfo, PresumedLocation};
+use crate::preprocessor::expression::*;
+use crate::preprocessor::headers::*;
+use crate::preprocessor::line_map::*;
+use crate::preprocessor::macros::*;
+use crate::preprocessor::predefined::*;
+use crate::preprocessor::token::*;
+use crate::source_map::{FileId, SourceMap};
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A range of the preprocessed code and the source range it came from.
+/// Verbatim segments map byte by byte, expanded segments map to the whole
+/// macro invocation.
+#[derive(Debug, PartialEq, Clone)]
+struct Segment {
+    file: FileId,
+    output_start: usize,
+    output_end: usize,
+    source_start: usize,
+    source_end: usize,
+    is_verbatim: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PreprocessedCode {
+    pub code: String,
+    segments: Vec<Segment>,
+    /// Line maps of the files read, for the locations given by `#line`.
+    line_maps: HashMap<FileId, LineMap>,
+}
+impl PreprocessedCode {
+    fn new() -> PreprocessedCode {
+        PreprocessedCode {
+            code: String::new(),
+            segments: Vec::new(),
+            line_maps: HashMap::new(),
+        }
+    }
+    fn push_token(&mut self, file: FileId, token: &PpToken) {
+        let output_start = self.code.len();
+        self.code.push_str(&token.text);
+        self.segments.push(Segment {
+            file,
+            output_start,
+            output_end: self.code.len(),
+            source_start: token.start,
+            source_end: token.end,
+            is_verbatim: !token.expanded,
+        });
+    }
+    fn find_segment(&self, offset: usize) -> Option<&Segment> {
+        let idx = match self
+            .segments
+            .binary_search_by_key(&offset, |segment| segment.output_start)
+        {
+            Ok(idx) => idx,
+            Err(0) => return self.segments.first(),
+            Err(idx) => idx - 1,
+        };
+        self.segments.get(idx)
+    }
+    fn source_start(&self, offset: usize) -> usize {
+        match self.find_segment(offset) {
+            Some(segment) if segment.is_verbatim => {
+                let len = segment.output_end - segment.output_start;
+                segment.source_start + (offset.saturating_sub(segment.output_start)).min(len)
+            }
+            Some(segment) => segment.source_start,
+            None => offset,
+        }
+    }
+    fn source_end(&self, offset: usize) -> usize {
+        match self.find_segment(offset.saturating_sub(1)) {
+            Some(segment) if segment.is_verbatim => {
+                let len = segment.output_end - segment.output_start;
+                segment.source_start + (offset.saturating_sub(segment.output_start)).min(len)
+            }
+            Some(segment) => segment.source_end,
+            None => offset,
+        }
+    }
+    /// Rewrite the location of a token lexed from the preprocessed code so that
+    /// it points into the file the token came from.
+    pub fn map_debug_info(&self, debug_info: &mut DebugInfo) {
+        let file = match self.find_segment(debug_info.start) {
+            Some(segment) => segment.file,
+            None => FileId::default(),
+        };
+        let end_file = self
+            .find_segment(debug_info.end.saturating_sub(1))
+            .map_or(file, |segment| segment.file);
+        let start = self.source_start(debug_info.start);
+        let end = self.source_end(debug_info.end);
+        debug_info.file = file;
+        debug_info.start = start;
+        debug_info.end = if end_file == file {
+            end.max(start)
+        } else {
+            start
+        };
+        if let Some(line_map) = self.line_maps.get(&file) {
+            let (file, line) = line_map.presumed(start);
+            debug_info.presumed = Some(PresumedLocation {
+                file: file.to_string(),
+                line,
+            });
+        }
+    }
+}
+
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+type PpResult<T> = Result<T, Diagnostic>;
+
+/// A file being preprocessed.
+struct SourceFile {
+    id: FileId,
+    path: PathBuf,
+    code: String,
+    line_map: LineMap,
+}
+impl SourceFile {
+    fn new(source_map: &mut SourceMap, path: PathBuf, code: String) -> SourceFile {
+        let name = path.display().to_string();
+        let id = source_map.add_file(&name, &code);
+        let line_map = LineMap::new(&name, &code);
+        SourceFile {
+            id,
+            path,
+            code,
+            line_map,
+        }
+    }
+}
+
+/// An `#if` section being read.
+struct Conditional {
+    /// The opening `if`, `ifdef` or `ifndef`.
+    directive: PpToken,
+    is_parent_active: bool,
+    /// Whether one of the groups of this section has been selected.
+    is_taken: bool,
+    /// Whether the lines of the current group are kept.
+    is_active: bool,
+    has_else: bool,
+}
+
+pub struct Preprocessor {
+    macros: MacroTable,
+    include_dirs: Vec<PathBuf>,
+    include_stack: Vec<PathBuf>,
+    /// Files marked with `#pragma once`.
+    once_files: HashSet<PathBuf>,
+    source_map: SourceMap,
+    warnings: Vec<Diagnostic>,
+}
+impl Preprocessor {
+    pub fn new() -> Preprocessor {
+        let mut macros = MacroTable::new();
+        for (name, value) in predefined_macros() {
+            macros.define(Macro {
+                name: name.to_string(),
+                parameters: None,
+                is_variadic: false,
+                replacement: tokenize(&value),
+            });
+        }
+        Preprocessor {
+            macros,
+            include_dirs: Vec::new(),
+            include_stack: Vec::new(),
+            once_files: HashSet::new(),
+            source_map: SourceMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+    /// Add a directory searched by both `#include "..."` and `#include <...>`,
+    /// in the order the directories are added.
+    pub fn add_include_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.include_dirs.push(dir.as_ref().to_path_buf());
+    }
+    /// Every file read so far, the main files and the headers they include.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+    pub fn preprocess<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        code: String,
+    ) -> Result<PreprocessedCode, Diagnostic> {
+        let mut output = PreprocessedCode::new();
+        let mut file = SourceFile::new(&mut self.source_map, path.as_ref().to_path_buf(), code);
+        self.preprocess_file(&mut file, &mut output)?;
+        Ok(output)
+    }
+    /// Warnings such as `#warning` and macro redefinitions, in the order they were found.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+    fn preprocess_file(
+        &mut self,
+        file: &mut SourceFile,
+        output: &mut PreprocessedCode,
+    ) -> PpResult<()> {
+        self.include_stack.push(canonical_path(&file.path));
+        // text lines are expanded together so that macro arguments can span lines
+        let mut text: Vec<PpToken> = Vec::new();
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let tokens = tokenize(&file.code);
+        if let Some(token) = tokens
+            .last()
+            .filter(|token| token.kind == PpKind::Other && token.text == "/*")
+        {
+            return Err(error(file, token, "unterminated comment"));
+        }
+        for line in split_lines(tokens) {
+            let is_active = conditionals.last().map_or(true, |c| c.is_active);
+            if line.first().map_or(false, |token| token.is_punct("#")) {
+                self.flush_text(file, output, &mut text)?;
+                let is_conditional = self.conditional(file, &line, &mut conditionals)?;
+                if is_active && !is_conditional {
+                    self.directive(file, line, output)?;
+                }
+                output.code.push('\n');
+            } else if is_active {
+                text.extend(line);
+            } else if line
+                .last()
+                .map_or(false, |token| token.kind == PpKind::NewLine)
+            {
+                // skipped lines are kept as blank lines
+                output.code.push('\n');
+            }
+        }
+        self.flush_text(file, output, &mut text)?;
+        if let Some(conditional) = conditionals.last() {
+            return Err(error(
+                file,
+                &conditional.directive,
+                format!(
+                    "unterminated #{}, missing #endif",
+                    conditional.directive.text
+                ),
+            ));
+        }
+        output.line_maps.insert(file.id, file.line_map.clone());
+        self.include_stack.pop();
+        Ok(())
+    }
+    fn flush_text(
+        &self,
+        file: &SourceFile,
+        output: &mut PreprocessedCode,
+        text: &mut Vec<PpToken>,
+    ) -> PpResult<()> {
+        let tokens: Vec<PpToken> = text.drain(..).collect();
+        let expanded = match self.macros.expand(tokens, &file.line_map) {
+            Ok(expanded) => expanded,
+            Err(err) => return Err(error(file, &err.token, err.message)),
+        };
+        push_text(output, file.id, &expanded);
+        Ok(())
+    }
+    /// Handle `#if`, `#ifdef`, `#ifndef`, `#elif`, `#else` and `#endif`. These are
+    /// processed even inside skipped groups to keep track of the nesting.
+    /// Returns false for any other directive.
+    fn conditional(
+        &self,
+        file: &SourceFile,
+        line: &[PpToken],
+        conditionals: &mut Vec<Conditional>,
+    ) -> PpResult<bool> {
+        let line: Vec<&PpToken> = line
+            .iter()
+            .filter(|token| token.kind != PpKind::NewLine)
+            .collect();
+        let name = match line.get(1) {
+            Some(name) if name.is_ide() => *name,
+            _ => return Ok(false),
+        };
+        let rest: Vec<PpToken> = line[2..].iter().map(|token| (*token).clone()).collect();
+        let is_active = conditionals.last().map_or(true, |c| c.is_active);
+        match name.text.as_ref() {
+            "if" | "ifdef" | "ifndef" => {
+                // the condition of a nested group in a skipped group is never evaluated
+                let condition = is_active && self.condition(file, name, rest)?;
+                conditionals.push(Conditional {
+                    directive: name.clone(),
+                    is_parent_active: is_active,
+                    is_taken: condition,
+                    is_active: condition,
+                    has_else: false,
+                });
+            }
+            "elif" => {
+                let conditional = match conditionals.last_mut() {
+                    Some(conditional) => conditional,
+                    None => return Err(error(file, name, "#elif without #if")),
+                };
+                if conditional.has_else {
+                    return Err(error(file, name, "#elif after #else"));
+                }
+                let condition = conditional.is_parent_active
+                    && !conditional.is_taken
+                    && self.condition(file, name, rest)?;
+                conditional.is_active = condition;
+                conditional.is_taken |= condition;
+            }
+            "else" => {
+                let conditional = match conditionals.last_mut() {
+                    Some(conditional) => conditional,
+                    None => return Err(error(file, name, "#else without #if")),
+                };
+                if conditional.has_else {
+                    return Err(error(file, name, "#else after #else"));
+                }
+                conditional.is_active = conditional.is_parent_active && !conditional.is_taken;
+                conditional.is_taken = true;
+                conditional.has_else = true;
+            }
+            "endif" => {
+                if conditionals.pop().is_none() {
+                    return Err(error(file, name, "#endif without #if"));
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+    fn condition(
+        &self,
+        file: &SourceFile,
+        directive: &PpToken,
+        rest: Vec<PpToken>,
+    ) -> PpResult<bool> {
+        match directive.text.as_ref() {
+            "ifdef" | "ifndef" => {
+                let name = match rest.first() {
+                    Some(token) if token.is_ide() => token,
+                    Some(token) => {
+                        return Err(error(file, token, "macro names must be identifiers"))
+                    }
+                    None => {
+                        return Err(error(
+                            file,
+                            directive,
+                            format!("no macro name given in #{} directive", directive.text),
+                        ))
+                    }
+                };
+                let is_defined = self.macros.is_defined(&name.text);
+                Ok(is_defined == (directive.text == "ifdef"))
+            }
+            _ => match evaluate(rest, &self.macros, &file.line_map) {
+                Ok(value) => Ok(value.is_true()),
+                Err(err) => {
+                    let token = err.token.as_ref().unwrap_or(directive);
+                    Err(error(file, token, err.message))
+                }
+            },
+        }
+    }
+    fn directive(
+        &mut self,
+        file: &mut SourceFile,
+        line: Vec<PpToken>,
+        output: &mut PreprocessedCode,
+    ) -> PpResult<()> {
+        let mut line = line
+            .into_iter()
+            .filter(|token| token.kind != PpKind::NewLine);
+        let hash = line.next().expect("a directive starts with #");
+        let name = match line.next() {
+            Some(name) => name,
+            None => return Ok(()), // null directive
+        };
+        let rest: Vec<PpToken> = line.collect();
+        match name.text.as_ref() {
+            "define" => self.define(file, &name, rest)?,
+            "include" => self.include(file, &hash, rest, output)?,
+            "undef" => {
+                match rest.first() {
+                    Some(token) if token.is_ide() => {
+                        check_macro_name(file, token)?;
+                        self.macros.undef(&token.text);
+                    }
+                    _ => return Err(error(file, &name, "macro names must be identifiers")),
+                };
+            }
+            "line" => self.line(file, &name, rest)?,
+            "error" => return Err(error(file, &hash, format!("#error {}", spell(&rest)))),
+            "warning" => {
+                let message = format!("#warning {}", spell(&rest));
+                self.warnings
+                    .push(Diagnostic::warning(message).at(span(file, &hash)));
+            }
+            "pragma" => self.pragma(file, hash, name, rest, output),
+            _ => {
+                return Err(error(
+                    file,
+                    &hash,
+                    format!("invalid preprocessing directive #{}", name.text),
+                ))
+            }
+        }
+        Ok(())
+    }
+    fn define(
+        &mut self,
+        file: &SourceFile,
+        directive: &PpToken,
+        rest: Vec<PpToken>,
+    ) -> PpResult<()> {
+        let mut rest = rest.into_iter();
+        let name = match rest.next() {
+            Some(token) if token.is_ide() => token,
+            Some(token) => return Err(error(file, &token, "macro names must be identifiers")),
+            None => return Err(error(file, directive, "no macro name given")),
+        };
+        check_macro_name(file, &name)?;
+        let mut rest = rest.peekable();
+        let (parameters, is_variadic) = match rest.peek() {
+            Some(token) if token.is_punct("(") && !token.leading_space => {
+                let paren_s = rest.next().expect("the peeked (");
+                let (parameters, is_variadic) = parse_parameters(file, &paren_s, &mut rest)?;
+                (Some(parameters), is_variadic)
+            }
+            _ => (None, false),
+        };
+        let replacement: Vec<PpToken> = rest.collect();
+        check_replacement(file, &name, &parameters, is_variadic, &replacement)?;
+
+        let new_macro = Macro {
+            name: name.text.clone(),
+            parameters,
+            is_variadic,
+            replacement,
+        };
+        if let Some(old_macro) = self.macros.get(&name.text) {
+            if !old_macro.is_same_definition(&new_macro) {
+                let message = format!("\"{}\" redefined", name.text);
+                self.warnings
+                    .push(Diagnostic::warning(message).at(span(file, &name)));
+            }
+        }
+        self.macros.define(new_macro);
+        Ok(())
+    }
+    /// `#line digits "file"`, where the file name is optional. The line after the
+    /// directive gets the given line number.
+    fn line(&self, file: &mut SourceFile, directive: &PpToken, rest: Vec<PpToken>) -> PpResult<()> {
+        let end = rest.last().map_or(directive.end, |token| token.end);
+        let rest = match rest.first() {
+            Some(token) if token.kind == PpKind::Num => rest,
+            _ => match self.macros.expand(rest, &file.line_map) {
+                Ok(expanded) => expanded,
+                Err(err) => return Err(error(file, &err.token, err.message)),
+            },
+        };
+        let line = match rest.first() {
+            Some(token) if token.text.bytes().all(|c| c.is_ascii_digit()) => {
+                match token.text.parse::<usize>() {
+                    Ok(line) if line > 0 => line,
+                    _ => {
+                        return Err(error(
+                            file,
+                            token,
+                            "line number out of range in #line directive",
+                        ))
+                    }
+                }
+            }
+            Some(token) => {
+                return Err(error(
+                    file,
+                    token,
+                    format!("\"{}\" after #line is not a positive integer", token.text),
+                ))
+            }
+            None => {
+                return Err(error(
+                    file,
+                    directive,
+                    "#line directive requires a line number",
+                ))
+            }
+        };
+        let name = match rest.get(1) {
+            Some(token) if token.kind == PpKind::Str => {
+                let name = parse_string_literal(&token.text)
+                    .map_err(|message| error(file, token, message))?;
+                match String::from_utf8(name) {
+                    Ok(name) => Some(name),
+                    Err(_) => {
+                        return Err(error(
+                            file,
+                            token,
+                            format!("filename {} in #line directive is not UTF-8", token.text),
+                        ))
+                    }
+                }
+            }
+            Some(token) => {
+                return Err(error(
+                    file,
+                    token,
+                    format!("invalid filename \"{}\" in #line directive", token.text),
+                ))
+            }
+            None => None,
+        };
+        if let Some(token) = rest.get(2) {
+            return Err(error(file, token, "extra tokens at end of #line directive"));
+        }
+        let next_line = file.code[end..]
+            .find('\n')
+            .map_or(file.code.len(), |idx| end + idx + 1);
+        file.line_map.set_line(next_line, line, name);
+        Ok(())
+    }
+    /// `#pragma once` is handled here. Other pragmas are written to the output as
+    /// `#pragma ...` on a line of their own for the lexer to read as one token.
+    fn pragma(
+        &mut self,
+        file: &SourceFile,
+        hash: PpToken,
+        name: PpToken,
+        rest: Vec<PpToken>,
+        output: &mut PreprocessedCode,
+    ) {
+        if rest.len() == 1 && rest[0].is_ide() && rest[0].text == "once" {
+            self.once_files.insert(canonical_path(&file.path));
+            return;
+        }
+        let mut tokens: Vec<PpToken> = vec![hash, name].into_iter().chain(rest).collect();
+        tokens[0].leading_space = false;
+        tokens[1].leading_space = false;
+        push_text(output, file.id, &tokens);
+    }
+    fn include(
+        &mut self,
+        file: &SourceFile,
+        hash: &PpToken,
+        rest: Vec<PpToken>,
+        output: &mut PreprocessedCode,
+    ) -> PpResult<()> {
+        let (name, is_angled) = self.parse_header_name(file, hash, rest)?;
+        let (path, code) = match self.find_header(file, &name, is_angled) {
+            Some(header) => header,
+            None => {
+                return Err(error(
+                    file,
+                    hash,
+                    format!("{}: No such file or directory", name),
+                ))
+            }
+        };
+
+        // a file may reenter itself once, which is enough for include guards to take effect
+        let canonical = canonical_path(&path);
+        if self.once_files.contains(&canonical) {
+            return Ok(());
+        }
+        let depth = self
+            .include_stack
+            .iter()
+            .filter(|p| **p == canonical)
+            .count();
+        if depth >= 2 || self.include_stack.len() >= MAX_INCLUDE_DEPTH {
+            let chain: Vec<String> = self
+                .include_stack
+                .iter()
+                .chain(vec![&canonical])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(error(
+                file,
+                hash,
+                format!("#include cycle detected: {}", chain.join(" -> ")),
+            ));
+        }
+
+        let mut header = SourceFile::new(&mut self.source_map, path, code);
+        self.preprocess_file(&mut header, output)
+    }
+    /// Read `"name"` or `<name>`. Any other form is macro expanded first.
+    fn parse_header_name(
+        &self,
+        file: &SourceFile,
+        hash: &PpToken,
+        rest: Vec<PpToken>,
+    ) -> PpResult<(String, bool)> {
+        let rest = match rest.first() {
+            Some(token) if token.kind == PpKind::Str || token.is_punct("<") => rest,
+            _ => match self.macros.expand(rest, &file.line_map) {
+                Ok(expanded) => expanded,
+                Err(err) => return Err(error(file, &err.token, err.message)),
+            },
+        };
+        let mut rest = rest.into_iter();
+        match rest.next() {
+            Some(ref token) if token.kind == PpKind::Str => {
+                Ok((token.text.trim_matches('"').to_string(), false))
+            }
+            Some(ref token) if token.is_punct("<") => {
+                let mut name = String::new();
+                for token in rest {
+                    if token.is_punct(">") {
+                        return Ok((name, true));
+                    }
+                    if token.leading_space && !name.is_empty() {
+                        name.push(' ');
+                    }
+                    name.push_str(&token.text);
+                }
+                Err(error(file, hash, "missing terminating > character"))
+            }
+            _ => Err(error(
+                file,
+                hash,
+                "#include expects \"FILENAME\" or <FILENAME>",
+            )),
+        }
+    }
+    /// `"name"` is searched next to the including file first, then in the include
+    /// directories, then in the bundled headers. `<name>` skips the first step.
+    fn find_header(
+        &self,
+        file: &SourceFile,
+        name: &str,
+        is_angled: bool,
+    ) -> Option<(PathBuf, String)> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        if !is_angled {
+            if let Some(dir) = file.path.parent() {
+                dirs.push(dir.to_path_buf());
+            }
+        }
+        dirs.extend(self.include_dirs.iter().cloned());
+        for dir in dirs {
+            let path = dir.join(name);
+            if path.is_file() {
+                if let Ok(code) = fs::read_to_string(&path) {
+                    return Some((path, code));
+                }
+            }
+        }
+        get_builtin_header(name).map(|code| (Path::new("<built-in>").join(name), code.to_string()))
+    }
+}
+
+/// Split tokens into lines. Every line but the last one ends with its newline token.
+fn split_lines(tokens: Vec<PpToken>) -> Vec<Vec<PpToken>> {
+    let mut lines: Vec<Vec<PpToken>> = vec![Vec::new()];
+    for token in tokens {
+        let is_new_line = token.kind == PpKind::NewLine;
+        lines.last_mut().unwrap().push(token);
+        if is_new_line {
+            lines.push(Vec::new());
+        }
+    }
+    lines
+}
+
+/// Read the parameter list of a function-like macro after its `(`.
+fn parse_parameters<I>(
+    file: &SourceFile,
+    paren_s: &PpToken,
+    rest: &mut I,
+) -> PpResult<(Vec<String>, bool)>
+where
+    I: Iterator<Item = PpToken>,
+{
+    let mut parameters: Vec<String> = Vec::new();
+    loop {
+        let token = match rest.next() {
+            Some(token) => token,
+            None => return Err(error(file, paren_s, "missing ')' in macro parameter list")),
+        };
+        if token.is_punct(")") && parameters.is_empty() {
+            return Ok((parameters, false));
+        }
+        if token.is_punct("...") {
+            parameters.push(String::from("__VA_ARGS__"));
+            match rest.next() {
+                Some(ref token) if token.is_punct(")") => return Ok((parameters, true)),
+                _ => return Err(error(file, &token, "missing ')' in macro parameter list")),
+            }
+        }
+        if !token.is_ide() || token.text == "__VA_ARGS__" {
+            return Err(error(
+                file,
+                &token,
+                format!("expected parameter name, found \"{}\"", token.text),
+            ));
+        }
+        if parameters.contains(&token.text) {
+            return Err(error(
+                file,
+                &token,
+                format!("duplicate macro parameter \"{}\"", token.text),
+            ));
+        }
+        parameters.push(token.text);
+        match rest.next() {
+            Some(ref token) if token.is_punct(",") => (),
+            Some(ref token) if token.is_punct(")") => return Ok((parameters, false)),
+            Some(token) => {
+                return Err(error(
+                    file,
+                    &token,
+                    format!("expected ',' or ')', found \"{}\"", token.text),
+                ))
+            }
+            None => return Err(error(file, paren_s, "missing ')' in macro parameter list")),
+        }
+    }
+}
+
+fn check_replacement(
+    file: &SourceFile,
+    name: &PpToken,
+    parameters: &Option<Vec<String>>,
+    is_variadic: bool,
+    replacement: &[PpToken],
+) -> PpResult<()> {
+    let is_parameter = |token: &PpToken| {
+        parameters
+            .as_ref()
+            .map_or(false, |p| p.contains(&token.text))
+    };
+    for (i, token) in replacement.iter().enumerate() {
+        if token.is_ide() && token.text == "__VA_ARGS__" && !is_variadic {
+            return Err(error(
+                file,
+                token,
+                "__VA_ARGS__ can only appear in the expansion of a variadic macro",
+            ));
+        }
+        if token.is_punct("##") && (i == 0 || i + 1 == replacement.len()) {
+            return Err(error(
+                file,
+                token,
+                "'##' cannot appear at either end of a macro expansion",
+            ));
+        }
+        if token.is_punct("#") && parameters.is_some() {
+            let next = replacement.get(i + 1);
+            if !next.map_or(false, |next| is_parameter(next)) {
+                return Err(error(
+                    file,
+                    token,
+                    format!(
+                        "'#' is not followed by a macro parameter in \"{}\"",
+                        name.text
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_text(output: &mut PreprocessedCode, file: FileId, tokens: &[PpToken]) {
+    let mut previous: Option<&PpToken> = None;
+    for token in tokens {
+        if token.kind == PpKind::NewLine {
+            output.code.push('\n');
+            previous = None;
+            continue;
+        }
+        let separate = match previous {
+            Some(previous) => {
+                token.leading_space
+                    || ((previous.expanded || token.expanded) && would_merge(previous, token))
+            }
+            None => token.leading_space,
+        };
+        if separate {
+            output.code.push(' ');
+        }
+        output.push_token(file, token);
+        previous = Some(token);
+    }
+}
+
+/// Whether two adjacent tokens would be read back as something else when
+/// printed without a space between them (ex. `-` followed by `-1`).
+fn would_merge(lhs: &PpToken, rhs: &PpToken) -> bool {
+    tokenize(&format!("{}{}", lhs.text, rhs.text)).len() != 2
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Spell tokens back as text, as `#error` and `#warning` print their message.
+fn spell(tokens: &[PpToken]) -> String {
+    let mut text = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.leading_space {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+    text
+}
+
+fn check_macro_name(file: &SourceFile, name: &PpToken) -> PpResult<()> {
+    if name.text == "defined" || DYNAMIC_MACROS.contains(&name.text.as_ref()) {
+        return Err(error(
+            file,
+            name,
+            format!("\"{}\" cannot be used as a macro name", name.text),
+        ));
+    }
+    Ok(())
+}
+
+/// The span of a token, at the presumed line which `#line` can change.
+fn span(file: &SourceFile, token: &PpToken) -> Span {
+    let (path, line) = file.line_map.presumed(token.start);
+    Span {
+        file: file.id,
+        start: token.start,
+        end: token.end,
+        presumed: Some(PresumedLocation {
+            file: path.to_string(),
+            line,
+        }),
+    }
+}
+
+fn error<S: Into<String>>(file: &SourceFile, token: &PpToken, message: S) -> Diagnostic {
+    Diagnostic::error(message).at(span(file, token))
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Names and replacement lists of the macros defined before any source is read.
+/// `__FILE__` and `__LINE__` change with every use and are expanded by the `MacroTable` itself.
+pub fn predefined_macros() -> Vec<(&'static str, String)> {
+    let (date, time) = compilation_date_time();
+    vec![
+        ("__STDC__", String::from("1")),
+        ("__STDC_HOSTED__", String::from("1")),
+        ("__STDC_VERSION__", String::from("201112L")),
+        ("__DATE__", date),
+        ("__TIME__", time),
+        // major * 10000 + minor * 100 + patch
+        ("__REXPARSER__", rexparser_version().to_string()),
+    ]
+}
+
+fn rexparser_version() -> u32 {
+    let major: u32 = env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0);
+    let minor: u32 = env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0);
+    let patch: u32 = env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or(0);
+    major * 10000 + minor * 100 + patch
+}
+
+/// `"Mmm dd yyyy"` and `"hh:mm:ss"` in UTC. `SOURCE_DATE_EPOCH` overrides the
+/// current time to make builds reproducible.
+fn compilation_date_time() -> (String, String) {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs())
+        });
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    let date = format!("\"{} {:>2} {}\"", MONTHS[month - 1], day, year);
+    let time = format!(
+        "\"{:02}:{:02}:{:02}\"",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+    (date, time)
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) of the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, usize, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as usize, day)
+}
@@ -0,0 +1,184 @@
+#[derive(Debug, PartialEq, Clone)]
+pub enum PpKind {
+    Ide,
+    Num,
+    Str,
+    Char,
+    Punct,
+    Other,
+    NewLine,
+    /// An empty argument in `##` pasting. Removed once expansion is finished.
+    Placemarker,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PpToken {
+    pub kind: PpKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub leading_space: bool,
+    pub expanded: bool,
+    pub hide_set: Vec<String>,
+}
+impl PpToken {
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == PpKind::Punct && self.text == punct
+    }
+    pub fn is_ide(&self) -> bool {
+        self.kind == PpKind::Ide
+    }
+    pub fn is_hidden(&self, name: &str) -> bool {
+        self.hide_set.iter().any(|hidden| hidden == name)
+    }
+}
+
+const PUNCTUATORS: [&str; 48] = [
+    "%:%:", "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "*=", "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "<:", ":>", "<%", "%>", "%:", "[", "]",
+    "(", ")", "{", "}", ".", "&", "*", "+", "-", "~", "!", "/", "%", "<", ">", "^", "|",
+];
+const SINGLE_PUNCTUATORS: &str = "?:;=,#";
+
+/// Split source code into preprocessing tokens.
+/// A backslash-newline is treated as whitespace so directives can span lines.
+/// Comments are whitespace too. The `/*` of an unterminated comment is left as an
+/// `Other` token for the caller to report.
+pub fn tokenize(code: &str) -> Vec<PpToken> {
+    let bytes = code.as_bytes();
+    let mut tokens: Vec<PpToken> = Vec::new();
+    let mut pos = 0;
+    let mut leading_space = false;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        if c == b'\n' {
+            tokens.push(new_token(
+                PpKind::NewLine,
+                code,
+                pos,
+                pos + 1,
+                leading_space,
+            ));
+            leading_space = false;
+            pos += 1;
+            continue;
+        }
+        if c == b'\\' && bytes.get(pos + 1) == Some(&b'\n') {
+            leading_space = true;
+            pos += 2;
+            continue;
+        }
+        if c == b'\\' && bytes.get(pos + 1) == Some(&b'\r') && bytes.get(pos + 2) == Some(&b'\n') {
+            leading_space = true;
+            pos += 3;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            leading_space = true;
+            pos += 1;
+            continue;
+        }
+        if c == b'/' && bytes.get(pos + 1) == Some(&b'/') {
+            pos = skip_line_comment(bytes, pos);
+            leading_space = true;
+            continue;
+        }
+        if c == b'/' && bytes.get(pos + 1) == Some(&b'*') {
+            if let Some(end) = code[pos + 2..].find("*/") {
+                pos += end + 4;
+                leading_space = true;
+                continue;
+            }
+            tokens.push(new_token(PpKind::Other, code, pos, pos + 2, leading_space));
+            return tokens;
+        }
+
+        let start = pos;
+        let kind = if c.is_ascii_alphabetic() || c == b'_' {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            PpKind::Ide
+        } else if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(pos + 1).map_or(false, |c| c.is_ascii_digit()))
+        {
+            pos = scan_number(bytes, pos);
+            PpKind::Num
+        } else if c == b'"' || c == b'\'' {
+            pos = scan_quoted(bytes, pos, c);
+            if c == b'"' {
+                PpKind::Str
+            } else {
+                PpKind::Char
+            }
+        } else if let Some(punct) = PUNCTUATORS
+            .iter()
+            .find(|punct| code[pos..].starts_with(*punct))
+        {
+            pos += punct.len();
+            PpKind::Punct
+        } else if SINGLE_PUNCTUATORS.as_bytes().contains(&c) {
+            pos += 1;
+            PpKind::Punct
+        } else {
+            pos += code[pos..].chars().next().map_or(1, |c| c.len_utf8());
+            PpKind::Other
+        };
+        tokens.push(new_token(kind, code, start, pos, leading_space));
+        leading_space = false;
+    }
+    tokens
+}
+
+fn new_token(kind: PpKind, code: &str, start: usize, end: usize, leading_space: bool) -> PpToken {
+    PpToken {
+        kind,
+        text: code[start..end].to_string(),
+        start,
+        end,
+        leading_space,
+        expanded: false,
+        hide_set: Vec::new(),
+    }
+}
+
+/// Skip a `//` comment up to the end of the line. A backslash-newline continues the comment.
+fn skip_line_comment(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start + 2;
+    while pos < bytes.len() && bytes[pos] != b'\n' {
+        if bytes[pos] == b'\\' && bytes.get(pos + 1) == Some(&b'\n') {
+            pos += 1;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+/// pp-number: digit or .digit, followed by identifier characters, dots and signed exponents.
+fn scan_number(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let is_exponent_sign = (c == b'+' || c == b'-')
+            && (bytes[pos - 1] | 0x20 == b'e' || bytes[pos - 1] | 0x20 == b'p');
+        if !(is_exponent_sign || c.is_ascii_alphanumeric() || c == b'_' || c == b'.') {
+            break;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+/// String or character literal. An unterminated literal stops at the end of the line.
+fn scan_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' if pos + 1 < bytes.len() => pos += 2,
+            b'\n' => return pos,
+            c if c == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    pos
+}
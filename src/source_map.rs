@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// Index of a file in a `SourceMap`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, Serialize, Deserialize)]
+pub struct FileId(pub usize);
+
+/// A file loaded into a `SourceMap`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub code: String,
+    /// Byte offsets where each line starts.
+    line_starts: Vec<usize>,
+}
+impl SourceFile {
+    fn new(name: &str, code: &str) -> SourceFile {
+        SourceFile {
+            name: name.to_string(),
+            code: code.to_string(),
+            line_starts: line_starts(code),
+        }
+    }
+    /// Line and column of a byte offset, both starting from 1. The column counts characters.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.code.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        };
+        let line_start = self.line_starts[line - 1];
+        let column = self
+            .code
+            .get(line_start..offset)
+            .map_or(offset - line_start, |text| text.chars().count())
+            + 1;
+        (line, column)
+    }
+    /// Text of a line without its newline.
+    pub fn line_text(&self, line: usize) -> &str {
+        let start = match self.line_starts.get(line.wrapping_sub(1)) {
+            Some(start) => *start,
+            None => return "",
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.code.len(), |end| end - 1);
+        &self.code[start..end]
+    }
+}
+
+/// Byte offsets where each line of `code` starts.
+pub fn line_starts(code: &str) -> Vec<usize> {
+    vec![0]
+        .into_iter()
+        .chain(code.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect()
+}
+
+/// Every file read while compiling, so that a `DebugInfo` only needs a file id and
+/// a byte span. Lines and columns are computed when a location is printed.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap { files: Vec::new() }
+    }
+    pub fn add_file(&mut self, name: &str, code: &str) -> FileId {
+        self.files.push(SourceFile::new(name, code));
+        FileId(self.files.len() - 1)
+    }
+    pub fn file(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0)
+    }
+}
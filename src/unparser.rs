@@ -0,0 +1,742 @@
+//! Printing of the AST back to C, and the formatter built on it.
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::token::{Associativity, Token};
+use crate::parser::ctype::{CType, Qualifiers};
+use crate::parser::declare::initializer::{Designator, Initializer};
+use crate::parser::declare::*;
+use crate::parser::expression::*;
+use crate::parser::statement::*;
+use crate::parser::*;
+
+use std::collections::VecDeque;
+
+const INDENT: &str = "    ";
+/// The precedence of `?:`, between those of `=` and `||` in `get_property`.
+const TERNARY_PRECEDENCE: u32 = 3;
+/// The precedence of the operands of a comma, such as the arguments of a call.
+const ASSIGNMENT_PRECEDENCE: u32 = 2;
+
+/// Print a program as C, one declaration or statement per line. Parentheses are
+/// printed only where the precedence of the operators needs them. Declarations
+/// and statements with syntax errors are left out.
+pub fn unparse(node: &ProgramNode) -> String {
+    let mut unparser = Unparser::new(None, VecDeque::new());
+    unparser.program(node);
+    unparser.out
+}
+
+/// Format C code. The code is not preprocessed, so it can have no directives
+/// other than `#pragma`. Comments are kept before the declaration or statement
+/// they are in, or at the end of its line.
+pub fn format(code: &str) -> Result<String, Vec<Diagnostic>> {
+    let mut lexer = Lexer::new();
+    lexer.keep_comments = true;
+    let mut tokens = lexer.lex(code.to_string()).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|error| match &error.span {
+                Some(span) if &code[span.start..span.end] == "#" => {
+                    Diagnostic::error("only #pragma directives can be formatted").at(span.clone())
+                }
+                _ => error,
+            })
+            .collect::<Vec<Diagnostic>>()
+    })?;
+    let comments = tokens
+        .tokens
+        .iter()
+        .flat_map(|token| &token.debug_info().comments)
+        .chain(&tokens.trailing_comments)
+        .map(|comment| Comment {
+            start: comment.start,
+            end: comment.end,
+            text: comment.s.trim_end().to_string(),
+        })
+        .collect();
+    let node = parser(&mut tokens)?;
+    let mut unparser = Unparser::new(Some(code), comments);
+    unparser.program(&node);
+    Ok(unparser.out)
+}
+
+struct Comment {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+struct Unparser<'a> {
+    out: String,
+    indent: usize,
+    /// The source of the program, to keep its blank lines and trailing comments.
+    code: Option<&'a str>,
+    comments: VecDeque<Comment>,
+    /// Where the last declaration, statement or comment printed ends in the source.
+    /// None at the start of a block.
+    last_end: Option<usize>,
+}
+impl<'a> Unparser<'a> {
+    fn new(code: Option<&'a str>, comments: VecDeque<Comment>) -> Unparser<'a> {
+        Unparser {
+            out: String::new(),
+            indent: 0,
+            code,
+            comments,
+            last_end: None,
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+    /// Keep a blank line the source has before `start`.
+    fn blank_line(&mut self, start: usize) {
+        if let (Some(code), Some(end)) = (self.code, self.last_end) {
+            if end <= start
+                && code[end..start].matches('\n').count() >= 2
+                && !self.out.ends_with("\n\n")
+            {
+                self.out.push('\n');
+            }
+        }
+    }
+    fn flush_comments(&mut self, until: usize) {
+        while let Some(comment) = self.comments.front() {
+            if comment.start >= until {
+                break;
+            }
+            let comment = self.comments.pop_front().unwrap();
+            self.blank_line(comment.start);
+            self.line(&comment.text);
+            self.last_end = Some(comment.end);
+        }
+    }
+    /// Start printing what is at `start` in the source, after the comments before
+    /// `until`.
+    fn begin(&mut self, start: usize, until: usize) {
+        self.flush_comments(until);
+        self.blank_line(start);
+    }
+    /// Finish printing what ends at `end` in the source, with a comment that
+    /// follows it on the same line.
+    fn end(&mut self, end: usize) {
+        self.last_end = Some(end);
+        let code = match self.code {
+            Some(code) => code,
+            None => return,
+        };
+        if let Some(comment) = self.comments.front() {
+            let between = code.get(end..comment.start).unwrap_or("\n");
+            if between.chars().all(|c| c == ' ' || c == '\t') {
+                let comment = self.comments.pop_front().unwrap();
+                self.out.pop(); // the newline
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+                self.out.push('\n');
+                self.last_end = Some(comment.end);
+            }
+        }
+    }
+    /// Remove the last line printed, to continue it.
+    fn reopen_line(&mut self) -> String {
+        self.out.pop(); // the newline
+        let start = self.out.rfind('\n').map_or(0, |idx| idx + 1);
+        let line = self.out[start..].trim_start().to_string();
+        self.out.truncate(start);
+        line
+    }
+
+    fn program(&mut self, node: &ProgramNode) {
+        let mut is_previous_function = false;
+        for (idx, declare) in node.declares.iter().enumerate() {
+            let is_function = match declare {
+                DeclareNode::Function(node) => node.statements.is_some(),
+                _ => false,
+            };
+            // function definitions are set apart
+            if idx > 0 && (is_function || is_previous_function) && !self.out.ends_with("\n\n") {
+                self.out.push('\n');
+            }
+            self.declare(declare);
+            is_previous_function = is_function;
+        }
+        self.last_end = None;
+        self.flush_comments(usize::MAX);
+    }
+
+    fn declare(&mut self, node: &DeclareNode) {
+        match node {
+            DeclareNode::Function(node) => self.function(node),
+            DeclareNode::Variable(node) => self.statement(&StatementNode::Declare(node.clone())),
+            DeclareNode::Pragma(node) => self.statement(&StatementNode::Pragma(node.clone())),
+            DeclareNode::Typedef(node) => self.statement(&StatementNode::Typedef(node.clone())),
+            DeclareNode::Error(_) => (),
+        }
+    }
+
+    fn function(&mut self, node: &FunctionNode) {
+        let span = &node.span;
+        let header = format!(
+            "{}{}",
+            if node.is_extern { "extern " } else { "" },
+            function_declarator(
+                &node.return_type,
+                &node.identifier,
+                &node.parameters,
+                node.is_var_args
+            )
+        );
+        match &node.statements {
+            Some(statements) => {
+                self.begin(span.start, span.start);
+                self.line(&format!("{} {{", header));
+                self.block(statements, span.end);
+                self.line("}");
+            }
+            None => {
+                self.begin(span.start, span.end);
+                self.line(&format!("{};", header));
+            }
+        }
+        self.end(span.end);
+    }
+
+    /// Print statements one level deeper, up to the `}` that closes them at `end`.
+    fn block<'b, I>(&mut self, statements: I, end: usize)
+    where
+        I: IntoIterator<Item = &'b StatementNode>,
+    {
+        self.indent += 1;
+        self.last_end = None;
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.flush_comments(end);
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, node: &StatementNode) {
+        let span = node.span();
+        match node {
+            StatementNode::Compound(node) => {
+                self.begin(span.start, span.start);
+                self.line("{");
+                self.block(&node.statements, span.end);
+                self.line("}");
+            }
+            StatementNode::If(node) => {
+                self.begin(span.start, span.start);
+                self.if_statement(node, "");
+            }
+            StatementNode::While(node) => {
+                self.begin(span.start, span.start);
+                let header = format!("while ({})", expression(&node.condition_expression, 0));
+                self.body(&header, &node.block);
+            }
+            StatementNode::For(node) => {
+                self.begin(span.start, span.start);
+                let header = format!(
+                    "for ({} {}; {})",
+                    inline_statement(&node.first_statement),
+                    expression(&node.condition_expression, 0),
+                    expression(&node.loop_expression, 0)
+                );
+                self.body(&header, &node.block);
+            }
+            StatementNode::Switch(node) => {
+                self.begin(span.start, span.start);
+                let condition = expression(&node.condition_expression, 0);
+                self.line(&format!("switch ({}) {{", condition));
+                self.block(&node.statements.statements, span.end);
+                self.line("}");
+            }
+            StatementNode::Case(node) => {
+                self.begin(span.start, span.start);
+                self.line(&format!(
+                    "case {}:",
+                    expression(&node.condition_expression, 0)
+                ));
+                self.labeled(&node.statements);
+            }
+            StatementNode::Default(node) => {
+                self.begin(span.start, span.start);
+                self.line("default:");
+                self.labeled(&node.statements);
+            }
+            StatementNode::Struct(StructStatementNode::Definition(node)) => {
+                self.begin(span.start, span.start);
+                self.struct_definition(node, "", "};");
+            }
+            StatementNode::Enum(EnumStatementNode::Definition(node)) => {
+                self.begin(span.start, span.start);
+                self.enum_definition(node, "", "};");
+            }
+            StatementNode::Typedef(TypedefNode {
+                identifier,
+                value_type,
+                definition: Some(definition),
+                ..
+            }) => {
+                self.begin(span.start, span.start);
+                // the declarator follows the `}`, as in `} *P;`
+                let close = format!("}} {};", bare_declarator(value_type, identifier));
+                let prefix = match base(value_type) {
+                    CType::Qualified(_, qualifiers) => {
+                        format!("typedef {} ", qualifiers.keywords().join(" "))
+                    }
+                    _ => String::from("typedef "),
+                };
+                match definition {
+                    TypeDefinitionNode::Struct(node) => {
+                        self.struct_definition(node, &prefix, &close)
+                    }
+                    TypeDefinitionNode::Enum(node) => self.enum_definition(node, &prefix, &close),
+                }
+            }
+            StatementNode::Error(_) => return,
+            node => {
+                self.begin(span.start, span.end);
+                self.line(&simple_statement(node));
+            }
+        }
+        self.end(span.end);
+    }
+
+    /// Print a struct definition, from `prefix` and `struct` to `close`.
+    fn struct_definition(&mut self, node: &StructDefinitionNode, prefix: &str, close: &str) {
+        match &node.tag {
+            Some(tag) => self.line(&format!("{}struct {} {{", prefix, tag)),
+            None => self.line(&format!("{}struct {{", prefix)),
+        }
+        self.indent += 1;
+        for member in &node.members {
+            let text = declarator(&member.value_type, &member.identifier, false);
+            self.line(&format!("{};", text));
+        }
+        self.flush_comments(node.span.end);
+        self.indent -= 1;
+        self.line(close);
+    }
+
+    /// Print an enum definition, from `prefix` and `enum` to `close`.
+    fn enum_definition(&mut self, node: &EnumDefinitionNode, prefix: &str, close: &str) {
+        match &node.tag {
+            Some(tag) => self.line(&format!("{}enum {} {{", prefix, tag)),
+            None => self.line(&format!("{}enum {{", prefix)),
+        }
+        self.indent += 1;
+        self.last_end = None;
+        for enumerator in &node.enums {
+            self.begin(enumerator.span.start, enumerator.span.end);
+            let text = match &enumerator.initializer {
+                Some(value) => format!("{} = {},", enumerator.identifier, initializer(value)),
+                None => format!("{},", enumerator.identifier),
+            };
+            self.line(&text);
+            self.end(enumerator.span.end);
+        }
+        self.flush_comments(node.span.end);
+        self.indent -= 1;
+        self.line(close);
+    }
+
+    fn if_statement(&mut self, node: &IfStatementNode, prefix: &str) {
+        let header = format!(
+            "{}if ({})",
+            prefix,
+            expression(&node.condition_expression, 0)
+        );
+        self.body(&header, &node.block);
+        let else_block = match &node.else_block {
+            Some(else_block) => else_block,
+            None => return,
+        };
+        let prefix = match node.block.as_ref() {
+            StatementNode::Compound(_) => format!("{} else ", self.reopen_line()),
+            _ => String::from("else "),
+        };
+        match else_block.as_ref() {
+            StatementNode::If(node) => self.if_statement(node, &prefix),
+            else_block => self.body(prefix.trim_end(), else_block),
+        }
+    }
+
+    /// Print `header` and the statement it controls: a block opens on the same
+    /// line, another statement goes on the next line, one level deeper.
+    fn body(&mut self, header: &str, node: &StatementNode) {
+        match node {
+            StatementNode::Compound(block) => {
+                self.line(&format!("{} {{", header));
+                self.block(&block.statements, block.span.end);
+                self.line("}");
+            }
+            node => {
+                self.line(header);
+                self.indent += 1;
+                self.last_end = None;
+                self.statement(node);
+                self.indent -= 1;
+            }
+        }
+    }
+
+    /// The statements after a `case` or `default` label, one level deeper.
+    fn labeled(&mut self, node: &CompoundStatementNode) {
+        self.indent += 1;
+        self.last_end = None;
+        for statement in &node.statements {
+            self.statement(statement);
+        }
+        self.indent -= 1;
+    }
+}
+
+/// A statement that fits on one line.
+fn simple_statement(node: &StatementNode) -> String {
+    match node {
+        StatementNode::Expression(node) => format!("{};", expression(&node.expression, 0)),
+        StatementNode::Return(node) => format!("return {};", expression(&node.expression, 0)),
+        StatementNode::Declare(node) => declaration(node),
+        StatementNode::Struct(StructStatementNode::Declare(node))
+        | StatementNode::Enum(EnumStatementNode::Declare(node)) => declaration(node),
+        StatementNode::Break(_) => String::from("break;"),
+        StatementNode::Continue(_) => String::from("continue;"),
+        StatementNode::Typedef(node) => match &node.parameters {
+            Some(parameters) => format!(
+                "typedef {};",
+                function_declarator(
+                    &node.value_type,
+                    &node.identifier,
+                    parameters,
+                    node.is_var_args
+                )
+            ),
+            None => format!(
+                "typedef {};",
+                declarator(&node.value_type, &node.identifier, false)
+            ),
+        },
+        StatementNode::Pragma(node) => format!("#pragma {}", node.pragma),
+        StatementNode::Empty(_) => String::from(";"),
+        node => inline_statement(node),
+    }
+}
+
+/// A statement on one line, such as the first statement of a `for`.
+fn inline_statement(node: &StatementNode) -> String {
+    let mut unparser = Unparser::new(None, VecDeque::new());
+    unparser.statement(node);
+    unparser
+        .out
+        .lines()
+        .map(|line| line.trim())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Print the declarators after the type they share, as in `int a = 1, *p;`.
+fn declaration(node: &DeclareStatementNode) -> String {
+    let declarators: Vec<String> = node
+        .declare_variable_nodes
+        .iter()
+        .map(|node| {
+            let declarator = bare_declarator(&node.value_type, &node.identifier);
+            match &node.initializer {
+                Some(value) => format!("{} = {}", declarator, initializer(value)),
+                None => declarator,
+            }
+        })
+        .collect();
+    let base = node
+        .declare_variable_nodes
+        .first()
+        .map(|node| base_type(&node.value_type))
+        .unwrap_or_default();
+    format!("{} {};", base, declarators.join(", "))
+}
+
+/// Print an initializer, as in `{1, [2] = 3, .x = {4}}`.
+fn initializer(node: &Initializer) -> String {
+    match node {
+        Initializer::Expression(node) => expression(node, ASSIGNMENT_PRECEDENCE),
+        Initializer::List(node) => {
+            let items: Vec<String> = node
+                .items
+                .iter()
+                .map(|item| {
+                    let designators: String = item
+                        .designators
+                        .iter()
+                        .map(|designator| match designator {
+                            Designator::Field(name) => format!(".{}", name),
+                            Designator::Index(index) => format!("[{}]", index),
+                        })
+                        .collect();
+                    match designators.as_str() {
+                        "" => initializer(&item.initializer),
+                        _ => format!("{} = {}", designators, initializer(&item.initializer)),
+                    }
+                })
+                .collect();
+            format!("{{{}}}", items.join(", "))
+        }
+    }
+}
+
+/// The type a declarator starts from, such as `const int` in `const int *p[3]`.
+fn base(value_type: &CType) -> &CType {
+    match value_type {
+        CType::Pointer(ctype) | CType::Array(ctype, _) => base(ctype),
+        CType::Function { return_type, .. } => base(return_type),
+        CType::Qualified(ctype, _) if is_pointer(ctype) => base(ctype),
+        ctype => ctype,
+    }
+}
+
+fn base_type(value_type: &CType) -> String {
+    match base(value_type) {
+        CType::Qualified(ctype, qualifiers) => {
+            format!("{} {}", qualifiers.keywords().join(" "), base_type(ctype))
+        }
+        CType::Void => String::from("void"),
+        CType::Int { bits: 1, .. } => String::from("_Bool"),
+        CType::Int { bits, is_signed } => {
+            let name = match bits {
+                8 => "char",
+                16 => "short",
+                32 => "int",
+                _ => "long",
+            };
+            if *is_signed {
+                name.to_string()
+            } else {
+                format!("unsigned {}", name)
+            }
+        }
+        CType::Float { bits: 32 } => String::from("float"),
+        CType::Float { bits: 64 } => String::from("double"),
+        CType::Float { .. } => String::from("long double"),
+        CType::Struct(Some(tag)) => format!("struct {}", tag),
+        CType::Struct(None) => String::from("struct"),
+        CType::Union(tag) => format!("union {}", tag),
+        CType::Enum(Some(tag)) => format!("enum {}", tag),
+        CType::Enum(None) => String::from("enum"),
+        CType::Typedef(name) => name.clone(),
+        _ => unreachable!("the base of a type is not derived"),
+    }
+}
+
+fn is_pointer(ctype: &CType) -> bool {
+    matches!(ctype, CType::Pointer(_))
+}
+
+/// The declarator of `identifier` without its base type, such as `*p[3]`.
+fn bare_declarator(value_type: &CType, identifier: &str) -> String {
+    derived_declarator(identifier.to_string(), value_type, false)
+}
+
+/// Declare `identifier` of `value_type`. The identifier is empty for a parameter
+/// without a name.
+fn declarator(value_type: &CType, identifier: &str, is_parameter: bool) -> String {
+    let declarator = derived_declarator(identifier.to_string(), value_type, is_parameter);
+    format!("{} {}", base_type(value_type), declarator)
+        .trim_end()
+        .to_string()
+}
+
+/// Wrap `inner` in the pointers, arrays and functions `value_type` is derived
+/// with, from the outermost. A pointer is put in parentheses before an array or
+/// function, as in `(*p)[3]`. An array parameter is written as a pointer, so that
+/// pointer is printed back as `[]`.
+fn derived_declarator(inner: String, value_type: &CType, is_parameter: bool) -> String {
+    let parenthesize = |inner: String| {
+        if inner.starts_with('*') {
+            format!("({})", inner)
+        } else {
+            inner
+        }
+    };
+    match value_type {
+        CType::Pointer(ctype) => {
+            pointer_declarator(inner, ctype, Qualifiers::default(), is_parameter)
+        }
+        CType::Qualified(pointer, qualifiers) => match &**pointer {
+            CType::Pointer(ctype) => pointer_declarator(inner, ctype, *qualifiers, is_parameter),
+            _ => inner,
+        },
+        CType::Array(ctype, size) => {
+            let size = size.map(|size| size.to_string()).unwrap_or_default();
+            derived_declarator(format!("{}[{}]", parenthesize(inner), size), ctype, false)
+        }
+        CType::Function {
+            return_type,
+            parameters,
+            is_var_args,
+        } => {
+            let mut parameters: Vec<String> = parameters
+                .iter()
+                .map(|parameter| declarator(parameter, "", true))
+                .collect();
+            if *is_var_args {
+                parameters.push(String::from("..."));
+            }
+            let inner = format!("{}({})", parenthesize(inner), parameters.join(", "));
+            derived_declarator(inner, return_type, false)
+        }
+        _ => inner,
+    }
+}
+
+/// Wrap `inner` in a pointer to `ctype`, as in `*const p`.
+fn pointer_declarator(
+    inner: String,
+    ctype: &CType,
+    qualifiers: Qualifiers,
+    is_parameter: bool,
+) -> String {
+    if is_parameter && qualifiers == Qualifiers::default() && matches!(ctype, CType::Array(_, _)) {
+        return derived_declarator(inner + "[]", ctype, false);
+    }
+    let mut words = qualifiers.keywords().join(" ");
+    if !words.is_empty() {
+        words.push(' ');
+    }
+    let inner = format!("*{}{}", words, inner).trim_end().to_string();
+    derived_declarator(inner, ctype, false)
+}
+
+/// Declare the function `identifier`, such as `int f(int a, ...)`.
+fn function_declarator(
+    return_type: &CType,
+    identifier: &str,
+    parameters: &VecDeque<DeclareVariableNode>,
+    is_var_args: bool,
+) -> String {
+    let mut parameters: Vec<String> = parameters
+        .iter()
+        .map(|parameter| declarator(&parameter.value_type, &parameter.identifier, true))
+        .collect();
+    if is_var_args {
+        parameters.push(String::from("..."));
+    }
+    let inner = format!("{}({})", identifier, parameters.join(", "));
+    format!(
+        "{} {}",
+        base_type(return_type),
+        derived_declarator(inner, return_type, false)
+    )
+}
+
+/// Print an expression that is an operand of an operator of `min_precedence`, in
+/// parentheses when it binds less tightly.
+fn expression(node: &ExpressionNode, min_precedence: u32) -> String {
+    match node {
+        ExpressionNode::BinExp(node) => {
+            let op = token_text(&node.op.token);
+            let property = get_property(&op);
+            let precedence = property.precedence;
+            let (lhs_precedence, rhs_precedence) = match property.associativity {
+                Associativity::Left => (precedence, precedence + 1),
+                Associativity::Right => (precedence + 1, precedence),
+            };
+            let lhs = expression(&node.lhs, lhs_precedence);
+            let rhs = expression(&node.rhs, rhs_precedence);
+            let text = if op == "," {
+                format!("{}, {}", lhs, rhs)
+            } else {
+                format!("{} {} {}", lhs, op, rhs)
+            };
+            parenthesize(text, precedence < min_precedence)
+        }
+        ExpressionNode::TernaryExp(node) => {
+            let text = format!(
+                "{} ? {} : {}",
+                expression(&node.condition, TERNARY_PRECEDENCE + 1),
+                expression(&node.lhs, 0),
+                expression(&node.rhs, TERNARY_PRECEDENCE)
+            );
+            parenthesize(text, TERNARY_PRECEDENCE < min_precedence)
+        }
+        ExpressionNode::Prefix(node) => {
+            let op = token_text(&node.prefix.token);
+            let operand = match node.node.as_ref() {
+                ExpressionNode::BinExp(_) | ExpressionNode::TernaryExp(_) => {
+                    parenthesize(expression(&node.node, 0), true)
+                }
+                operand => expression(operand, 0),
+            };
+            // so that `- -a` is not read as `--a`
+            let is_glued = match (op.chars().last(), operand.chars().next()) {
+                (Some(last), Some(first)) => last == first && "+-&".contains(last),
+                _ => false,
+            };
+            if is_glued {
+                format!("{} {}", op, operand)
+            } else {
+                format!("{}{}", op, operand)
+            }
+        }
+        ExpressionNode::Suffix(node) => {
+            format!(
+                "{}{}",
+                postfix_operand(&node.node),
+                token_text(&node.suffix.token)
+            )
+        }
+        ExpressionNode::ArrayIndex(node) => format!(
+            "{}[{}]",
+            postfix_operand(&node.array),
+            expression(&node.index, 0)
+        ),
+        ExpressionNode::FunctionCall(node) => format!(
+            "{}({})",
+            token_text(&node.identifier.token),
+            expression(&node.parameters, 0)
+        ),
+        ExpressionNode::Access(node) => format!(
+            "{}.{}",
+            postfix_operand(&node.node),
+            token_text(&node.access_identifier)
+        ),
+        ExpressionNode::Token(node) => token_text(&node.token),
+        ExpressionNode::Empty(_) => String::new(),
+    }
+}
+
+/// The operand of a call, subscript, member access or suffix `++`, which binds
+/// tighter than any other operator.
+fn postfix_operand(node: &ExpressionNode) -> String {
+    match node {
+        ExpressionNode::BinExp(_) | ExpressionNode::TernaryExp(_) | ExpressionNode::Prefix(_) => {
+            parenthesize(expression(node, 0), true)
+        }
+        node => expression(node, 0),
+    }
+}
+
+fn parenthesize(text: String, is_needed: bool) -> String {
+    if is_needed {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Ide(val, _)
+        | Token::Keyword(val, _)
+        | Token::Op(val, _)
+        | Token::PrefixOp(val, _)
+        | Token::SuffixOp(val, _) => val.clone(),
+        Token::Pragma(val, _) => format!("#pragma {}", val),
+        // literals are printed as they were written
+        token => token.debug_info().s.clone(),
+    }
+}
@@ -0,0 +1,8 @@
+int big[4294967296];
+int negative[-1];
+
+int main() {
+    enum color { RED, GREEN };
+    int a[2] = {[GREEN] = 1};
+    return 0;
+}
@@ -0,0 +1,5 @@
+int a[2] = {1, 2, 3};
+
+int main() {
+    return a[0];
+}
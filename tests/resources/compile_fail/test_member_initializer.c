@@ -0,0 +1,7 @@
+int main() {
+    struct point {
+        int x;
+        int y = 1;
+    };
+    return 0;
+}
@@ -0,0 +1,18 @@
+int x = ;
+
+int main() {
+    int a = 1;
+    if (a > 1 {
+        a = 2;
+    } else {
+        a = 3;
+    }
+    while (a < 3) {
+        break
+    }
+    return a
+}
+
+int f() {
+    return 1;
+}
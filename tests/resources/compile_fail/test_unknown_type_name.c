@@ -0,0 +1,8 @@
+int twice(number n) {
+    return n * 2;
+}
+
+int main() {
+    typedef int number;
+    return twice(2);
+}
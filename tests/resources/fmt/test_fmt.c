@@ -0,0 +1,27 @@
+// a program to format
+#pragma once
+int  g=1 ;
+const  char*const name=0;
+
+int add(int a,int b){return a+b;} /* after add */
+int main( ){
+  struct point{int x;int y;};
+  enum color{RED,GREEN=3,BLUE};
+    int arr[3];   // three of them
+    int *p;
+    volatile unsigned   long ticks=0;
+    struct point pt;
+    pt.x=(1+2)*3;pt.y=1+(2*3);
+
+
+    for(int i=0;i<3;i++)arr[i]=i;
+    if(pt.x>pt.y){
+        // the first branch
+        g=-(-g);
+    }
+    else if(pt.x==pt.y)g=0;else{g=2;}
+    switch(g){case 1:g++;break;default:g--;}
+    while(g<10)g+=add(g,1);
+    return (g==10)?(g,1):pt.y+(2*3);
+}
+// end of the program
@@ -0,0 +1,8 @@
+int main() {
+    struct point {
+        int x;
+        int y;
+    };
+    int a = 2;
+    return a + 1;
+}
@@ -0,0 +1,20 @@
+int main() {
+    int i = 3;
+    unsigned char c = 250;
+    long long big = i + 5000000000LL;
+    int f = i * 2.5;
+    int status = 0;
+    if (big > 4294967296LL) {
+        status = status + 1;
+    }
+    if (-1 < 10u) {
+        status = status + 8;
+    } else {
+        status = status + 2;
+    }
+    if (c + c > 255) {
+        status = status + 4;
+    }
+    c += 10;
+    return status * 10 + f + c;
+}
@@ -0,0 +1,20 @@
+// comments are removed before the code is lexed
+#include <stdio.h> // so they can follow a directive
+
+/* a block comment
+   can span several lines, and don't mind an apostrophe */
+#define SCALE /* inside a macro */ 3 // after a macro
+
+int triple(int x) {
+    return x * SCALE; /* return x + 1; */
+}
+
+int main() {
+    int a = 4; // int a = 0;
+    int b = /**/ 2;
+    int t = triple(a);
+    printf("/* not a comment */ // %d", t);
+    // the next line is a comment \
+    return 0;
+    return a */* multiply */b;
+}
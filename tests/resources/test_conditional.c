@@ -0,0 +1,40 @@
+#include <stdio.h>
+#include <stdio.h>
+
+#define VERSION 3
+#define FEATURE_A
+
+#if VERSION >= 3 && defined(FEATURE_A)
+#define BASE 10
+#elif VERSION == 2
+#define BASE 20
+#else
+#define BASE 30
+#endif
+
+#ifdef FEATURE_B
+int broken = 'unterminated;
+#else
+#ifndef FEATURE_C
+#define EXTRA (2 + 2)
+#else
+#define EXTRA 0
+#endif
+#endif
+
+#if !defined FEATURE_A || (0 && 1 / 0)
+#error "unreachable"
+#elif (VERSION > 1 ? 0x10 : 0) == 16 && ~0 == -1 && 10u > 2 \
+    && 0xFFFFFFFF > -1 && 0x80000000 > -1 && !(0xFFFFFFFFFFFFFFFF > -1)
+#define MULTIPLIER 2
+#endif
+
+#if 0
+#if 1
+#define BASE 0
+#endif
+#endif
+
+int main() {
+    return BASE * MULTIPLIER + EXTRA;
+}
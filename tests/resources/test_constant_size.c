@@ -0,0 +1,8 @@
+#define N 4
+
+int table[N - 1] = {[N - 2] = 7, [(1 < 2) * 1] = 2};
+
+int main() {
+    int c[2 * 3 + -1] = {[2 * 2] = 5, [N > 3 ? 1 : 2] = 1};
+    return table[2] + table[1] + c[4] + c[1];
+}
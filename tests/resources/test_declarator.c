@@ -0,0 +1,23 @@
+extern int printf(const char *, ...);
+
+int last(int *values[], int n) {
+    return *values[n - 1];
+}
+
+int second_row(int (*rows)[2]) {
+    return rows[1][0] + rows[1][1];
+}
+
+int main() {
+    int a = 3, b = 4;
+    int *p = &a, **pp = &p;
+    int *values[2];
+    values[0] = &a;
+    values[1] = &b;
+    int grid[3][2];
+    grid[1][0] = 5;
+    grid[1][1] = 6;
+    int (*rows)[2] = &grid[1];
+    int (*cb)(int, int);
+    return **pp + last(values, 2) + second_row(grid) + (*rows)[1];
+}
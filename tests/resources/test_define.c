@@ -0,0 +1,10 @@
+#define SIZE 10
+#define DOUBLE_SIZE (SIZE + SIZE)
+#define a a + 1
+
+int main() {
+#undef a
+    int a = 3;
+#define a a + 1
+    return DOUBLE_SIZE * a;
+}
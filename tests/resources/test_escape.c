@@ -0,0 +1,12 @@
+#include <stdio.h>
+
+int main() {
+    char nl = '\n';
+    int quote = '\'';
+    printf("a\tb\\" "c\"d"
+           "\x41\101é");
+    putchar(nl);
+    printf("%d,%d;", '\0', 'z' - 'a');
+    printf("%s-%s", "x", "y");
+    return quote + '\x10';
+}
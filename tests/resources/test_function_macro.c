@@ -0,0 +1,15 @@
+#define MUL(a, b) ((a) * (b))
+#define CAT(a, b) a##b
+#define SUM(first, ...) first + add(__VA_ARGS__)
+#define TWICE(x) (x) * 2
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int main() {
+    int CAT(val, ue) = MUL(3,
+                           7);
+    int sum = SUM(1, 2, 3);
+    return TWICE(value) + sum;
+}
@@ -0,0 +1,9 @@
+int twice(int value);
+
+int main() {
+    return twice(4);
+}
+
+int twice(int twice) {
+    return twice + twice;
+}
@@ -0,0 +1,9 @@
+#include <stdio.h>
+#include <stdbool.h>
+#include "test_include.h"
+
+int main() {
+    int n = square(7);
+    printf("%d", n);
+    return true + HELPER_VALUE;
+}
@@ -0,0 +1,5 @@
+#define HELPER_VALUE 10
+
+int square(int x) {
+    return x * x;
+}
@@ -0,0 +1,21 @@
+typedef struct point {
+    int x;
+    int y;
+} Point;
+
+int primes[5] = {2, 3, 5, 7, 11};
+int grid[2][3] = {{1, 2}, {4}};
+int sparse[] = {[3] = 4, 1};
+
+int main() {
+    int a[3] = {1, 2, 3};
+    int board[8][8] = {0};
+    int flat[2][2] = {1, 2, 3};
+    int squares[] = {0, 1, 4, 9,};
+    int days[4] = {[2] = 30, [0] = 31};
+    Point p = {.y = 5, .x = 2};
+    Point q = {7};
+    int sum = a[2] + board[7][7] + flat[1][0] + flat[1][1] + squares[3];
+    sum = sum + days[2] - days[0] + days[3] + p.x * p.y + q.x + q.y;
+    return sum + primes[4] + grid[0][1] + grid[1][2] + sparse[4];
+}
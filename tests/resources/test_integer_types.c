@@ -0,0 +1,24 @@
+typedef unsigned long size;
+
+int widen(int value) {
+    return value;
+}
+
+int main() {
+    const int k = 4;
+    unsigned char c = 200;
+    signed char d = -56;
+    short s;
+    long long l;
+    size n;
+    unsigned int u = 4294967295u;
+    int i = c;
+    int j = d;
+    s = 3;
+    l = 5;
+    n = 2;
+    if (d < 0) {
+        j = j + widen(c);
+    }
+    return k + s + l + n + i + j - 300 + (u > 1);
+}
@@ -0,0 +1,22 @@
+#include <stdio.h>
+
+const int forward = 3;
+
+int doit(const int iffy) { return iffy * 2; }
+
+int main() {
+    int format = 1;
+    int integer = 2;
+    int returned = 0;
+    int switched = 4;
+    int structure = 5;
+    int const elsewhere = 6;
+    const char *const whileloop = "ok";
+    volatile int casefold = 7;
+    returned = doit(integer);
+    printf("%s", whileloop);
+    if (format) {
+        returned = returned + forward + switched + structure + elsewhere + casefold;
+    }
+    return returned;
+}
@@ -0,0 +1,12 @@
+long double scale = 2.5L;
+
+long double twice(long double x) {
+    return x + x;
+}
+
+int main() {
+    long double x = 1.5L;
+    double y = x + 0.25;
+    long double z = twice(x) + scale;
+    return z * 10 + y * 4;
+}
@@ -0,0 +1,20 @@
+int first = 2, second = 3;
+
+int main() {
+    int i, j;
+    i = 1;
+    j = 2;
+    int a = 10, *p = &a, arr[4];
+    arr[3] = 4;
+    struct pair {
+        int x, y;
+    };
+    struct pair u, v;
+    u.x = 5;
+    v.y = 6;
+    int sum = 0;
+    for (int k = 0, n = 3; k < n; k++) {
+        sum += k;
+    }
+    return first + second + i + j + *p + arr[3] + u.x + v.y + sum;
+}
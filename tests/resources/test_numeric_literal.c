@@ -0,0 +1,15 @@
+int main() {
+    int hex = 0x1F;
+    int oct = 017;
+    int bin = 0b101;
+    int u = 10u;
+    int l = 3L;
+    int big = 10000000000 - 9999999990LL;
+    float f = 2.f;
+    float g = .5;
+    float e = 1e1;
+    float h = 0x1.8p1 + 0x10p-4f;
+    int total = hex + oct + bin + u + l;
+    int fl = f * g + e + h;
+    return total + fl + big;
+}
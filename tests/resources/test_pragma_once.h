@@ -0,0 +1,5 @@
+#pragma once
+
+int once_value() {
+    return 3;
+}
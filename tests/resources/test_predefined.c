@@ -0,0 +1,17 @@
+#include "test_pragma_once.h"
+#include "test_pragma_once.h"
+
+#pragma pack(1)
+
+#if __STDC__ && __STDC_VERSION__ >= 199901L && defined(__REXPARSER__)
+#define OFFSET 10
+#endif
+
+int main() {
+#pragma unroll
+    int first = __LINE__;
+#line 40
+    int second = __LINE__;
+    int third = once_value();
+    return first + second + third + OFFSET;
+}
@@ -0,0 +1,7 @@
+int main() {
+    char *s = "\xff\377a\0b";
+    unsigned char first = s[0];
+    unsigned char second = s[1];
+    char after = s[4];
+    return first + second - 500 + s[2] - 97 + after - 98 + s[3];
+}
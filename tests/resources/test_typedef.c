@@ -0,0 +1,45 @@
+typedef int number;
+typedef int *number_pointer;
+typedef int printer(const char *format, ...);
+
+extern printer printf;
+
+int twice(number n) {
+    return n * 2;
+}
+
+// a parameter hides a typedef name in its parameter list only
+int scaled(int number, int factor);
+number thrice(number n) {
+    return n * 3;
+}
+
+int main() {
+    typedef struct point {
+        int x;
+        int y;
+    } Point;
+    typedef struct {
+        int width;
+    } Size;
+    typedef enum { SMALL, MEDIUM = 4, LARGE } Scale;
+    typedef number row[3];
+
+    Point p;
+    p.x = 1;
+    p.y = 2;
+    Size s;
+    s.width = 10;
+    Scale scale = LARGE;
+    row r;
+    r[2] = 20;
+    number n = 3;
+    number_pointer np;
+    np = &n;
+    {
+        // a variable can hide a typedef name
+        int number = 10;
+        n = n + number;
+    }
+    return p.x + p.y + s.width + scale + r[2] + *np + twice(n) + thrice(1);
+}
@@ -0,0 +1,14 @@
+int main() {
+    int a = 12;
+    int b = 10;
+    int c = a&b;
+    int d;
+    d=++c;
+    int e = -d;
+    int f = --a;
+    int g = a--;
+    (b)++;
+    int *p = &b;
+    int h = *p + +e;
+    return c + d + f + g + a + h - -b;
+}
@@ -0,0 +1,132 @@
+use std::process::Command;
+
+fn compile_fail(filepath: &str, errors: &[&str]) {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("./target/debug/rexparser \"{}\"", filepath))
+        .output()
+        .expect("process failed to execute");
+
+    // assert status
+    println!("{:?}", output.status.to_string());
+    assert!(!output.status.success());
+
+    // assert stderr
+    let stderr_string = std::str::from_utf8(&output.stderr).unwrap();
+    println!("{}", stderr_string);
+    for error in errors {
+        assert!(stderr_string.contains(error));
+    }
+}
+
+#[test]
+fn test_unknown_character() {
+    let filepath = "./tests/resources/compile_fail/test_unknown_character.c";
+    compile_fail(
+        filepath,
+        &[
+            "./tests/resources/compile_fail/test_unknown_character.c:2:15: error: unknown character '@'",
+            "./tests/resources/compile_fail/test_unknown_character.c:3:15: error: unknown character '$'",
+            "./tests/resources/compile_fail/test_unknown_character.c:4:14: error: unknown character '\\\\'",
+        ],
+    );
+}
+
+#[test]
+fn test_missing_semicolon() {
+    let filepath = "./tests/resources/compile_fail/test_missing_semicolon.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_missing_semicolon.c:4:1: error: expected \";\" but found \"}\""],
+    );
+}
+
+#[test]
+fn test_undeclared_identifier() {
+    let filepath = "./tests/resources/compile_fail/test_undeclared_identifier.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_undeclared_identifier.c:3:16: error: use of undeclared identifier \"b\""],
+    );
+}
+
+#[test]
+fn test_syntax_errors() {
+    let filepath = "./tests/resources/compile_fail/test_syntax_errors.c";
+    compile_fail(
+        filepath,
+        &[
+            "./tests/resources/compile_fail/test_syntax_errors.c:1:9: error: expected an expression but found \";\"",
+            "./tests/resources/compile_fail/test_syntax_errors.c:5:15: error: expected \")\" but found \"{\"",
+            "./tests/resources/compile_fail/test_syntax_errors.c:12:5: error: expected \";\" but found \"}\"",
+            "./tests/resources/compile_fail/test_syntax_errors.c:14:1: error: expected \";\" but found \"}\"",
+        ],
+    );
+}
+
+#[test]
+fn test_break_outside_loop() {
+    let filepath = "./tests/resources/compile_fail/test_break_outside_loop.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_break_outside_loop.c:3:5: error: a break statement not within a loop or switch"],
+    );
+}
+
+#[test]
+fn test_unknown_type_name() {
+    let filepath = "./tests/resources/compile_fail/test_unknown_type_name.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_unknown_type_name.c:1:11: error: unknown type name \"number\""],
+    );
+}
+
+#[test]
+fn test_excess_initializer() {
+    let filepath = "./tests/resources/compile_fail/test_excess_initializer.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_excess_initializer.c:1:19: error: excess elements in an initializer"],
+    );
+}
+
+#[test]
+fn test_member_initializer() {
+    let filepath = "./tests/resources/compile_fail/test_member_initializer.c";
+    compile_fail(
+        filepath,
+        &["./tests/resources/compile_fail/test_member_initializer.c:4:13: error: a struct member cannot have an initializer"],
+    );
+}
+
+#[test]
+fn test_array_size() {
+    let filepath = "./tests/resources/compile_fail/test_array_size.c";
+    compile_fail(
+        filepath,
+        &[
+            "./tests/resources/compile_fail/test_array_size.c:1:9: error: an array size of 4294967296 is too large",
+            "./tests/resources/compile_fail/test_array_size.c:2:14: error: an array size is negative",
+            "./tests/resources/compile_fail/test_array_size.c:6:18: error: an array index can only use integer literals, not \"GREEN\"",
+        ],
+    );
+}
+
+#[test]
+fn test_unwritable_output() {
+    // compiled.ll cannot be written where it is a directory
+    let dir = std::env::temp_dir().join("rexparser_test_unwritable_output");
+    std::fs::create_dir_all(dir.join("compiled.ll")).unwrap();
+    let root = std::env::current_dir().unwrap();
+    let output = Command::new(root.join("target/debug/rexparser"))
+        .arg(root.join("tests/resources/test_single_num.c"))
+        .current_dir(&dir)
+        .output()
+        .expect("process failed to execute");
+
+    assert!(!output.status.success());
+    let stderr_string = std::str::from_utf8(&output.stderr).unwrap();
+    println!("{}", stderr_string);
+    assert!(stderr_string.contains("error: compiled.ll: "));
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::process::Command;
+
+use rexparser::lexer::lexer::Lexer;
+use rexparser::lexer::token::{DebugInfo, Token};
+use rexparser::parser::declare::initializer::*;
+use rexparser::parser::declare::*;
+use rexparser::parser::expression::*;
+use rexparser::parser::statement::*;
+use rexparser::parser::visitor::*;
+use rexparser::parser::*;
+use rexparser::preprocessor::Preprocessor;
+use rexparser::unparser::unparse;
+
+/// Forgets where each node and token was read from, so that trees parsed from
+/// different text compare equal.
+struct ClearLocations;
+
+macro_rules! clear_span {
+    ($($visit:ident, $walk:ident, $node:ty;)*) => {
+        $(
+            fn $visit(&mut self, node: &mut $node) {
+                node.span = Default::default();
+                $walk(self, node)
+            }
+        )*
+    };
+}
+
+fn clear_token(token: &mut Token) {
+    let debug_info = token.debug_info_mut();
+    debug_info.file = Default::default();
+    debug_info.start = 0;
+    debug_info.end = 0;
+    debug_info.presumed = None;
+}
+
+impl VisitorMut for ClearLocations {
+    clear_span! {
+        visit_function_mut, walk_function_mut, FunctionNode;
+        visit_declare_variable_mut, walk_declare_variable_mut, DeclareVariableNode;
+        visit_initializer_list_mut, walk_initializer_list_mut, InitializerListNode;
+        visit_typedef_mut, walk_typedef_mut, TypedefNode;
+        visit_struct_definition_mut, walk_struct_definition_mut, StructDefinitionNode;
+        visit_enum_definition_mut, walk_enum_definition_mut, EnumDefinitionNode;
+        visit_compound_statement_mut, walk_compound_statement_mut, CompoundStatementNode;
+        visit_expression_statement_mut, walk_expression_statement_mut, ExpressionStatementNode;
+        visit_return_statement_mut, walk_return_statement_mut, ReturnStatementNode;
+        visit_declare_statement_mut, walk_declare_statement_mut, DeclareStatementNode;
+        visit_if_statement_mut, walk_if_statement_mut, IfStatementNode;
+        visit_while_statement_mut, walk_while_statement_mut, WhileStatementNode;
+        visit_for_statement_mut, walk_for_statement_mut, ForStatementNode;
+        visit_switch_statement_mut, walk_switch_statement_mut, SwitchStatementNode;
+        visit_case_statement_mut, walk_case_statement_mut, CaseStatementNode;
+        visit_default_statement_mut, walk_default_statement_mut, DefaultStatementNode;
+        visit_bin_exp_mut, walk_bin_exp_mut, BinExpNode;
+        visit_ternary_exp_mut, walk_ternary_exp_mut, TernaryExpNode;
+        visit_prefix_mut, walk_prefix_mut, PrefixNode;
+        visit_suffix_mut, walk_suffix_mut, SuffixNode;
+        visit_array_index_mut, walk_array_index_mut, ArrayIndexNode;
+        visit_function_call_mut, walk_function_call_mut, FunctionCallNode;
+    }
+    fn visit_pragma_mut(&mut self, node: &mut PragmaNode) {
+        node.span = Default::default();
+    }
+    fn visit_statement_mut(&mut self, node: &mut StatementNode) {
+        if let StatementNode::Empty(span) = node {
+            *span = Default::default();
+        }
+        walk_statement_mut(self, node)
+    }
+    fn visit_break_statement_mut(&mut self, node: &mut BreakStatementNode) {
+        node.span = Default::default();
+    }
+    fn visit_continue_statement_mut(&mut self, node: &mut ContinueStatementNode) {
+        node.span = Default::default();
+    }
+    fn visit_expression_mut(&mut self, node: &mut ExpressionNode) {
+        if let ExpressionNode::Empty(span) = node {
+            *span = Default::default();
+        }
+        walk_expression_mut(self, node)
+    }
+    fn visit_access_mut(&mut self, node: &mut AccessNode) {
+        node.span = Default::default();
+        clear_token(&mut node.access_identifier);
+        walk_access_mut(self, node)
+    }
+    fn visit_token_mut(&mut self, node: &mut TokenNode) {
+        clear_token(&mut node.token);
+    }
+}
+
+fn parse(code: String) -> ProgramNode {
+    let mut tokens = Lexer::new().lex(code).expect("the code to lex");
+    let mut node = parser(&mut tokens).expect("the code to parse");
+    ClearLocations.visit_program_mut(&mut node);
+    node
+}
+
+fn round_trip(filepath: &str) {
+    let code = fs::read_to_string(filepath).unwrap();
+    let preprocessed = Preprocessor::new()
+        .preprocess(filepath, code)
+        .expect("the code to preprocess");
+    let node = parse(preprocessed.code);
+    let printed = unparse(&node);
+    println!("{}", printed);
+    assert!(parse(printed) == node);
+}
+
+#[test]
+fn test_round_trip() {
+    for dir in &["./tests/resources", "./tests/resources/builtin"] {
+        let mut filepaths: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path().to_string_lossy().into_owned())
+            .filter(|filepath| filepath.ends_with(".c"))
+            .collect();
+        filepaths.sort();
+        for filepath in filepaths {
+            println!("{}", filepath);
+            round_trip(&filepath);
+        }
+    }
+}
+
+#[test]
+fn test_fmt() {
+    let filepath = std::env::temp_dir().join("rexparser_test_fmt.c");
+    fs::copy("./tests/resources/fmt/test_fmt.c", &filepath).unwrap();
+    let expected = fs::read_to_string("./tests/resources/fmt/test_fmt.expected.c").unwrap();
+
+    // formatting again changes nothing
+    for _ in 0..2 {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "./target/debug/rexparser fmt \"{}\"",
+                filepath.display()
+            ))
+            .status()
+            .expect("process failed to execute");
+        assert!(status.success());
+        let formatted = fs::read_to_string(&filepath).unwrap();
+        println!("{}", formatted);
+        assert!(formatted == expected);
+    }
+}
+
+#[test]
+fn test_keep_comments() {
+    let code = "// a\n/* b */ int x; // c\n// d\n";
+    let text = |comments: &[DebugInfo]| {
+        comments
+            .iter()
+            .map(|comment| comment.s.clone())
+            .collect::<Vec<String>>()
+    };
+    let mut lexer = Lexer::new();
+    lexer.keep_comments = true;
+    let tokens = lexer.lex(code.to_string()).unwrap();
+    assert!(text(&tokens.tokens[0].debug_info().comments) == ["// a", "/* b */"]);
+    assert!(tokens.tokens[1].debug_info().comments.is_empty());
+    assert!(text(&tokens.trailing_comments) == ["// c", "// d"]);
+    assert!(tokens.tokens[0].debug_info().comments[1].start == 5);
+
+    // comments are dropped by default
+    let tokens = Lexer::new().lex(code.to_string()).unwrap();
+    assert!(tokens.tokens[0].debug_info().comments.is_empty());
+    assert!(tokens.trailing_comments.is_empty());
+}
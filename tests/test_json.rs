@@ -0,0 +1,122 @@
+use std::fs;
+use std::process::Command;
+
+use rexparser::json::*;
+use rexparser::lexer::lexer::Lexer;
+use rexparser::parser::parser;
+use rexparser::preprocessor::Preprocessor;
+
+fn rexparser(args: &str) -> String {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("./target/debug/rexparser {}", args))
+        .output()
+        .expect("process failed to execute");
+    println!("{}", std::str::from_utf8(&output.stderr).unwrap());
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_json_round_trip() {
+    for dir in &["./tests/resources", "./tests/resources/builtin"] {
+        let mut filepaths: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path().to_string_lossy().into_owned())
+            .filter(|filepath| filepath.ends_with(".c"))
+            .collect();
+        filepaths.sort();
+        for filepath in filepaths {
+            println!("{}", filepath);
+            let code = fs::read_to_string(&filepath).unwrap();
+            let preprocessed = Preprocessor::new().preprocess(&filepath, code).unwrap();
+            let tokens = Lexer::new()
+                .lex_mapped(preprocessed.code.clone(), &|debug_info| {
+                    preprocessed.map_debug_info(debug_info)
+                })
+                .unwrap();
+            assert!(tokens_from_json(&tokens_to_json(&tokens)).unwrap() == tokens);
+            let node = parser(&mut tokens.clone()).unwrap();
+            assert!(program_from_json(&program_to_json(&node)).unwrap() == node);
+        }
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let filepath = "./tests/resources/json/test_json.c";
+    let tokens = rexparser(&format!("--emit=tokens-json \"{}\"", filepath));
+    let expected = fs::read_to_string("./tests/resources/json/test_json.tokens.json").unwrap();
+    assert!(tokens == expected);
+    let ast = rexparser(&format!("--emit=ast-json \"{}\"", filepath));
+    let expected = fs::read_to_string("./tests/resources/json/test_json.ast.json").unwrap();
+    assert!(ast == expected);
+}
+
+#[test]
+fn test_json_version() {
+    let error = program_from_json("{\"version\": 8, \"program\": {\"declares\": []}}").unwrap_err();
+    assert!(error.message == "unsupported AST JSON version 8, expected 9");
+}
+
+/// The AST JSON of `code` with each `from` in it replaced by `to`.
+fn malformed(code: &str, from: &str, to: &str) -> String {
+    let mut tokens = Lexer::new().lex(code.to_string()).unwrap();
+    let node = parser(&mut tokens).unwrap();
+    program_to_json(&node).replace(from, to)
+}
+
+#[test]
+fn test_malformed_ast_json() {
+    // a tree the parser would not build is refused instead of compiled
+    let cases = [
+        (
+            "int main() { return 0; }",
+            "\"bits\": 32",
+            "\"bits\": 12",
+            "invalid AST JSON: an integer type of 12 bits",
+        ),
+        (
+            "int main() { return 1 + 2; }",
+            "\"Op\"",
+            "\"Ide\"",
+            "invalid AST JSON: \"+\" is not a binary operator",
+        ),
+        (
+            "int main() { return -1; }",
+            "\"PrefixOp\"",
+            "\"Ide\"",
+            "invalid AST JSON: \"-\" is not a prefix operator",
+        ),
+        (
+            "int main() { return -1; }",
+            "\"-\"",
+            "\"!\"",
+            "invalid AST JSON: \"!\" is not a prefix operator",
+        ),
+        (
+            "int main() { return 12; }",
+            "\"12\"",
+            "\"abc\"",
+            "invalid AST JSON: invalid digit \"a\" in integer constant \"abc\"",
+        ),
+    ];
+    for (code, from, to, message) in &cases {
+        let error = program_from_json(&malformed(code, from, to)).unwrap_err();
+        println!("{}", error.message);
+        assert!(error.message == *message);
+    }
+}
+
+#[test]
+fn test_ast_json_run() {
+    // compile the AST read back from JSON
+    rexparser("--input=ast-json ./tests/resources/json/test_json.ast.json");
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg("llvm-link -S -o runnable.ll compiled.ll builtin.ll; llvm-as runnable.ll; lli runnable.bc")
+        .output()
+        .expect("failed to execute process");
+    println!("{:?}", output.status.to_string());
+    assert!(output.status.to_string() == "exit code: 3");
+}
@@ -61,6 +61,36 @@ fn test_float_exp() {
     run(filepath, "", "6");
 }
 
+#[test]
+fn test_numeric_literal() {
+    let filepath = "./tests/resources/test_numeric_literal.c";
+    run(filepath, "", "89");
+}
+
+#[test]
+fn test_long_double() {
+    let filepath = "./tests/resources/test_long_double.c";
+    run(filepath, "", "62");
+}
+
+#[test]
+fn test_escape() {
+    let filepath = "./tests/resources/test_escape.c";
+    run(filepath, "a\tb\\c\"dAAé\n0,25;x-y", "55");
+}
+
+#[test]
+fn test_keyword_boundary() {
+    let filepath = "./tests/resources/test_keyword_boundary.c";
+    run(filepath, "ok", "29");
+}
+
+#[test]
+fn test_unary_binary() {
+    let filepath = "./tests/resources/test_unary_binary.c";
+    run(filepath, "", "63");
+}
+
 #[test]
 fn test_int_declare() {
     let filepath = "./tests/resources/test_int_declare.c";
@@ -241,6 +271,12 @@ fn test_continue2() {
     run(filepath, "", "55");
 }
 
+#[test]
+fn test_function_prototype() {
+    let filepath = "./tests/resources/test_function_prototype.c";
+    run(filepath, "", "8");
+}
+
 #[test]
 fn test_function_return() {
     let filepath = "./tests/resources/test_function_return.c";
@@ -319,6 +355,48 @@ fn test_enum() {
     run(filepath, "", "71");
 }
 
+#[test]
+fn test_typedef() {
+    let filepath = "./tests/resources/test_typedef.c";
+    run(filepath, "", "80");
+}
+
+#[test]
+fn test_multiple_declarators() {
+    let filepath = "./tests/resources/test_multiple_declarators.c";
+    run(filepath, "", "36");
+}
+
+#[test]
+fn test_declarator() {
+    let filepath = "./tests/resources/test_declarator.c";
+    run(filepath, "", "24");
+}
+
+#[test]
+fn test_integer_types() {
+    let filepath = "./tests/resources/test_integer_types.c";
+    run(filepath, "", "59");
+}
+
+#[test]
+fn test_arithmetic_conversions() {
+    let filepath = "./tests/resources/test_arithmetic_conversions.c";
+    run(filepath, "", "81");
+}
+
+#[test]
+fn test_initializer() {
+    let filepath = "./tests/resources/test_initializer.c";
+    run(filepath, "", "45");
+}
+
+#[test]
+fn test_constant_size() {
+    let filepath = "./tests/resources/test_constant_size.c";
+    run(filepath, "", "15");
+}
+
 #[test]
 fn test_switch() {
     let filepath = "./tests/resources/test_switch.c";
@@ -337,12 +415,54 @@ fn test_ternary_exp() {
     run(filepath, "", "100");
 }
 
+#[test]
+fn test_string_bytes() {
+    let filepath = "./tests/resources/test_string_bytes.c";
+    run(filepath, "", "10");
+}
+
 #[test]
 fn test_extern() {
     let filepath = "./tests/resources/test_extern.c";
     run(filepath, "", "0");
 }
 
+#[test]
+fn test_define() {
+    let filepath = "./tests/resources/test_define.c";
+    run(filepath, "", "61");
+}
+
+#[test]
+fn test_function_macro() {
+    let filepath = "./tests/resources/test_function_macro.c";
+    run(filepath, "", "48");
+}
+
+#[test]
+fn test_include() {
+    let filepath = "./tests/resources/test_include.c";
+    run(filepath, "49", "11");
+}
+
+#[test]
+fn test_conditional() {
+    let filepath = "./tests/resources/test_conditional.c";
+    run(filepath, "", "24");
+}
+
+#[test]
+fn test_predefined() {
+    let filepath = "./tests/resources/test_predefined.c";
+    run(filepath, "", "65");
+}
+
+#[test]
+fn test_comment() {
+    let filepath = "./tests/resources/test_comment.c";
+    run(filepath, "/* not a comment */ // 12", "8");
+}
+
 #[test]
 fn test_comp_int() {
     let filepath = "./tests/resources/builtin/test_comp_int.c";